@@ -0,0 +1,366 @@
+//! Chunking of the imported rows into `SetRow` instructions and tracking of the import progress
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::path::Path;
+
+use solcery_db_program::instruction::{ColumnId, Data, SegmentId, SetRowParams};
+
+use crate::table::RowDump;
+
+/// Group of `SetRow` instructions, which should be sent in one transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub instructions: Vec<SetRowParams>,
+    /// Number of rows, which are completely imported after this chunk
+    pub completed_rows: usize,
+}
+
+/// Import progress, saved after each successful transaction
+///
+/// The progress is bound to the source file and the target DB, so an interrupted import is never
+/// resumed with a changed file or into another table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Size of the source file in bytes
+    pub source_size: u64,
+    /// 64-bit FNV-1a hash of the source file
+    pub source_hash: u64,
+    /// Pubkey of the account with the target DB header segment
+    pub db_account: String,
+    /// Id of the target DB header segment
+    pub db_segment: u32,
+    /// Number of rows from the beginning of the file, which were already imported
+    pub completed_rows: usize,
+}
+
+impl Progress {
+    /// Progress of a new import of the `source` file content into the `db`
+    pub fn new(source: &[u8], db: SegmentId) -> Self {
+        Self {
+            source_size: source.len() as u64,
+            source_hash: fnv_hash(source),
+            db_account: db.pubkey.to_string(),
+            db_segment: db.id,
+            completed_rows: 0,
+        }
+    }
+
+    /// Loads progress of the import of the `source` into the `db` from the `path`, returns new
+    /// progress if the file does not exist.
+    ///
+    /// Returns an [`ErrorKind::InvalidData`] error if the saved progress belongs to another source
+    /// file or DB.
+    pub fn load(path: &Path, source: &[u8], db: SegmentId) -> io::Result<Self> {
+        let new = Self::new(source, db);
+
+        let saved: Self = match File::open(path) {
+            Ok(file) => serde_json::from_reader(file).map_err(io::Error::from)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(new),
+            Err(err) => return Err(err),
+        };
+
+        if (saved.source_size, saved.source_hash) != (new.source_size, new.source_hash) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the progress file {} belongs to another source file, remove it to start over",
+                    path.display()
+                ),
+            ));
+        }
+
+        if (&saved.db_account, saved.db_segment) != (&new.db_account, new.db_segment) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the progress file {} was saved for the DB in {} {}, remove it to start over",
+                    path.display(),
+                    saved.db_account,
+                    saved.db_segment
+                ),
+            ));
+        }
+
+        Ok(saved)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer(file, self).map_err(io::Error::from)
+    }
+}
+
+/// 64-bit FNV-1a hash, it does not depend on the Rust version, unlike the std hashers
+fn fnv_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Converts `rows` to `SetRow` instructions for the `db` and groups them into [`Chunk`]s.
+///
+/// `fits` checks if the given instructions fit in one transaction. Rows are never split between
+/// chunks, unless a row does not fit in a transaction on its own. In this case, the row is split
+/// into several `SetRow` instructions, each setting only some of the columns. `SetRow` overwrites
+/// values, so resending a partially imported row is harmless.
+///
+/// Rows before `skip_rows` are not included.
+pub fn chunk_rows<F>(
+    db: SegmentId,
+    rows: Vec<RowDump>,
+    columns: &BTreeMap<String, ColumnId>,
+    skip_rows: usize,
+    fits: F,
+) -> io::Result<Vec<Chunk>>
+where
+    F: Fn(&[SetRowParams]) -> bool,
+{
+    let set_row = |key: &Data, row: Vec<(ColumnId, Data)>| SetRowParams {
+        db,
+        key: key.clone(),
+        row,
+        is_initialized: true,
+    };
+
+    let total_rows = rows.len();
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+
+    for (position, row) in rows.into_iter().enumerate().skip(skip_rows) {
+        let values = row
+            .values
+            .into_iter()
+            .map(|(name, value)| match columns.get(&name) {
+                Some(&id) => Ok((id, value)),
+                None => Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("there is no column \"{name}\" in the DB"),
+                )),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if values.is_empty() {
+            continue;
+        }
+
+        let instruction = set_row(&row.key, values.clone());
+        if fits(&[instruction.clone()]) {
+            push_instruction(&mut chunks, &mut current, instruction, position, &fits);
+            continue;
+        }
+
+        // The row is too large, so it is imported column by column
+        for value in values {
+            let instruction = set_row(&row.key, vec![value]);
+            if !fits(&[instruction.clone()]) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "value of the row {:?} does not fit in a transaction",
+                        row.key
+                    ),
+                ));
+            }
+
+            push_instruction(&mut chunks, &mut current, instruction, position, &fits);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(Chunk {
+            instructions: current,
+            completed_rows: total_rows,
+        });
+    }
+
+    Ok(chunks)
+}
+
+/// Appends `instruction` to the `current` chunk, if it does not fit, the `current` chunk is
+/// finished and a new one is started
+fn push_instruction<F>(
+    chunks: &mut Vec<Chunk>,
+    current: &mut Vec<SetRowParams>,
+    instruction: SetRowParams,
+    completed_rows: usize,
+    fits: &F,
+) where
+    F: Fn(&[SetRowParams]) -> bool,
+{
+    current.push(instruction);
+
+    if current.len() > 1 && !fits(current) {
+        // `current` is not empty, as we've just pushed an instruction in it
+        let instruction = current.pop().unwrap();
+        chunks.push(Chunk {
+            instructions: mem::take(current),
+            completed_rows,
+        });
+        current.push(instruction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    fn segment() -> SegmentId {
+        SegmentId {
+            pubkey: Pubkey::new_unique(),
+            id: 0,
+        }
+    }
+
+    fn columns() -> BTreeMap<String, ColumnId> {
+        ["a", "b", "c"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| (name.to_string(), ColumnId::new(id as u32)))
+            .collect()
+    }
+
+    fn row(key: i32, values: &[(&str, i32)]) -> RowDump {
+        RowDump {
+            key: Data::Int(key),
+            values: values
+                .iter()
+                .map(|&(name, value)| (name.to_string(), Data::Int(value)))
+                .collect(),
+        }
+    }
+
+    fn rows() -> Vec<RowDump> {
+        vec![
+            row(1, &[("a", 1)]),
+            row(2, &[("a", 2), ("b", 2)]),
+            row(3, &[("a", 3), ("b", 3), ("c", 3)]),
+            row(4, &[]),
+            row(5, &[("b", 5)]),
+        ]
+    }
+
+    /// Transaction fits at most two values
+    fn fits(instructions: &[SetRowParams]) -> bool {
+        instructions
+            .iter()
+            .map(|instruction| instruction.row.len())
+            .sum::<usize>()
+            <= 2
+    }
+
+    #[test]
+    fn chunks() {
+        let db = segment();
+        let chunks = chunk_rows(db, rows(), &columns(), 0, fits).unwrap();
+
+        let keys: Vec<Vec<(Data, usize)>> = chunks
+            .iter()
+            .map(|chunk| {
+                chunk
+                    .instructions
+                    .iter()
+                    .map(|instruction| (instruction.key.clone(), instruction.row.len()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                vec![(Data::Int(1), 1)],
+                vec![(Data::Int(2), 2)],
+                // The third row does not fit in a transaction, so it is split by columns
+                vec![(Data::Int(3), 1), (Data::Int(3), 1)],
+                vec![(Data::Int(3), 1), (Data::Int(5), 1)],
+            ]
+        );
+
+        let completed_rows: Vec<usize> = chunks.iter().map(|chunk| chunk.completed_rows).collect();
+        assert_eq!(completed_rows, vec![1, 2, 2, 5]);
+
+        assert!(chunks
+            .iter()
+            .flat_map(|chunk| chunk.instructions.iter())
+            .all(|instruction| instruction.db == db));
+    }
+
+    #[test]
+    fn resume() {
+        let db = segment();
+        let chunks = chunk_rows(db, rows(), &columns(), 0, fits).unwrap();
+
+        // Import was interrupted after each of the chunks
+        for sent in 1..chunks.len() {
+            let skip_rows = chunks[sent - 1].completed_rows;
+            let resumed = chunk_rows(db, rows(), &columns(), skip_rows, fits).unwrap();
+
+            let remaining: Vec<SetRowParams> = chunks[sent..]
+                .iter()
+                .flat_map(|chunk| chunk.instructions.clone())
+                .collect();
+            let resumed: Vec<SetRowParams> = resumed
+                .into_iter()
+                .flat_map(|chunk| chunk.instructions)
+                .collect();
+
+            // Partially imported rows are sent again, all the other rows are sent once
+            assert!(resumed.ends_with(&remaining));
+            assert!(resumed[..resumed.len() - remaining.len()]
+                .iter()
+                .all(|instruction| instruction.key == Data::Int(3)));
+        }
+
+        let all_rows = chunk_rows(db, rows(), &columns(), rows().len(), fits).unwrap();
+        assert_eq!(all_rows, Vec::new());
+    }
+
+    #[test]
+    fn wrong_rows() {
+        let db = segment();
+
+        let err = chunk_rows(db, vec![row(1, &[("d", 1)])], &columns(), 0, fits).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = chunk_rows(db, rows(), &columns(), 0, |_| false).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn progress() {
+        let path = std::env::temp_dir().join(format!("db-cli-progress-{}", std::process::id()));
+        let db = segment();
+        let source = b"[1, 2, 3]";
+
+        let progress = Progress::load(&path, source, db).unwrap();
+        assert_eq!(progress, Progress::new(source, db));
+        assert_eq!(progress.completed_rows, 0);
+
+        let progress = Progress {
+            completed_rows: 3,
+            ..progress
+        };
+        progress.save(&path).unwrap();
+        assert_eq!(Progress::load(&path, source, db).unwrap(), progress);
+
+        // The source file was changed
+        let err = Progress::load(&path, b"[1, 2, 4]", db).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = Progress::load(&path, b"[1, 2, 3, 4]", db).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // The rows are imported into another DB
+        let err = Progress::load(&path, source, segment()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let other_segment = SegmentId { id: 1, ..db };
+        let err = Progress::load(&path, source, other_segment).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
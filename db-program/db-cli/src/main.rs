@@ -2,23 +2,34 @@ use clap::{Parser, Subcommand};
 use shellexpand::tilde;
 use solana_client::rpc_client::RpcClient;
 use solana_program::{
-    instruction::Instruction as SolanaInstruction, program_pack::Pack, pubkey::Pubkey,
-    system_instruction::create_account, system_program::ID as SystemID,
+    account_info::AccountInfo, instruction::Instruction as SolanaInstruction, program_pack::Pack,
+    pubkey::Pubkey, system_instruction::create_account, system_program::ID as SystemID,
 };
 use solana_sdk::{
-    instruction::AccountMeta, signature::Signer, signer::keypair::Keypair,
-    sysvar::rent::ID as RentSysvar, transaction::Transaction,
+    account::Account, instruction::AccountMeta, packet::PACKET_DATA_SIZE, signature::Signer,
+    signer::keypair::Keypair, sysvar::rent::ID as RentSysvar, transaction::Transaction,
 };
 use spl_token::{state::Account as TokenAccount, ID as TokenID};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
 
-use std::fs::File;
+use std::fs::{self, File};
 
+use account_fs::FS;
+use solcery_db::DB;
 use solcery_db_program::{
     instruction::*,
     state::{GLOBAL_STATE_SEED, MINT_SEED},
 };
 
+mod import;
+mod table;
+
+use import::{chunk_rows, Progress};
+use table::{read_rows, Format, TableDump};
+
 #[derive(Debug, Parser)]
 #[command(name = "db-client")]
 #[command(about = "Solcery DB-program management utility", long_about = None, version = {clap::crate_version!()})]
@@ -90,6 +101,90 @@ enum Commands {
         #[arg(value_name = "LAMPORTS", default_value_t = 1_000_000_000, short)]
         amount: u64,
     },
+    /// Export all rows of the DB to JSON or CSV file
+    #[command(arg_required_else_help = true)]
+    Export {
+        #[arg(required = true)]
+        #[arg(value_name = "PUBKEY")]
+        /// Pubkey of the db-program
+        program_id: String,
+        #[arg(required = true)]
+        #[arg(value_name = "PUBKEY")]
+        /// Pubkey of the account with the DB header segment
+        db_account: String,
+        #[arg(required = true)]
+        #[arg(value_name = "ID")]
+        /// Id of the DB header segment
+        db_segment: u32,
+        #[arg(required = true)]
+        #[arg(value_name = "PATH")]
+        /// Path to the output file
+        output: PathBuf,
+        /// Pubkeys of all the FS accounts, used by the DB
+        #[arg(value_name = "PUBKEY", long = "fs", required = true)]
+        fs_accounts: Vec<String>,
+        /// File format, by default it is derived from the file extension
+        #[arg(value_enum, short)]
+        format: Option<Format>,
+        #[arg(
+            value_name = "URL",
+            default_value_t = {"https://api.devnet.solana.com".to_string()},
+            short,
+        )]
+        /// URL of solana network
+        network: String,
+    },
+    /// Import rows from JSON or CSV file, created by the `export` command
+    ///
+    /// Rows are sent in `SetRow` instructions, packed into transactions. After each transaction
+    /// the progress is saved, so the interrupted import can be resumed by running the same
+    /// command again. The progress is refused, if the input file or the DB segment was changed.
+    #[command(arg_required_else_help = true)]
+    Import {
+        #[arg(value_name = "PATH")]
+        #[arg(required = true)]
+        /// Path to access token keypair
+        token: String,
+        #[arg(required = true)]
+        #[arg(value_name = "PUBKEY")]
+        /// Pubkey of the db-program
+        program_id: String,
+        #[arg(required = true)]
+        #[arg(value_name = "PUBKEY")]
+        /// Pubkey of the account with the DB header segment
+        db_account: String,
+        #[arg(required = true)]
+        #[arg(value_name = "ID")]
+        /// Id of the DB header segment
+        db_segment: u32,
+        #[arg(required = true)]
+        #[arg(value_name = "PATH")]
+        /// Path to the input file
+        input: PathBuf,
+        /// Pubkeys of all the FS accounts, used by the DB
+        #[arg(value_name = "PUBKEY", long = "fs", required = true)]
+        fs_accounts: Vec<String>,
+        /// File format, by default it is derived from the file extension
+        #[arg(value_enum, short)]
+        format: Option<Format>,
+        /// Path to the progress file, by default it is the input path with `.progress` extension
+        #[arg(value_name = "PATH", long)]
+        progress: Option<PathBuf>,
+        #[arg(
+            value_name = "URL",
+            default_value_t = {"https://api.devnet.solana.com".to_string()},
+            short,
+        )]
+        /// URL of solana network
+        network: String,
+        /// Path ot keypair of the funding account
+        #[arg(
+            value_name = "PATH",
+            default_value_t = {"~/.config/solana/id.json".to_string()},
+            short,
+        )]
+        payer: String,
+    },
 }
 fn main() -> std::io::Result<()> {
     let args = Cli::parse();
@@ -283,7 +378,181 @@ fn main() -> std::io::Result<()> {
 
             println!("Successfuly minted new access token: {}", signature);
 
+            Ok(())
+        }
+        Commands::Export {
+            program_id,
+            db_account,
+            db_segment,
+            output,
+            fs_accounts,
+            format,
+            network,
+        } => {
+            let program_id = Pubkey::from_str(&program_id).unwrap();
+            let segment = SegmentId {
+                pubkey: Pubkey::from_str(&db_account).unwrap(),
+                id: db_segment,
+            };
+
+            let client = RpcClient::new(network);
+            let mut accounts = fetch_accounts(&client, &fs_accounts);
+            let account_infos: Vec<AccountInfo> =
+                accounts.iter_mut().map(AccountInfo::from).collect();
+
+            let fs = FS::from_account_iter(&program_id, &mut account_infos.iter())
+                .expect("Failed to read FS accounts");
+            let db =
+                DB::from_segment(Rc::new(RefCell::new(fs)), segment).expect("Failed to open DB");
+
+            let dump = TableDump::from_db(&db).expect("Failed to read DB rows");
+
+            let format = format.unwrap_or_else(|| Format::from_path(&output));
+            dump.write(File::create(&output)?, format)?;
+
+            println!(
+                "Exported {} rows of \"{}\" to {}",
+                dump.rows.len(),
+                dump.table_name,
+                output.display()
+            );
+
+            Ok(())
+        }
+        Commands::Import {
+            token,
+            program_id,
+            db_account,
+            db_segment,
+            input,
+            fs_accounts,
+            format,
+            progress,
+            network,
+            payer,
+        } => {
+            let admin = read_keypair(&payer)?;
+            println!("Using funding pubkey: {}", admin.pubkey());
+
+            let token_key = read_keypair(&token)?;
+            println!("Using token pubkey: {}", token_key.pubkey());
+
+            let program_id = Pubkey::from_str(&program_id).unwrap();
+            let segment = SegmentId {
+                pubkey: Pubkey::from_str(&db_account).unwrap(),
+                id: db_segment,
+            };
+
+            let format = format.unwrap_or_else(|| Format::from_path(&input));
+            let source = fs::read(&input)?;
+            let rows = read_rows(source.as_slice(), format)?;
+
+            let progress_path = progress.unwrap_or_else(|| input.with_extension("progress"));
+            let mut progress = Progress::load(&progress_path, &source, segment)?;
+            if progress.completed_rows > 0 {
+                println!(
+                    "Resuming import after {} rows, progress file: {}",
+                    progress.completed_rows,
+                    progress_path.display()
+                );
+            }
+
+            let client = RpcClient::new(network);
+
+            let columns = {
+                let mut accounts = fetch_accounts(&client, &fs_accounts);
+                let account_infos: Vec<AccountInfo> =
+                    accounts.iter_mut().map(AccountInfo::from).collect();
+
+                let fs = FS::from_account_iter(&program_id, &mut account_infos.iter())
+                    .expect("Failed to read FS accounts");
+                let db = DB::from_segment(Rc::new(RefCell::new(fs)), segment)
+                    .expect("Failed to open DB");
+
+                db.column_ids()
+            };
+
+            let (global_state_id, _) =
+                Pubkey::find_program_address(&[GLOBAL_STATE_SEED], &program_id);
+
+            let mut account_metas = vec![
+                AccountMeta::new_readonly(global_state_id, false),
+                AccountMeta::new_readonly(token_key.pubkey(), true),
+            ];
+            for account in &fs_accounts {
+                account_metas.push(AccountMeta::new(Pubkey::from_str(account).unwrap(), false));
+            }
+
+            let make_transaction = |chunk: &[SetRowParams]| {
+                let instructions: Vec<_> = chunk
+                    .iter()
+                    .map(|params| {
+                        SolanaInstruction::new_with_borsh(
+                            program_id,
+                            &DBInstruction::SetRow(params.clone()),
+                            account_metas.clone(),
+                        )
+                    })
+                    .collect();
+
+                Transaction::new_with_payer(&instructions, Some(&admin.pubkey()))
+            };
+
+            let fits = |chunk: &[SetRowParams]| {
+                bincode::serialized_size(&make_transaction(chunk))
+                    .map_or(false, |size| size as usize <= PACKET_DATA_SIZE)
+            };
+
+            let chunks = chunk_rows(segment, rows, &columns, progress.completed_rows, fits)?;
+
+            for (number, chunk) in chunks.iter().enumerate() {
+                let mut transaction = make_transaction(&chunk.instructions);
+
+                let recent_blockhash = client.get_latest_blockhash().unwrap();
+                transaction.sign(&[&admin, &token_key], recent_blockhash);
+
+                let signature = client
+                    .send_and_confirm_transaction(&transaction)
+                    .expect("Failed to send transaction, run the same command to resume import");
+
+                progress.completed_rows = chunk.completed_rows;
+                progress.save(&progress_path)?;
+
+                println!("Sent chunk {}/{}: {}", number + 1, chunks.len(), signature);
+            }
+
+            if progress_path.exists() {
+                fs::remove_file(&progress_path)?;
+            }
+
+            println!("Successfuly imported {}", input.display());
+
             Ok(())
         }
     }
 }
+
+fn read_keypair(path: &str) -> std::io::Result<Keypair> {
+    let keypair_path: String = tilde(path).into_owned();
+
+    println!("Reading keypair from {}", &keypair_path);
+
+    let keypair_file = File::open(keypair_path)?;
+
+    let keypair_array: Vec<u8> = serde_json::from_reader(keypair_file)?;
+
+    Ok(Keypair::from_bytes(&keypair_array).expect("Failed to parse keypair file"))
+}
+
+fn fetch_accounts(client: &RpcClient, pubkeys: &[String]) -> Vec<(Pubkey, Account)> {
+    pubkeys
+        .iter()
+        .map(|pubkey| {
+            let pubkey = Pubkey::from_str(pubkey).unwrap();
+            let account = client
+                .get_account(&pubkey)
+                .expect("Failed to fetch FS account");
+            (pubkey, account)
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+//! Off-chain export and import of DB tables in JSON and CSV formats
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use solcery_db::{ColumnId, ColumnType, Data, DataType, Error as DBError, DB};
+
+/// Name of the primary key column in CSV files
+const KEY_COLUMN: &str = "key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// Guesses format from the file extension, JSON is used by default
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Content of the table, suitable for serialization
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableDump {
+    pub table_name: String,
+    pub primary_key_type: DataType,
+    pub columns: Vec<ColumnDump>,
+    pub rows: Vec<RowDump>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnDump {
+    pub name: String,
+    pub data_type: DataType,
+    pub column_type: ColumnType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowDump {
+    pub key: Data,
+    /// Values of the row by column names, missing values are omitted
+    pub values: BTreeMap<String, Data>,
+}
+
+impl TableDump {
+    /// Reads all the rows of the `db`
+    ///
+    /// Blob columns are skipped, as their values are handles of the segments in the same FS.
+    pub fn from_db(db: &DB) -> Result<Self, DBError> {
+        let columns: Vec<_> = db
+            .columns()
+            .into_iter()
+            .filter(|column| column.data_type != DataType::Blob)
+            .collect();
+
+        let names: BTreeMap<ColumnId, String> = columns
+            .iter()
+            .map(|column| (column.id, column.name.clone()))
+            .collect();
+
+        let rows = db
+            .select()
+            .rows()?
+            .into_iter()
+            .map(|(key, row)| RowDump {
+                key,
+                values: row
+                    .into_iter()
+                    .filter_map(|(id, value)| Some((names.get(&id)?.clone(), value?)))
+                    .collect(),
+            })
+            .filter(|row| !row.values.is_empty())
+            .collect();
+
+        Ok(Self {
+            table_name: db.table_name(),
+            primary_key_type: db.primary_key_type(),
+            columns: columns
+                .into_iter()
+                .map(|column| ColumnDump {
+                    name: column.name,
+                    data_type: column.data_type,
+                    column_type: column.column_type,
+                })
+                .collect(),
+            rows,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Json => serde_json::to_writer_pretty(writer, self).map_err(io::Error::from),
+            Format::Csv => self.write_csv(writer),
+        }
+    }
+
+    /// Writes rows in CSV format
+    ///
+    /// The first column contains primary keys, other columns are named after the table columns.
+    /// Each cell is a JSON-serialized [`Data`], missing values are left empty. Table name and
+    /// column types are not stored.
+    fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+
+        let mut header = vec![KEY_COLUMN.to_string()];
+        header.extend(self.columns.iter().map(|column| column.name.clone()));
+        writer.write_record(&header)?;
+
+        for row in &self.rows {
+            let mut record = vec![serde_json::to_string(&row.key)?];
+            for column in &self.columns {
+                let cell = match row.values.get(&column.name) {
+                    Some(value) => serde_json::to_string(value)?,
+                    None => String::new(),
+                };
+                record.push(cell);
+            }
+            writer.write_record(&record)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Reads rows, written by [`TableDump::write`]
+pub fn read_rows<R: Read>(reader: R, format: Format) -> io::Result<Vec<RowDump>> {
+    match format {
+        Format::Json => serde_json::from_reader::<_, TableDump>(reader)
+            .map(|dump| dump.rows)
+            .map_err(io::Error::from),
+        Format::Csv => read_csv_rows(reader),
+    }
+}
+
+fn read_csv_rows<R: Read>(reader: R) -> io::Result<Vec<RowDump>> {
+    let mut reader = csv::Reader::from_reader(reader);
+
+    let header = reader.headers()?.clone();
+    if header.get(0) != Some(KEY_COLUMN) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the first CSV column should be named \"{KEY_COLUMN}\""),
+        ));
+    }
+    let names: Vec<&str> = header.iter().skip(1).collect();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let mut cells = record.iter();
+
+        // csv::Reader checks that all the records have the same length as the header
+        let key = serde_json::from_str(cells.next().unwrap_or_default())?;
+
+        let mut values = BTreeMap::new();
+        for (&name, cell) in names.iter().zip(cells) {
+            if !cell.is_empty() {
+                values.insert(name.to_string(), serde_json::from_str(cell)?);
+            }
+        }
+
+        rows.push(RowDump { key, values });
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solcery_db::ShortString;
+
+    fn dump() -> TableDump {
+        let name = |name: &str| Data::ShortString(ShortString::try_from(name).unwrap());
+
+        TableDump {
+            table_name: String::from("Persons"),
+            primary_key_type: DataType::Int,
+            columns: vec![
+                ColumnDump {
+                    name: String::from("Name"),
+                    data_type: DataType::ShortString,
+                    column_type: ColumnType::OneToOne,
+                },
+                ColumnDump {
+                    name: String::from("Age"),
+                    data_type: DataType::Int,
+                    column_type: ColumnType::RBTree,
+                },
+            ],
+            rows: vec![
+                RowDump {
+                    key: Data::Int(0),
+                    values: BTreeMap::from([
+                        (String::from("Name"), name("Alice, \"Al\"")),
+                        (String::from("Age"), Data::Int(22)),
+                    ]),
+                },
+                RowDump {
+                    key: Data::Int(1),
+                    values: BTreeMap::from([(String::from("Name"), name("Bob"))]),
+                },
+                RowDump {
+                    key: Data::Int(2),
+                    values: BTreeMap::from([(String::from("Age"), Data::Int(-3))]),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let dump = dump();
+
+        let mut bytes = Vec::new();
+        dump.write(&mut bytes, Format::Json).unwrap();
+
+        let read: TableDump = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(read, dump);
+        assert_eq!(
+            read_rows(bytes.as_slice(), Format::Json).unwrap(),
+            dump.rows
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let dump = dump();
+
+        let mut bytes = Vec::new();
+        dump.write(&mut bytes, Format::Csv).unwrap();
+
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert_eq!(text.lines().next(), Some("key,Name,Age"));
+
+        assert_eq!(read_rows(bytes.as_slice(), Format::Csv).unwrap(), dump.rows);
+    }
+
+    #[test]
+    fn csv_without_key_column() {
+        let csv = "id,Name\n{\"Int\":0},\n";
+
+        let err = read_rows(csv.as_bytes(), Format::Csv).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(Format::from_path(Path::new("table.CSV")), Format::Csv);
+        assert_eq!(Format::from_path(Path::new("table.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("table")), Format::Json);
+    }
+}
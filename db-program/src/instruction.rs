@@ -1,7 +1,10 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
 pub use account_fs::SegmentId;
-pub use solcery_db::{ColumnId, ColumnParams, Data, DataType};
+pub use solcery_db::{
+    Agg, ColumnId, ColumnParams, CompositeKey, Cursor, Data, DataType, KeyRange, OnDelete, Page,
+    TableOptions,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
 pub enum DBInstruction {
@@ -10,7 +13,11 @@ pub enum DBInstruction {
     SetRow(SetRowParams),
     DeleteRow(DeleteRowParams),
     DeleteRowSecondary(DeleteRowSecondaryParams),
-    /// Create a new database
+    /// Create a new database with the given columns
+    ///
+    /// [`SegmentId`] of the DB and [`ColumnId`]s of the columns in the order of
+    /// [`CreateDBParams::columns`] are returned as a borsh-serialized `(SegmentId, Vec<ColumnId>)`
+    /// in the program return data.
     ///
     /// Accounts expected:
     ///
@@ -34,6 +41,183 @@ pub enum DBInstruction {
     Bootstrap(BootstrapParams),
     AddColumn(AddColumnParams),
     RemoveColumn(RemoveColumnParams),
+    RenameColumn(RenameColumnParams),
+    /// Convert all the values of the column to the new [`DataType`]
+    ///
+    /// Fails without changing the column, if any value can not be converted without loss.
+    AlterColumnType(AlterColumnTypeParams),
+    /// Declare a column as a foreign key to the other DB in the same FS
+    ///
+    /// Both DBs must be created with [`TableOptions`].
+    AddForeignKey(AddForeignKeyParams),
+    RemoveForeignKey(RemoveForeignKeyParams),
+    /// Store a variable-length payload in the Blob column
+    SetBlob(SetBlobParams),
+    /// Compute an aggregate function over the column
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized `Option<Data>` in the program return data.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    Aggregate(AggregateParams),
+    /// Designate a timestamp column as the expiry column of the DB
+    ///
+    /// The DB must be created with [`TableOptions`].
+    SetExpiryColumn(SetExpiryColumnParams),
+    /// Delete a bounded number of expired rows, using the current time from the `Clock` sysvar
+    ///
+    /// No Access Token is needed, so anyone can crank it. The FS accounts must be initialized.
+    /// The numbers of deleted rows and of skipped expired rows, which are still referenced by
+    /// other tables, are returned as a borsh-serialized `(u32, u32)` in the program return data.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[writable]` FS accounts
+    PurgeExpired(PurgeExpiredParams),
+    /// Create a new catalog of tables
+    ///
+    /// [`SegmentId`] of the catalog is returned as borsh-serialized program return data.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` Global DB-program state account
+    /// 1. `[signer]` Access Token account
+    /// 2. `[writable]` FS accounts
+    CreateCatalog(CreateCatalogParams),
+    /// Create a new table in the catalog
+    ///
+    /// [`SegmentId`] of the table is returned as borsh-serialized program return data.
+    CreateTable(CreateTableParams),
+    /// Delete the table and remove it from the catalog
+    DropTable(DropTableParams),
+    /// Find the table in the catalog by its name
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. [`SegmentId`] of the
+    /// table is returned as borsh-serialized program return data, it can be used in all the other
+    /// instructions.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    GetTable(GetTableParams),
+    /// Read a page of `primary_key - value` pairs of the column, starting after the cursor
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized [`Page`] in the program return data. The page is shortened,
+    /// if it does not fit in the return data, use [`Page::next`] to read the rest.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    Scan(ScanParams),
+    /// Add or remove the unique constraint on the column
+    ///
+    /// The DB must be created with [`TableOptions`].
+    SetUnique(SetUniqueParams),
+    /// Apply a sequence of [`DBOperation`]s to the DB, opening the FS only once
+    ///
+    /// Operations are applied in order. If any of them fails, the whole instruction fails, so
+    /// none of the changes are persisted.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` Global DB-program state account
+    /// 1. `[signer]` Access Token account
+    /// 2. `[writable]` FS accounts
+    Batch(BatchParams),
+    /// Read the value of the column by its primary key
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized `Option<Data>` in the program return data.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    GetValue(GetValueParams),
+    /// Read the value of the column by the secondary key, located in `key_column`
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized `Option<Data>` in the program return data.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    GetValueSecondary(GetValueSecondaryParams),
+    /// Read all the values of the row by its primary key
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized `BTreeMap<ColumnId, Option<Data>>` in the program return
+    /// data. The instruction fails, if the row does not fit in the return data, use [`Scan`] to
+    /// read the columns one by one in this case.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    ///
+    /// [`Scan`]: DBInstruction::Scan
+    GetRow(GetRowParams),
+    /// Allocate a zero-filled buffer for a blob payload
+    ///
+    /// Payloads, which do not fit in a single [`SetBlob`] instruction, are written to the buffer
+    /// with [`WriteBlobChunk`] and then set in the column with [`FinalizeBlob`]. [`SegmentId`] of
+    /// the buffer is returned as borsh-serialized program return data.
+    ///
+    /// [`SetBlob`]: DBInstruction::SetBlob
+    /// [`WriteBlobChunk`]: DBInstruction::WriteBlobChunk
+    /// [`FinalizeBlob`]: DBInstruction::FinalizeBlob
+    CreateBlobBuffer(CreateBlobBufferParams),
+    /// Write a chunk of the payload to the blob buffer
+    WriteBlobChunk(WriteBlobChunkParams),
+    /// Set the payload of the blob buffer in the Blob column, the buffer can not be written after
+    /// that
+    FinalizeBlob(FinalizeBlobParams),
+    /// Deallocate the blob buffer, which is not finalized
+    DiscardBlobBuffer(DiscardBlobBufferParams),
+    /// Read a chunk of the blob payload
+    ///
+    /// This instruction does not modify the DB, so no Access Token is needed. The result is
+    /// returned as a borsh-serialized `Option<Vec<u8>>` in the program return data. The chunk is
+    /// empty if the offset is beyond the end of the payload.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[]` FS accounts
+    GetBlob(GetBlobParams),
+}
+
+/// Single modification of the DB, applied as a part of [`DBInstruction::Batch`]
+///
+/// The variants mirror the corresponding [`DBInstruction`]s.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub enum DBOperation {
+    SetValue {
+        column: ColumnId,
+        key: Data,
+        value: Data,
+    },
+    SetValueSecondary {
+        key_column: ColumnId,
+        secondary_key: Data,
+        value_column: ColumnId,
+        value: Data,
+    },
+    SetRow {
+        key: Data,
+        row: Vec<(ColumnId, Data)>,
+    },
+    DeleteValue {
+        column: ColumnId,
+        key: Data,
+    },
+    DeleteRow {
+        key: Data,
+    },
+    DeleteRowSecondary {
+        key_column: ColumnId,
+        secondary_key: Data,
+    },
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
@@ -90,6 +274,7 @@ pub struct CreateDBParams {
     pub table_name: String,
     pub max_columns: u32,
     pub max_rows: u32,
+    pub options: TableOptions,
     /// Are all the FS accounts initialized
     pub is_initialized: bool,
 }
@@ -119,3 +304,203 @@ pub struct RemoveColumnParams {
     /// Are all the FS accounts initialized
     pub is_initialized: bool,
 }
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct RenameColumnParams {
+    pub db: SegmentId,
+    pub column_id: ColumnId,
+    pub name: String,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct AlterColumnTypeParams {
+    pub db: SegmentId,
+    pub column_id: ColumnId,
+    pub dtype: DataType,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct AddForeignKeyParams {
+    pub db: SegmentId,
+    pub column_id: ColumnId,
+    /// Segment of the referenced DB
+    pub table: SegmentId,
+    pub on_delete: OnDelete,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct RemoveForeignKeyParams {
+    pub db: SegmentId,
+    pub column_id: ColumnId,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct SetBlobParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    pub key: Data,
+    pub payload: Vec<u8>,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct CreateBlobBufferParams {
+    pub db: SegmentId,
+    /// Length of the payload
+    pub len: u32,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct WriteBlobChunkParams {
+    pub db: SegmentId,
+    pub buffer: SegmentId,
+    /// Offset of the chunk from the start of the payload
+    pub offset: u32,
+    pub chunk: Vec<u8>,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct FinalizeBlobParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    pub key: Data,
+    pub buffer: SegmentId,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct DiscardBlobBufferParams {
+    pub db: SegmentId,
+    pub buffer: SegmentId,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct GetBlobParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    pub key: Data,
+    /// Offset of the chunk from the start of the payload
+    pub offset: u32,
+    /// Maximal length of the chunk
+    pub len: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct SetExpiryColumnParams {
+    pub db: SegmentId,
+    /// `None` removes the expiry column
+    pub column: Option<ColumnId>,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct PurgeExpiredParams {
+    pub db: SegmentId,
+    /// Maximum number of rows to delete
+    pub limit: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct CreateCatalogParams {
+    pub max_tables: u32,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct CreateTableParams {
+    pub catalog: SegmentId,
+    pub table_name: String,
+    pub primary_key_type: DataType,
+    pub max_columns: u32,
+    pub max_rows: u32,
+    pub options: TableOptions,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct DropTableParams {
+    pub catalog: SegmentId,
+    pub table_name: String,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct GetTableParams {
+    pub catalog: SegmentId,
+    pub table_name: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct ScanParams {
+    pub db: SegmentId,
+    pub cursor: Cursor,
+    /// Maximum number of pairs in the page
+    pub limit: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct SetUniqueParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    /// `false` removes the unique constraint
+    pub unique: bool,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct BatchParams {
+    pub db: SegmentId,
+    pub operations: Vec<DBOperation>,
+    /// Are all the FS accounts initialized
+    pub is_initialized: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct GetValueParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    pub key: Data,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct GetValueSecondaryParams {
+    pub db: SegmentId,
+    pub key_column: ColumnId,
+    pub secondary_key: Data,
+    pub value_column: ColumnId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct GetRowParams {
+    pub db: SegmentId,
+    pub key: Data,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct AggregateParams {
+    pub db: SegmentId,
+    pub column: ColumnId,
+    pub agg: Agg,
+    pub range: KeyRange,
+}
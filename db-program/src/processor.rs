@@ -1,13 +1,14 @@
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data, MAX_RETURN_DATA},
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
     system_instruction, system_program,
+    sysvar::{clock::Clock, Sysvar},
 };
 use spl_token::{
     instruction as token_instruction, state::Account as TokenAccount, state::Mint, ID as TokenID,
@@ -15,8 +16,8 @@ use spl_token::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use account_fs::{SegmentId, FS};
-use solcery_db::{Error as DBError, DB};
+use account_fs::{FSReader, SegmentId, FS};
+use solcery_db::{Catalog, CatalogReader, DBReader, Error as DBError, DB};
 
 use super::instruction::*;
 
@@ -37,6 +38,31 @@ pub fn process_instruction_bytes(
     match instruction {
         DBInstruction::Bootstrap(params) => bootstrap(program_id, account_iter, params),
         DBInstruction::MintNewAccessToken => mint_new_token(program_id, account_iter),
+        DBInstruction::Aggregate(params) => {
+            process_aggregate(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::Scan(params) => {
+            process_scan(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::GetTable(params) => {
+            process_get_table(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::GetValue(params) => {
+            process_get_value(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::GetValueSecondary(params) => {
+            process_get_value_secondary(program_id, account_iter, params)
+                .map_err(ProgramError::from)
+        }
+        DBInstruction::GetRow(params) => {
+            process_get_row(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::GetBlob(params) => {
+            process_get_blob(program_id, account_iter, params).map_err(ProgramError::from)
+        }
+        DBInstruction::PurgeExpired(params) => {
+            process_purge_expired(program_id, account_iter, params)
+        }
         other_instruction => {
             check_token(program_id, account_iter)?;
 
@@ -80,6 +106,31 @@ where
         }
         AddColumn(params) => process_add_column(program_id, account_iter, params),
         RemoveColumn(params) => process_remove_column(program_id, account_iter, params),
+        RenameColumn(params) => process_rename_column(program_id, account_iter, params),
+        AlterColumnType(params) => process_alter_column_type(program_id, account_iter, params),
+        AddForeignKey(params) => process_add_foreign_key(program_id, account_iter, params),
+        RemoveForeignKey(params) => process_remove_foreign_key(program_id, account_iter, params),
+        SetBlob(params) => process_set_blob(program_id, account_iter, params),
+        Aggregate(_) => unreachable!("Aggregate instruction should be handled separately"),
+        SetExpiryColumn(params) => process_set_expiry_column(program_id, account_iter, params),
+        PurgeExpired(_) => unreachable!("PurgeExpired instruction should be handled separately"),
+        CreateCatalog(params) => process_create_catalog(program_id, account_iter, params),
+        CreateTable(params) => process_create_table(program_id, account_iter, params),
+        DropTable(params) => process_drop_table(program_id, account_iter, params),
+        GetTable(_) => unreachable!("GetTable instruction should be handled separately"),
+        Scan(_) => unreachable!("Scan instruction should be handled separately"),
+        SetUnique(params) => process_set_unique(program_id, account_iter, params),
+        Batch(params) => process_batch(program_id, account_iter, params),
+        GetValue(_) => unreachable!("GetValue instruction should be handled separately"),
+        GetValueSecondary(_) => {
+            unreachable!("GetValueSecondary instruction should be handled separately")
+        }
+        GetRow(_) => unreachable!("GetRow instruction should be handled separately"),
+        CreateBlobBuffer(params) => process_create_blob_buffer(program_id, account_iter, params),
+        WriteBlobChunk(params) => process_write_blob_chunk(program_id, account_iter, params),
+        FinalizeBlob(params) => process_finalize_blob(program_id, account_iter, params),
+        DiscardBlobBuffer(params) => process_discard_blob_buffer(program_id, account_iter, params),
+        GetBlob(_) => unreachable!("GetBlob instruction should be handled separately"),
     }
 }
 
@@ -368,14 +419,22 @@ where
 
     let fs_cell = Rc::new(RefCell::new(fs));
 
-    DB::init_in_segment(
+    let (mut db, segment) = DB::init_in_segment_with_options(
         fs_cell,
         &params.table_name,
         params.max_columns as usize,
         params.max_rows as usize,
         params.primary_key_type,
-    )
-    .map(|_| ())
+        params.options,
+    )?;
+
+    let column_ids = params
+        .columns
+        .iter()
+        .map(|column| db.add_column(column.name(), column.data_type(), column.is_secondary_key()))
+        .collect::<Result<Vec<ColumnId>, DBError>>()?;
+
+    set_result_return_data(&(segment, column_ids))
 }
 fn process_drop_db<'long: 'short, 'short, AccountIter>(
     program_id: &Pubkey,
@@ -414,6 +473,438 @@ where
     db.remove_column(params.column_id).map(|_| ())
 }
 
+fn process_rename_column<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: RenameColumnParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.rename_column(params.column_id, &params.name)
+}
+
+fn process_alter_column_type<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: AlterColumnTypeParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.alter_column_type(params.column_id, params.dtype, |value| {
+        value.convert(params.dtype)
+    })
+}
+
+fn process_add_foreign_key<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: AddForeignKeyParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.add_foreign_key(params.column_id, params.table, params.on_delete)
+}
+
+fn process_remove_foreign_key<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: RemoveForeignKeyParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.remove_foreign_key(params.column_id)
+}
+
+fn process_set_blob<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: SetBlobParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.set_blob(params.key, params.column, &params.payload)
+}
+
+fn process_create_blob_buffer<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: CreateBlobBufferParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    let buffer = db.create_blob_buffer(params.len as usize)?;
+
+    set_segment_return_data(buffer);
+    Ok(())
+}
+
+fn process_write_blob_chunk<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: WriteBlobChunkParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.write_blob_chunk(&params.buffer, params.offset as usize, &params.chunk)
+}
+
+fn process_finalize_blob<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: FinalizeBlobParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.finalize_blob(params.key, params.column, params.buffer)
+}
+
+fn process_discard_blob_buffer<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: DiscardBlobBufferParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.discard_blob_buffer(&params.buffer)
+}
+
+fn process_get_blob<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: GetBlobParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+    let chunk = db.blob_chunk(
+        params.key,
+        params.column,
+        params.offset as usize,
+        params.len as usize,
+    )?;
+
+    set_result_return_data(&chunk)
+}
+
+fn process_aggregate<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: AggregateParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+    let result = db.aggregate(params.column, params.agg, &params.range)?;
+
+    set_result_return_data(&result)
+}
+
+fn process_scan<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: ScanParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+
+    let mut page = db.scan_from(&params.cursor, params.limit as usize)?;
+    page.truncate_to_size(MAX_RETURN_DATA)?;
+
+    let result = page
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible");
+    set_return_data(&result);
+
+    Ok(())
+}
+
+fn process_get_value<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: GetValueParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+    let value = db.value(params.key, params.column)?;
+
+    set_result_return_data(&value)
+}
+
+fn process_get_value_secondary<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: GetValueSecondaryParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+    let value = db.value_secondary(params.key_column, params.secondary_key, params.value_column)?;
+
+    set_result_return_data(&value)
+}
+
+fn process_get_row<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: GetRowParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let db = prepare_reader(program_id, accounts_iter, params.db)?;
+    let row = db.row(params.key)?;
+
+    set_result_return_data(&row)
+}
+
+fn process_set_expiry_column<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: SetExpiryColumnParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    db.set_expiry_column(params.column)
+}
+
+fn process_set_unique<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: SetUniqueParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+    if params.unique {
+        db.make_unique(params.column)
+    } else {
+        db.drop_unique(params.column)
+    }
+}
+
+fn process_batch<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: BatchParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut db = prepare_db(program_id, accounts_iter, params.db, params.is_initialized)?;
+
+    // An error aborts the instruction, and the runtime discards all the changes of the accounts,
+    // so the operations, applied before the failed one, are reverted too
+    for operation in params.operations {
+        apply_operation(&mut db, operation)?;
+    }
+
+    Ok(())
+}
+
+fn apply_operation(db: &mut DB, operation: DBOperation) -> Result<(), DBError> {
+    match operation {
+        DBOperation::SetValue { column, key, value } => {
+            db.set_value(key, column, value).map(|_| ())
+        }
+        DBOperation::SetValueSecondary {
+            key_column,
+            secondary_key,
+            value_column,
+            value,
+        } => db
+            .set_value_secondary(key_column, secondary_key, value_column, value)
+            .map(|_| ()),
+        DBOperation::SetRow { key, row } => db.set_row(key, row).map(|_| ()),
+        DBOperation::DeleteValue { column, key } => db.delete_value(key, column).map(|_| ()),
+        DBOperation::DeleteRow { key } => db.delete_row(key),
+        DBOperation::DeleteRowSecondary {
+            key_column,
+            secondary_key,
+        } => db.delete_row_secondary(key_column, secondary_key),
+    }
+}
+
+fn process_purge_expired<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: PurgeExpiredParams,
+) -> ProgramResult
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let now = Clock::get()?.unix_timestamp;
+
+    // The instruction is permissionless, so it never initializes the accounts
+    let mut db = prepare_db(program_id, accounts_iter, params.db, true)?;
+    let report = db.purge_expired(now, params.limit as usize)?;
+
+    msg!(
+        "Purged {} expired rows, skipped {} referenced rows",
+        report.deleted,
+        report.skipped
+    );
+
+    let result = (report.deleted as u32, report.skipped as u32)
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible");
+    set_return_data(&result);
+
+    Ok(())
+}
+
+fn process_create_catalog<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: CreateCatalogParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let fs = if params.is_initialized {
+        FS::from_account_iter(program_id, accounts_iter)?
+    } else {
+        FS::from_uninit_account_iter(program_id, accounts_iter, INODE_TABLE_SIZE)?
+    };
+
+    let fs_cell = Rc::new(RefCell::new(fs));
+
+    let (_, segment) = Catalog::init_in_segment(fs_cell, params.max_tables as usize)?;
+
+    set_segment_return_data(segment);
+    Ok(())
+}
+
+fn process_create_table<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: CreateTableParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut catalog = prepare_catalog(
+        program_id,
+        accounts_iter,
+        params.catalog,
+        params.is_initialized,
+    )?;
+
+    // The table is not modified further, so it is dropped right away
+    let table = catalog.create_table(
+        &params.table_name,
+        params.max_columns as usize,
+        params.max_rows as usize,
+        params.primary_key_type,
+        params.options,
+    )?;
+    drop(table);
+
+    set_segment_return_data(catalog.table_segment(&params.table_name)?);
+    Ok(())
+}
+
+fn process_drop_table<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: DropTableParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let mut catalog = prepare_catalog(
+        program_id,
+        accounts_iter,
+        params.catalog,
+        params.is_initialized,
+    )?;
+    catalog.drop_table(&params.table_name)
+}
+
+fn process_get_table<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    accounts_iter: &mut AccountIter,
+    params: GetTableParams,
+) -> Result<(), DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let fs = Rc::new(FSReader::from_account_iter(program_id, accounts_iter)?);
+    let catalog = CatalogReader::from_segment(fs, params.catalog)?;
+
+    set_segment_return_data(catalog.table_segment(&params.table_name)?);
+    Ok(())
+}
+
+/// Sets the borsh-serialized `result` as the program return data, failing with
+/// [`DBError::ResultTooLarge`] instead of truncating it
+fn set_result_return_data<T: BorshSerialize>(result: &T) -> Result<(), DBError> {
+    let result = result
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible");
+
+    if result.len() > MAX_RETURN_DATA {
+        return Err(DBError::ResultTooLarge);
+    }
+
+    set_return_data(&result);
+    Ok(())
+}
+
+fn set_segment_return_data(segment: SegmentId) {
+    let result = segment
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible");
+    set_return_data(&result);
+}
+
+fn prepare_catalog<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    account_iter: &mut AccountIter,
+    segment: SegmentId,
+    is_initialized: bool,
+) -> Result<Catalog<'long, 'short>, DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let fs = if is_initialized {
+        FS::from_account_iter(program_id, account_iter)?
+    } else {
+        FS::from_uninit_account_iter(program_id, account_iter, INODE_TABLE_SIZE)?
+    };
+
+    let fs_cell = Rc::new(RefCell::new(fs));
+
+    Catalog::from_segment(fs_cell, segment)
+}
+
 fn prepare_db<'long: 'short, 'short, AccountIter>(
     program_id: &Pubkey,
     account_iter: &mut AccountIter,
@@ -434,6 +925,20 @@ where
     DB::from_segment(fs_cell, segment)
 }
 
+/// Opens the DB for reading, the accounts are borrowed immutably and never initialized
+fn prepare_reader<'long: 'short, 'short, AccountIter>(
+    program_id: &Pubkey,
+    account_iter: &mut AccountIter,
+    segment: SegmentId,
+) -> Result<DBReader<'long, 'short>, DBError>
+where
+    AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+{
+    let fs = FSReader::from_account_iter(program_id, account_iter)?;
+
+    DBReader::from_segment(Rc::new(fs), segment)
+}
+
 fn check_token<'long: 'short, 'short, AccountIter>(
     program_id: &Pubkey,
     account_iter: &mut AccountIter,
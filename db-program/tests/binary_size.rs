@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Size budget of the deployable program in bytes.
+///
+/// By default, the program data account reserves twice the size of the binary, so every 100 KiB
+/// of the binary costs about 1.4 SOL of rent at deployment. Column containers take the sizes of
+/// keys and values at runtime, so they are instantiated once and a new
+/// [`DataType`](solcery_db::DataType) should not change the size noticeably. If the budget is
+/// exceeded deliberately, raise it in the same change.
+const PROGRAM_SIZE_BUDGET: u64 = 600 * 1024;
+
+/// Checks the size of the program, built with `cargo build-sbf`.
+///
+/// The test is ignored by default, because the native build does not produce the program binary.
+/// Run it with `cargo test --test binary_size -- --ignored` after `cargo build-sbf`, the location
+/// of the binary can be overridden with `BPF_OUT_DIR`.
+#[test]
+#[ignore]
+fn binary_size() {
+    let out_dir = std::env::var("BPF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy"));
+    let binary = out_dir.join("solcery_db_program.so");
+
+    let size = fs::metadata(&binary)
+        .unwrap_or_else(|err| {
+            panic!(
+                "{} is not found ({}), build the program with `cargo build-sbf` first",
+                binary.display(),
+                err
+            )
+        })
+        .len();
+
+    println!("{}: {} bytes", binary.display(), size);
+    assert!(
+        size <= PROGRAM_SIZE_BUDGET,
+        "The program is {} bytes, which exceeds the budget of {} bytes",
+        size,
+        PROGRAM_SIZE_BUDGET
+    );
+}
@@ -1,13 +1,15 @@
+use borsh::BorshDeserialize;
 use pretty_assertions::assert_eq;
 use solana_program::{
     instruction::Instruction as SolanaInstruction, instruction::InstructionError,
-    program_pack::Pack, pubkey::Pubkey, system_instruction::create_account,
-    system_program::ID as SystemID,
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+    system_instruction::create_account, system_program::ID as SystemID,
 };
-use solana_program_test::{processor, tokio, ProgramTest};
+use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
 use solana_sdk::{
     account::{Account, AccountSharedData},
     account_info::AccountInfo,
+    hash::Hash,
     instruction::AccountMeta,
     program_option::COption,
     signature::Signer,
@@ -20,10 +22,11 @@ use spl_token::{
     ID as TokenID,
 };
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 use account_fs::FS;
-use solcery_db::{ShortString, DB};
+use solcery_db::{ColumnInfo, ColumnType, Error as DBError, LongString, ShortString, DB};
 use solcery_db_program::{
     entrypoint::process_instruction_bytes,
     instruction::*,
@@ -381,6 +384,7 @@ async fn create_db() {
         table_name: String::from("Test DB"),
         max_columns: 10,
         max_rows: 10,
+        options: TableOptions::default(),
         is_initialized: false,
     });
 
@@ -429,6 +433,125 @@ async fn create_db() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn create_db_with_columns() {
+    let ProgramEnvironment {
+        global_state: global_state_id,
+        program: program_key,
+        test: program,
+        token: token_key,
+        ..
+    } = prepare_environment();
+
+    let program_id = program_key.pubkey();
+    let token_id = token_key.pubkey();
+
+    let fs_account_key = Keypair::new();
+
+    let (mut banks_client, admin, recent_blockhash) = program.start().await;
+
+    let create_fs_account = create_account(
+        &admin.pubkey(),
+        &fs_account_key.pubkey(),
+        AMOUNT * 2,
+        1_000_000,
+        &program_id,
+    );
+
+    let db_instruction = DBInstruction::CreateDB(CreateDBParams {
+        primary_key_type: DataType::Int,
+        columns: vec![
+            ColumnParams::new("Name", DataType::ShortString, true),
+            ColumnParams::new("Age", DataType::Int, false),
+        ],
+        table_name: String::from("Test DB"),
+        max_columns: 10,
+        max_rows: 10,
+        options: TableOptions::default(),
+        is_initialized: false,
+    });
+
+    let instruction = SolanaInstruction::new_with_borsh(
+        program_id,
+        &db_instruction,
+        vec![
+            AccountMeta::new_readonly(global_state_id, false),
+            AccountMeta::new_readonly(token_id, true),
+            AccountMeta::new(fs_account_key.pubkey(), false),
+        ],
+    );
+
+    let mut token_transaction =
+        Transaction::new_with_payer(&[create_fs_account, instruction], Some(&admin.pubkey()));
+
+    token_transaction.sign(&[&admin, &token_key, &fs_account_key], recent_blockhash);
+
+    let result = banks_client
+        .process_transaction_with_metadata(token_transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    // SegmentId of the DB and ColumnIds of the created columns are returned
+    let return_data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .expect("CreateDB should set the return data");
+    assert_eq!(return_data.program_id, program_id);
+    assert_eq!(
+        <(SegmentId, Vec<ColumnId>)>::try_from_slice(&return_data.data).unwrap(),
+        (
+            SegmentId {
+                id: 0,
+                pubkey: fs_account_key.pubkey(),
+            },
+            vec![ColumnId::new(0), ColumnId::new(1)]
+        )
+    );
+
+    // Retrieving accounts
+    let fs_account = banks_client
+        .get_account(fs_account_key.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut account_internals = (fs_account_key.pubkey(), fs_account);
+    let fs_account = vec![AccountInfo::from(&mut account_internals)];
+
+    // Deserializing data
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut fs_account.iter()).unwrap(),
+    ));
+
+    let db = DB::from_segment(
+        fs,
+        SegmentId {
+            id: 0,
+            pubkey: fs_account_key.pubkey(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        db.columns(),
+        vec![
+            ColumnInfo {
+                id: ColumnId::new(0),
+                name: "Name".to_string(),
+                data_type: DataType::ShortString,
+                column_type: ColumnType::OneToOne,
+            },
+            ColumnInfo {
+                id: ColumnId::new(1),
+                name: "Age".to_string(),
+                data_type: DataType::Int,
+                column_type: ColumnType::RBTree,
+            },
+        ]
+    );
+}
+
 #[tokio::test]
 async fn add_column() {
     let ProgramEnvironment {
@@ -461,6 +584,7 @@ async fn add_column() {
         table_name: String::from("Test DB"),
         max_columns: 10,
         max_rows: 10,
+        options: TableOptions::default(),
         is_initialized: false,
     });
 
@@ -570,6 +694,7 @@ async fn add_value() {
         table_name: String::from("Test DB"),
         max_columns: 10,
         max_rows: 10,
+        options: TableOptions::default(),
         is_initialized: false,
     });
 
@@ -700,6 +825,7 @@ async fn add_secondary_key_column() {
         table_name: String::from("Test DB"),
         max_columns: 10,
         max_rows: 10,
+        options: TableOptions::default(),
         is_initialized: false,
     });
 
@@ -809,6 +935,7 @@ async fn add_value_by_secondary_key() {
         table_name: String::from("Test DB"),
         max_columns: 10,
         max_rows: 10,
+        options: TableOptions::default(),
         is_initialized: false,
     });
 
@@ -963,6 +1090,592 @@ async fn add_value_by_secondary_key() {
     assert_eq!(secondary_value, Some(Data::Int(324)));
 }
 
+#[tokio::test]
+async fn batch() {
+    let ProgramEnvironment {
+        global_state: global_state_id,
+        program: program_key,
+        test: program,
+        token: token_key,
+        ..
+    } = prepare_environment();
+
+    let program_id = program_key.pubkey();
+    let token_id = token_key.pubkey();
+
+    let fs_account_key = Keypair::new();
+
+    let (mut banks_client, admin, recent_blockhash) = program.start().await;
+
+    let segment = SegmentId {
+        id: 0,
+        pubkey: fs_account_key.pubkey(),
+    };
+
+    let create_fs_account = create_account(
+        &admin.pubkey(),
+        &fs_account_key.pubkey(),
+        AMOUNT * 2,
+        1_000_000,
+        &program_id,
+    );
+
+    let db_create = DBInstruction::CreateDB(CreateDBParams {
+        primary_key_type: DataType::Int,
+        columns: vec![],
+        table_name: String::from("Test DB"),
+        max_columns: 10,
+        max_rows: 10,
+        options: TableOptions::default(),
+        is_initialized: false,
+    });
+
+    let add_column = DBInstruction::AddColumn(AddColumnParams {
+        db: segment,
+        name: "Test column".to_string(),
+        dtype: DataType::Int,
+        is_secondary_key: false,
+        is_initialized: true,
+    });
+
+    let batch = DBInstruction::Batch(BatchParams {
+        db: segment,
+        operations: vec![
+            DBOperation::SetValue {
+                column: ColumnId::new(0),
+                key: Data::Int(1),
+                value: Data::Int(10),
+            },
+            DBOperation::SetRow {
+                key: Data::Int(2),
+                row: vec![(ColumnId::new(0), Data::Int(20))],
+            },
+            DBOperation::SetValue {
+                column: ColumnId::new(0),
+                key: Data::Int(3),
+                value: Data::Int(30),
+            },
+            DBOperation::DeleteRow { key: Data::Int(3) },
+        ],
+        is_initialized: true,
+    });
+
+    let instructions: Vec<_> = [db_create, add_column, batch]
+        .iter()
+        .map(|instruction| {
+            SolanaInstruction::new_with_borsh(
+                program_id,
+                instruction,
+                vec![
+                    AccountMeta::new_readonly(global_state_id, false),
+                    AccountMeta::new_readonly(token_id, true),
+                    AccountMeta::new(fs_account_key.pubkey(), false),
+                ],
+            )
+        })
+        .collect();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[vec![create_fs_account], instructions].concat(),
+        Some(&admin.pubkey()),
+    );
+
+    transaction.sign(&[&admin, &token_key, &fs_account_key], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // The last operation refers to a nonexistent column, so the whole batch must fail
+    let failing_batch = DBInstruction::Batch(BatchParams {
+        db: segment,
+        operations: vec![
+            DBOperation::SetValue {
+                column: ColumnId::new(0),
+                key: Data::Int(4),
+                value: Data::Int(40),
+            },
+            DBOperation::DeleteValue {
+                column: ColumnId::new(0),
+                key: Data::Int(1),
+            },
+            DBOperation::SetValue {
+                column: ColumnId::new(5),
+                key: Data::Int(4),
+                value: Data::Int(40),
+            },
+        ],
+        is_initialized: true,
+    });
+
+    let failing_batch = SolanaInstruction::new_with_borsh(
+        program_id,
+        &failing_batch,
+        vec![
+            AccountMeta::new_readonly(global_state_id, false),
+            AccountMeta::new_readonly(token_id, true),
+            AccountMeta::new(fs_account_key.pubkey(), false),
+        ],
+    );
+
+    let mut transaction = Transaction::new_with_payer(&[failing_batch], Some(&admin.pubkey()));
+
+    transaction.sign(&[&admin, &token_key], recent_blockhash);
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+
+    // Retrieving accounts
+    let fs_account = banks_client
+        .get_account(fs_account_key.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut account_internals = (fs_account_key.pubkey(), fs_account);
+    let fs_account = vec![AccountInfo::from(&mut account_internals)];
+
+    // Deserializing data
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut fs_account.iter()).unwrap(),
+    ));
+
+    let db = DB::from_segment(fs, segment).unwrap();
+
+    let values: Vec<_> = (1..=4)
+        .map(|key| db.value(Data::Int(key), ColumnId::new(0)).unwrap())
+        .collect();
+
+    assert_eq!(
+        values,
+        vec![Some(Data::Int(10)), Some(Data::Int(20)), None, None]
+    );
+}
+
+#[tokio::test]
+async fn get_values() {
+    let ProgramEnvironment {
+        global_state: global_state_id,
+        program: program_key,
+        test: program,
+        token: token_key,
+        ..
+    } = prepare_environment();
+
+    let program_id = program_key.pubkey();
+    let token_id = token_key.pubkey();
+
+    let fs_account_key = Keypair::new();
+
+    let (mut banks_client, admin, recent_blockhash) = program.start().await;
+
+    let segment = SegmentId {
+        id: 0,
+        pubkey: fs_account_key.pubkey(),
+    };
+
+    let create_fs_account = create_account(
+        &admin.pubkey(),
+        &fs_account_key.pubkey(),
+        AMOUNT * 2,
+        1_000_000,
+        &program_id,
+    );
+
+    let db_create = DBInstruction::CreateDB(CreateDBParams {
+        primary_key_type: DataType::Int,
+        columns: vec![
+            ColumnParams::new("Name", DataType::ShortString, true),
+            ColumnParams::new("Age", DataType::Int, false),
+        ],
+        table_name: String::from("Test DB"),
+        max_columns: 10,
+        max_rows: 10,
+        options: TableOptions::default(),
+        is_initialized: false,
+    });
+
+    let set_row = DBInstruction::SetRow(SetRowParams {
+        db: segment,
+        key: Data::Int(1),
+        row: vec![
+            (
+                ColumnId::new(0),
+                Data::ShortString(ShortString::try_from("Alice").unwrap()),
+            ),
+            (ColumnId::new(1), Data::Int(30)),
+        ],
+        is_initialized: true,
+    });
+
+    let instructions: Vec<_> = [db_create, set_row]
+        .iter()
+        .map(|instruction| {
+            SolanaInstruction::new_with_borsh(
+                program_id,
+                instruction,
+                vec![
+                    AccountMeta::new_readonly(global_state_id, false),
+                    AccountMeta::new_readonly(token_id, true),
+                    AccountMeta::new(fs_account_key.pubkey(), false),
+                ],
+            )
+        })
+        .collect();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[vec![create_fs_account], instructions].concat(),
+        Some(&admin.pubkey()),
+    );
+
+    transaction.sign(&[&admin, &token_key, &fs_account_key], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Read instructions get the FS account as read-only and are signed by the payer only
+    let read = |instruction: DBInstruction| {
+        SolanaInstruction::new_with_borsh(
+            program_id,
+            &instruction,
+            vec![AccountMeta::new_readonly(fs_account_key.pubkey(), false)],
+        )
+    };
+
+    let get_value = read(DBInstruction::GetValue(GetValueParams {
+        db: segment,
+        column: ColumnId::new(1),
+        key: Data::Int(1),
+    }));
+    let data = simulate_return_data(&mut banks_client, &admin, recent_blockhash, get_value)
+        .await
+        .unwrap();
+    assert_eq!(
+        Option::<Data>::try_from_slice(&data).unwrap(),
+        Some(Data::Int(30))
+    );
+
+    let get_missing_value = read(DBInstruction::GetValue(GetValueParams {
+        db: segment,
+        column: ColumnId::new(1),
+        key: Data::Int(2),
+    }));
+    let data = simulate_return_data(
+        &mut banks_client,
+        &admin,
+        recent_blockhash,
+        get_missing_value,
+    )
+    .await
+    .unwrap();
+    assert_eq!(Option::<Data>::try_from_slice(&data).unwrap(), None);
+
+    let get_value_secondary = read(DBInstruction::GetValueSecondary(GetValueSecondaryParams {
+        db: segment,
+        key_column: ColumnId::new(0),
+        secondary_key: Data::ShortString(ShortString::try_from("Alice").unwrap()),
+        value_column: ColumnId::new(1),
+    }));
+    let data = simulate_return_data(
+        &mut banks_client,
+        &admin,
+        recent_blockhash,
+        get_value_secondary,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        Option::<Data>::try_from_slice(&data).unwrap(),
+        Some(Data::Int(30))
+    );
+
+    let get_row = read(DBInstruction::GetRow(GetRowParams {
+        db: segment,
+        key: Data::Int(1),
+    }));
+    let data = simulate_return_data(&mut banks_client, &admin, recent_blockhash, get_row)
+        .await
+        .unwrap();
+    assert_eq!(
+        BTreeMap::<ColumnId, Option<Data>>::try_from_slice(&data).unwrap(),
+        BTreeMap::from([
+            (
+                ColumnId::new(0),
+                Some(Data::ShortString(ShortString::try_from("Alice").unwrap()))
+            ),
+            (ColumnId::new(1), Some(Data::Int(30))),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn get_row_too_large() {
+    let ProgramEnvironment {
+        global_state: global_state_id,
+        program: program_key,
+        test: program,
+        token: token_key,
+        ..
+    } = prepare_environment();
+
+    let program_id = program_key.pubkey();
+    let token_id = token_key.pubkey();
+
+    let fs_account_key = Keypair::new();
+
+    let (mut banks_client, admin, recent_blockhash) = program.start().await;
+
+    let segment = SegmentId {
+        id: 0,
+        pubkey: fs_account_key.pubkey(),
+    };
+
+    let create_fs_account = create_account(
+        &admin.pubkey(),
+        &fs_account_key.pubkey(),
+        AMOUNT * 2,
+        1_000_000,
+        &program_id,
+    );
+
+    // Six strings of 200 bytes do not fit in the 1024 bytes of the return data
+    let column_count = 6;
+
+    let db_create = DBInstruction::CreateDB(CreateDBParams {
+        primary_key_type: DataType::Int,
+        columns: (0..column_count)
+            .map(|i| ColumnParams::new(&format!("Text {}", i), DataType::LongString, false))
+            .collect(),
+        table_name: String::from("Test DB"),
+        max_columns: 10,
+        max_rows: 10,
+        options: TableOptions::default(),
+        is_initialized: false,
+    });
+
+    let db_create = SolanaInstruction::new_with_borsh(
+        program_id,
+        &db_create,
+        vec![
+            AccountMeta::new_readonly(global_state_id, false),
+            AccountMeta::new_readonly(token_id, true),
+            AccountMeta::new(fs_account_key.pubkey(), false),
+        ],
+    );
+
+    let mut transaction =
+        Transaction::new_with_payer(&[create_fs_account, db_create], Some(&admin.pubkey()));
+
+    transaction.sign(&[&admin, &token_key, &fs_account_key], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // The values are set one by one, so that each transaction fits in a packet
+    for column in 0..column_count {
+        let set_value = DBInstruction::SetValue(SetValueParams {
+            db: segment,
+            column: ColumnId::new(column),
+            key: Data::Int(1),
+            value: Data::LongString(LongString::try_from("a".repeat(200).as_str()).unwrap()),
+            is_initialized: true,
+        });
+
+        let set_value = SolanaInstruction::new_with_borsh(
+            program_id,
+            &set_value,
+            vec![
+                AccountMeta::new_readonly(global_state_id, false),
+                AccountMeta::new_readonly(token_id, true),
+                AccountMeta::new(fs_account_key.pubkey(), false),
+            ],
+        );
+
+        let mut transaction = Transaction::new_with_payer(&[set_value], Some(&admin.pubkey()));
+
+        transaction.sign(&[&admin, &token_key], recent_blockhash);
+
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    let get_row = SolanaInstruction::new_with_borsh(
+        program_id,
+        &DBInstruction::GetRow(GetRowParams {
+            db: segment,
+            key: Data::Int(1),
+        }),
+        vec![AccountMeta::new_readonly(fs_account_key.pubkey(), false)],
+    );
+
+    let error = simulate_return_data(&mut banks_client, &admin, recent_blockhash, get_row)
+        .await
+        .unwrap_err();
+
+    let error_code = match ProgramError::from(DBError::ResultTooLarge) {
+        ProgramError::Custom(code) => code,
+        _ => unreachable!("DB errors are custom program errors"),
+    };
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::Custom(error_code))
+    );
+}
+
+#[tokio::test]
+async fn chunked_blob() {
+    let ProgramEnvironment {
+        global_state: global_state_id,
+        program: program_key,
+        test: program,
+        token: token_key,
+        ..
+    } = prepare_environment();
+
+    let program_id = program_key.pubkey();
+    let token_id = token_key.pubkey();
+
+    let fs_account_key = Keypair::new();
+
+    let (mut banks_client, admin, recent_blockhash) = program.start().await;
+
+    let segment = SegmentId {
+        id: 0,
+        pubkey: fs_account_key.pubkey(),
+    };
+
+    let create_fs_account = create_account(
+        &admin.pubkey(),
+        &fs_account_key.pubkey(),
+        AMOUNT * 2,
+        1_000_000,
+        &program_id,
+    );
+
+    let write = |instruction: DBInstruction| {
+        SolanaInstruction::new_with_borsh(
+            program_id,
+            &instruction,
+            vec![
+                AccountMeta::new_readonly(global_state_id, false),
+                AccountMeta::new_readonly(token_id, true),
+                AccountMeta::new(fs_account_key.pubkey(), false),
+            ],
+        )
+    };
+
+    let db_create = write(DBInstruction::CreateDB(CreateDBParams {
+        primary_key_type: DataType::Int,
+        columns: vec![ColumnParams::new("Description", DataType::Blob, false)],
+        table_name: String::from("Cards"),
+        max_columns: 10,
+        max_rows: 10,
+        options: TableOptions::default(),
+        is_initialized: false,
+    }));
+    let create_buffer = write(DBInstruction::CreateBlobBuffer(CreateBlobBufferParams {
+        db: segment,
+        len: 8,
+        is_initialized: true,
+    }));
+
+    let mut transaction = Transaction::new_with_payer(
+        &[create_fs_account, db_create, create_buffer],
+        Some(&admin.pubkey()),
+    );
+    transaction.sign(&[&admin, &token_key, &fs_account_key], recent_blockhash);
+
+    let result = banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    // SegmentId of the buffer is returned by the last instruction
+    let return_data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .expect("CreateBlobBuffer should set the return data");
+    let buffer = SegmentId::try_from_slice(&return_data.data).unwrap();
+
+    let instructions: Vec<_> = [
+        DBInstruction::WriteBlobChunk(WriteBlobChunkParams {
+            db: segment,
+            buffer,
+            offset: 4,
+            chunk: b"Text".to_vec(),
+            is_initialized: true,
+        }),
+        DBInstruction::WriteBlobChunk(WriteBlobChunkParams {
+            db: segment,
+            buffer,
+            offset: 0,
+            chunk: b"Card".to_vec(),
+            is_initialized: true,
+        }),
+        DBInstruction::FinalizeBlob(FinalizeBlobParams {
+            db: segment,
+            column: ColumnId::new(0),
+            key: Data::Int(1),
+            buffer,
+            is_initialized: true,
+        }),
+    ]
+    .into_iter()
+    .map(write)
+    .collect();
+
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&admin.pubkey()));
+    transaction.sign(&[&admin, &token_key], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let get_blob = SolanaInstruction::new_with_borsh(
+        program_id,
+        &DBInstruction::GetBlob(GetBlobParams {
+            db: segment,
+            column: ColumnId::new(0),
+            key: Data::Int(1),
+            offset: 2,
+            len: 4,
+        }),
+        vec![AccountMeta::new_readonly(fs_account_key.pubkey(), false)],
+    );
+    let data = simulate_return_data(&mut banks_client, &admin, recent_blockhash, get_blob)
+        .await
+        .unwrap();
+    assert_eq!(
+        Option::<Vec<u8>>::try_from_slice(&data).unwrap(),
+        Some(b"rdTe".to_vec())
+    );
+}
+
+/// Simulates a transaction with the single read-only `instruction` and returns the program
+/// return data
+async fn simulate_return_data(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    instruction: SolanaInstruction,
+) -> Result<Vec<u8>, TransactionError> {
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+
+    transaction.sign(&[payer], recent_blockhash);
+
+    let simulation = banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+
+    simulation
+        .result
+        .expect("Simulation result should be present")?;
+
+    Ok(simulation
+        .simulation_details
+        .and_then(|details| details.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default())
+}
+
 struct ProgramEnvironment {
     global_state: Pubkey,
     mint: Pubkey,
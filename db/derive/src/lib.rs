@@ -0,0 +1,235 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Ident, Lit, Meta, NestedMeta,
+    PathArguments, Type,
+};
+
+/// Derives `solcery_db::DbRow` for a struct with named fields.
+///
+/// Each field is mapped to the column with the same name, the column type is derived from the
+/// field type via `solcery_db::ColumnValue`. `Option<T>` fields are mapped to the columns of type
+/// `T`, which may have no value in the row.
+///
+/// Field attributes:
+/// - `#[db(name = "...")]` sets the column name
+/// - `#[db(secondary_key)]` marks the column as a secondary key
+#[proc_macro_derive(DbRow, attributes(db))]
+pub fn derive_db_row(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    db_row_impl(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+struct RowField {
+    ident: Ident,
+    column_name: String,
+    value_type: Type,
+    is_optional: bool,
+    is_secondary_key: bool,
+}
+
+fn db_row_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let struct_ident = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input,
+                    "DbRow can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "DbRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            // Named fields always have an ident
+            let ident = field.ident.clone().unwrap();
+            let mut column_name = ident.to_string();
+            let mut is_secondary_key = false;
+
+            for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("db")) {
+                let list = match attr.parse_meta()? {
+                    Meta::List(list) => list,
+                    meta => return Err(syn::Error::new_spanned(meta, "expected `db(...)`")),
+                };
+
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::NameValue(name_value))
+                            if name_value.path.is_ident("name") =>
+                        {
+                            match name_value.lit {
+                                Lit::Str(name) => column_name = name.value(),
+                                lit => {
+                                    return Err(syn::Error::new_spanned(
+                                        lit,
+                                        "column name should be a string literal",
+                                    ))
+                                }
+                            }
+                        }
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("secondary_key") => {
+                            is_secondary_key = true;
+                        }
+                        nested => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                "unknown attribute, expected `name = \"...\"` or `secondary_key`",
+                            ))
+                        }
+                    }
+                }
+            }
+
+            let (value_type, is_optional) = match option_inner_type(&field.ty) {
+                Some(inner) => (inner.clone(), true),
+                None => (field.ty.clone(), false),
+            };
+
+            Ok(RowField {
+                ident,
+                column_name,
+                value_type,
+                is_optional,
+                is_secondary_key,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let schema_entries = fields.iter().map(|field| {
+        let RowField {
+            column_name,
+            value_type,
+            is_secondary_key,
+            ..
+        } = field;
+
+        quote! {
+            ::solcery_db::ColumnSchema {
+                name: ::std::string::String::from(#column_name),
+                data_type: <#value_type as ::solcery_db::ColumnValue>::DATA_TYPE,
+                is_secondary_key: #is_secondary_key,
+            }
+        }
+    });
+
+    let to_row_entries = fields.iter().map(|field| {
+        let RowField {
+            ident,
+            column_name,
+            is_optional,
+            ..
+        } = field;
+
+        if *is_optional {
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    row.push((
+                        ::solcery_db::lookup_column(columns, #column_name)?,
+                        ::solcery_db::ColumnValue::to_data(value),
+                    ));
+                }
+            }
+        } else {
+            quote! {
+                row.push((
+                    ::solcery_db::lookup_column(columns, #column_name)?,
+                    ::solcery_db::ColumnValue::to_data(&self.#ident),
+                ));
+            }
+        }
+    });
+
+    let from_row_entries = fields.iter().map(|field| {
+        let RowField {
+            ident,
+            column_name,
+            value_type,
+            is_optional,
+            ..
+        } = field;
+
+        if *is_optional {
+            quote! {
+                #ident: ::solcery_db::take_value(&mut row, columns, #column_name)?
+                    .map(<#value_type as ::solcery_db::ColumnValue>::from_data)
+                    .transpose()?,
+            }
+        } else {
+            quote! {
+                #ident: <#value_type as ::solcery_db::ColumnValue>::from_data(
+                    ::solcery_db::take_value(&mut row, columns, #column_name)?
+                        .ok_or(::solcery_db::Error::MissingValue)?,
+                )?,
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::solcery_db::DbRow for #struct_ident #ty_generics #where_clause {
+            fn schema() -> ::std::vec::Vec<::solcery_db::ColumnSchema> {
+                ::std::vec![#(#schema_entries),*]
+            }
+
+            fn to_row(
+                &self,
+                columns: &::std::collections::BTreeMap<::std::string::String, ::solcery_db::ColumnId>,
+            ) -> ::std::result::Result<
+                ::std::vec::Vec<(::solcery_db::ColumnId, ::solcery_db::Data)>,
+                ::solcery_db::Error,
+            > {
+                let mut row = ::std::vec::Vec::new();
+                #(#to_row_entries)*
+                ::std::result::Result::Ok(row)
+            }
+
+            fn from_row(
+                mut row: ::std::collections::BTreeMap<
+                    ::solcery_db::ColumnId,
+                    ::std::option::Option<::solcery_db::Data>,
+                >,
+                columns: &::std::collections::BTreeMap<::std::string::String, ::solcery_db::ColumnId>,
+            ) -> ::std::result::Result<Self, ::solcery_db::Error> {
+                ::std::result::Result::Ok(Self {
+                    #(#from_row_entries)*
+                })
+            }
+        }
+    })
+}
+
+/// Returns `T` if the given type is `Option<T>`
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
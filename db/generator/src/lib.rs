@@ -1,248 +1,72 @@
 use proc_macro2::{Literal, TokenStream, TokenTree};
 use quote::quote;
-use syn::{Ident, ItemEnum, Variant};
+use syn::parse::{ParseStream, Parser};
+use syn::{Expr, ExprLit, Ident, ItemEnum, Lit, Token, Variant};
 
 #[proc_macro_attribute]
 pub fn generate_column_impls(
     attrs: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let output = column_impls(&TokenStream::from(attrs), input);
+    let output =
+        column_impls(&TokenStream::from(attrs), input).unwrap_or_else(|err| err.to_compile_error());
     proc_macro::TokenStream::from(output)
 }
 
-fn column_impls(attrs: &TokenStream, input: proc_macro::TokenStream) -> TokenStream {
-    let (holder_ident, trait_ident, error_ident, derive_attrs) = parse_attrs(attrs);
-    let mut enumeration: ItemEnum = syn::parse(input).expect("Failed to parse input");
+fn column_impls(attrs: &TokenStream, input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let Args {
+        holder_ident,
+        error_ident,
+        codec_ident,
+        derives,
+    } = parse_attrs(attrs)?;
+    let mut enumeration: ItemEnum = syn::parse(input)?;
     let enum_ident = enumeration.ident.clone();
-    let variants: Vec<Params> = enumeration
-        .variants
-        .iter_mut()
-        .map(|var| parse_params(var).expect("No parameter attributes present"))
-        .collect();
+
+    let mut variants = Vec::with_capacity(enumeration.variants.len());
+    let mut errors: Option<syn::Error> = None;
+    for var in enumeration.variants.iter_mut() {
+        match parse_params(var) {
+            Ok(params) => variants.push(params),
+            Err(err) => match &mut errors {
+                Some(errors) => errors.combine(err),
+                None => errors = Some(err),
+            },
+        }
+    }
+
+    if let Some(errors) = errors {
+        // The enum itself is still emitted, so that its uses do not produce unrelated errors
+        let errors = errors.to_compile_error();
+        return Ok(quote!(
+            #enumeration
+
+            #errors
+        ));
+    }
 
     let holder_enum = generate_holder_enum(&holder_ident, &variants);
 
-    let impl_enum = generate_enum_impl(&enum_ident, &variants);
+    // Column containers take the sizes of keys and values at runtime, so only the sizes of the
+    // types are generated and the number of instantiations does not depend on the number of types
+    let impl_enum = generate_enum_impl(&enum_ident, &holder_ident, &codec_ident, &variants);
 
     let holder_attrs = quote! {
-        #[derive #derive_attrs]
+        #[derive #derives]
         #[allow(missing_docs)]
     };
 
     let holder_try_from_impls = generate_try_from_impls(&holder_ident, &variants);
 
-    let column_trait_implementations = variants.iter().map(|key| {
-        let key_ident = &key.ident;
-        let key_type = &key.typ;
-        let key_size = &key.size;
-
-        variants.iter().map(|value| {
-            let value_ident = &value.ident;
-            let value_type = &value.typ;
-            let value_size = &value.size;
-            quote!{
-                impl<'a> #trait_ident for slice_rbtree::tree::RBTree<'a, #key_type, #value_type, #key_size, #value_size> {
-                    fn get_key(&self, value: #holder_ident) -> Option<#holder_ident> {
-                        //TODO: implement a slow but working variant
-                        None
-                    }
-
-                    fn get_value(&self, key: #holder_ident) -> Option<#holder_ident> {
-                        if let #holder_ident::#key_ident(unwrapped_key) = key {
-                            self.get(&unwrapped_key).map(|val| #holder_ident::#value_ident(val))
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn set(&mut self, key: #holder_ident, value: #holder_ident) -> Result<Option<#holder_ident>, #error_ident> {
-                        if let (#holder_ident::#key_ident(unwrapped_key), #holder_ident::#value_ident(unwrapped_value)) = (key, value) {
-                            self.insert(unwrapped_key, unwrapped_value)
-                                .map(|maybe_old_val| maybe_old_val.map(|old_val|#holder_ident::#value_ident(old_val)))
-                                .map_err(|e| #error_ident::from(e))
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn delete_by_key(&mut self, key: #holder_ident) -> bool {
-                        if let #holder_ident::#key_ident(unwrapped_key) = key {
-                            self.delete(&unwrapped_key)
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn delete_by_value(&mut self, value: #holder_ident) -> bool {
-                        //TODO: implement a slow but working variant
-                        panic!("It is impossible to delete by value in RBTree");
-                    }
-                }
-
-                impl<'a> #trait_ident for solcery_reltab::one_to_one::OneToOne<'a, #key_type, #value_type, #key_size, #value_size> {
-                    fn get_key(&self, value: #holder_ident) -> Option<#holder_ident> {
-                        if let #holder_ident::#value_ident(unwrapped_value) = value {
-                            self.get_key(&unwrapped_value).map(|key| #holder_ident::#key_ident(key))
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn get_value(&self, key: #holder_ident) -> Option<#holder_ident> {
-                        if let #holder_ident::#key_ident(unwrapped_key) = key {
-                            self.get_value(&unwrapped_key).map(|val| #holder_ident::#value_ident(val))
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn set(&mut self, key: #holder_ident, value: #holder_ident) -> Result<Option<#holder_ident>, #error_ident> {
-                        if let (#holder_ident::#key_ident(unwrapped_key), #holder_ident::#value_ident(unwrapped_value)) = (key, value) {
-                            //TODO: Do something with tis allocations
-                            let result = self.insert(unwrapped_key.clone(), unwrapped_value.clone());
-                            match result {
-                                Ok((Some(old_key), None)) => {
-                                    self.insert(old_key, unwrapped_value).expect("Failed to revert changes, DB is now corrupted");
-                                    Err(#error_ident::NonUniqueSecondaryKey)
-                                }
-                                Ok((Some(old_key), Some(old_value))) => {
-                                    self.insert(old_key, unwrapped_value).expect("Failed to revert changes, DB is now corrupted");
-                                    self.insert(unwrapped_key, old_value).expect("Failed to revert changes, DB is now corrupted");
-                                    Err(#error_ident::NonUniqueSecondaryKey)
-                                }
-                                Ok((None, maybe_old_val)) => {
-                                    Ok(maybe_old_val.map(|old_val|#holder_ident::#value_ident(old_val)))
-                                }
-                                Err(e) => {
-                                    Err(#error_ident::from(e))
-                                }
-                            }
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-
-                    fn delete_by_key(&mut self, key: #holder_ident) -> bool {
-                        if let #holder_ident::#key_ident(unwrapped_key) = key {
-                            self.remove_by_key(&unwrapped_key).is_some()
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
+    let impl_holder = generate_holder_impl(
+        &holder_ident,
+        &enum_ident,
+        &error_ident,
+        &codec_ident,
+        &variants,
+    );
 
-                    fn delete_by_value(&mut self, value: #holder_ident) -> bool {
-                        if let #holder_ident::#value_ident(unwrapped_value) = value {
-                            self.remove_by_value(&unwrapped_value).is_some()
-                        } else {
-                            panic!("Type mismatch!");
-                        }
-                    }
-                }
-            }
-        }).collect::<TokenStream>()
-    }).collect::<TokenStream>();
-
-    let result: ((TokenStream, TokenStream), (TokenStream, TokenStream))  = variants.iter().map(|key| {
-        let key_ident = key.ident.clone();
-        let key_type = key.typ.clone();
-        let key_size = key.size.clone();
-
-        variants.iter().map(|value| {
-            let value_ident = value.ident.clone();
-            let value_type = value.typ.clone();
-            let value_size = value.size.clone();
-
-            let slice_init_variant = quote! {
-                (#enum_ident::#key_ident, #enum_ident::#value_ident) => slice_rbtree::tree::RBTree::<#key_type, #value_type, #key_size, #value_size>::init_slice(slice)
-                    .map(|tree| Box::new(tree) as Box<dyn #trait_ident>)
-                    .map_err(|e| #error_ident::from(e)),
-            };
-
-            let slice_from_variant = quote! {
-                (#enum_ident::#key_ident, #enum_ident::#value_ident) => slice_rbtree::tree::RBTree::<#key_type, #value_type, #key_size, #value_size>::from_slice(slice)
-                    .map(|tree| Box::new(tree) as Box<dyn #trait_ident>)
-                    .map_err(|e| #error_ident::from(e)),
-            };
-
-            let one_to_one_init_variant = quote! {
-                (#enum_ident::#key_ident, #enum_ident::#value_ident) => solcery_reltab::one_to_one::OneToOne::<#key_type, #value_type, #key_size, #value_size>::init_slice(slice)
-                    .map(|container| Box::new(container) as Box<dyn #trait_ident>)
-                    .map_err(|e| #error_ident::from(e)),
-            };
-
-            let one_to_one_from_variant = quote! {
-                (#enum_ident::#key_ident, #enum_ident::#value_ident) => solcery_reltab::one_to_one::OneToOne::<#key_type, #value_type, #key_size, #value_size>::from_slice(slice)
-                    .map(|container| Box::new(container) as Box<dyn #trait_ident>)
-                    .map_err(|e| #error_ident::from(e)),
-            };
-
-            (
-                (slice_init_variant, slice_from_variant),
-                (one_to_one_init_variant, one_to_one_from_variant)
-            )
-        })
-        .unzip::<(TokenStream, TokenStream), (TokenStream, TokenStream), (TokenStream, TokenStream), (TokenStream, TokenStream)>()
-    }).unzip();
-
-    let (
-        (slice_init_variants, slice_from_variants),
-        (one_to_one_init_variants, one_to_one_from_variants),
-    ) = result;
-
-    let from_slice_fn = quote! {
-        /// Gets [`#trait_ident`] object from the given slice
-        pub fn from_column_slice<'a,'b: 'a>(
-            pk_type: #enum_ident,
-            val_type: #enum_ident,
-            column_type: ColumnType,
-            slice: &'b mut [u8],
-        ) -> Result<Box<dyn #trait_ident + 'a>, #error_ident> {
-            match column_type {
-                ColumnType::RBTree => {
-                    unsafe {
-                        match (pk_type, val_type) {
-                            #slice_from_variants
-                        }
-                    }
-                },
-                ColumnType::OneToOne => {
-                    unsafe {
-                        match (pk_type, val_type) {
-                            #one_to_one_from_variants
-                        }
-                    }
-                },
-            }
-        }
-    };
-
-    let init_slice_fn = quote! {
-        /// Inits [`#trait_ident`] object in the given slice
-        pub fn init_column_slice<'a,'b: 'a>(
-            pk_type: #enum_ident,
-            val_type: #enum_ident,
-            column_type: ColumnType,
-            slice: &'b mut [u8],
-        ) -> Result<Box<dyn #trait_ident + 'a>, #error_ident> {
-            match column_type {
-                ColumnType::RBTree => {
-                    match (pk_type, val_type) {
-                        #slice_init_variants
-                    }
-                },
-                ColumnType::OneToOne => {
-                    unsafe {
-                        match (pk_type, val_type) {
-                            #one_to_one_init_variants
-                        }
-                    }
-                },
-            }
-        }
-    };
-
-    quote!(
+    Ok(quote!(
         #enumeration
 
         #impl_enum
@@ -251,67 +75,91 @@ fn column_impls(attrs: &TokenStream, input: proc_macro::TokenStream) -> TokenStr
         #holder_enum
         #holder_try_from_impls
 
-        #column_trait_implementations
-
-        #init_slice_fn
-
-        #from_slice_fn
-    )
+        #impl_holder
+    ))
 }
 
-fn parse_params(var: &mut Variant) -> Option<Params> {
-    let attrs = &mut var.attrs;
+/// Parses and removes the `#[type_params(Type, size)]` attribute of the variant, the size is
+/// optional
+fn parse_params(var: &mut Variant) -> syn::Result<Params> {
     let ident = var.ident.clone();
 
-    // Actually, this is a performance nightmare: too mutch unneeded allocations.
-    // Good news: this code will be runned only during compilation, so no runtime costs.
-    let params_attr = attrs
-        .iter()
-        .find(|attr| {
-            attr.path.get_ident().map(|ident| format!("{}", ident))
-                == Some("type_params".to_string())
-        })
-        .cloned();
-
-    let attr = params_attr?;
-
-    attrs.retain(|attr| {
-        attr.path.get_ident().map(|ident| format!("{}", ident)) != Some("type_params".to_string())
-    });
-
-    let group = attr.tokens.into_iter().next()?;
+    let (params_attrs, other_attrs) = var
+        .attrs
+        .drain(..)
+        .partition::<Vec<_>, _>(|attr| attr.path.is_ident("type_params"));
+    var.attrs = other_attrs;
+
+    let mut params_attrs = params_attrs.into_iter();
+
+    let attr = params_attrs.next().ok_or_else(|| {
+        syn::Error::new(
+            ident.span(),
+            "missing `#[type_params(Type, size)]` attribute",
+        )
+    })?;
+
+    if let Some(duplicate) = params_attrs.next() {
+        return Err(syn::Error::new_spanned(
+            duplicate,
+            "duplicate `type_params` attribute, each variant must have exactly one",
+        ));
+    }
 
-    let inner_stream = match group {
-        TokenTree::Group(group) => group.stream(),
-        ttree => panic!("Unexpected tokens {:?}", ttree),
+    let (typ, size_expr) = attr.parse_args_with(|input: ParseStream| {
+        let typ: Ident = input.parse()?;
+        if input.parse::<Option<Token![,]>>()?.is_none() || input.is_empty() {
+            return Ok((typ, None));
+        }
+        let size: Expr = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok((typ, Some(size)))
+    })?;
+
+    let size_expr = match size_expr {
+        Some(size_expr) => size_expr,
+        None => {
+            return Ok(Params {
+                ident,
+                typ,
+                size: None,
+            })
+        }
     };
 
-    let mut tokens = inner_stream.into_iter();
-
-    let typ_tree = tokens.next()?;
-
-    let typ = match typ_tree {
-        TokenTree::Ident(ident) => ident,
-        ttree => panic!("Unexpected tokens {:?}", ttree),
+    let size = match &size_expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(literal),
+            ..
+        }) => literal.base10_parse::<usize>()?,
+        expr => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "size must be an integer literal",
+            ))
+        }
     };
 
-    tokens.next();
-
-    let size_tree = tokens.next()?;
-
-    let size = match size_tree {
-        TokenTree::Literal(literal) => literal,
-        ttree => panic!("Unexpected tokens {:?}", ttree),
-    };
+    if size == 0 {
+        return Err(syn::Error::new_spanned(
+            size_expr,
+            "size must be greater than zero",
+        ));
+    }
 
-    Some(Params { ident, typ, size })
+    Ok(Params {
+        ident,
+        typ,
+        size: Some(size),
+    })
 }
 
 #[derive(Debug, Clone)]
 struct Params {
     ident: Ident,
     typ: Ident,
-    size: Literal,
+    /// Explicit size of the type, if it is omitted, `MAX_ENCODED_SIZE` of the codec is used
+    size: Option<usize>,
 }
 
 fn generate_holder_enum(holder_ident: &Ident, variants: &[Params]) -> TokenStream {
@@ -335,25 +183,131 @@ fn generate_holder_enum(holder_ident: &Ident, variants: &[Params]) -> TokenStrea
     }
 }
 
-fn generate_enum_impl(enum_ident: &Ident, variants: &[Params]) -> TokenStream {
-    let fn_vars: TokenStream = variants
+fn generate_enum_impl(
+    enum_ident: &Ident,
+    holder_ident: &Ident,
+    codec_ident: &Ident,
+    variants: &[Params],
+) -> TokenStream {
+    let size_vars: TokenStream = variants
+        .iter()
+        .map(|var| {
+            let ident = &var.ident;
+            let typ = &var.typ;
+
+            match var.size {
+                Some(size) => {
+                    let size = Literal::usize_unsuffixed(size);
+                    quote! {
+                        #enum_ident::#ident => #size,
+                    }
+                }
+                None => quote! {
+                    #enum_ident::#ident => <#typ as #codec_ident>::MAX_ENCODED_SIZE,
+                },
+            }
+        })
+        .collect();
+
+    // Explicit sizes are checked at compile time, the omitted ones fit by construction
+    let size_asserts: TokenStream = variants
+        .iter()
+        .filter_map(|var| {
+            let ident = &var.ident;
+            let typ = &var.typ;
+            let message = Literal::string(&format!(
+                "size of `{}::{}` is smaller than `<{} as {}>::MAX_ENCODED_SIZE`",
+                enum_ident, ident, typ, codec_ident
+            ));
+            let size = Literal::usize_unsuffixed(var.size?);
+
+            Some(quote! {
+                const _: () = assert!(<#typ as #codec_ident>::MAX_ENCODED_SIZE <= #size, #message);
+            })
+        })
+        .collect();
+
+    let decode_vars: TokenStream = variants
         .iter()
         .map(|var| {
             let ident = &var.ident;
-            let size = &var.size;
+            let typ = &var.typ;
 
             quote! {
-                #enum_ident::#ident => #size,
+                #enum_ident::#ident => #holder_ident::#ident(<#typ as #codec_ident>::decode(bytes)),
             }
         })
         .collect();
 
+    let variant_count = Literal::usize_unsuffixed(variants.len());
+
     quote! {
         impl #enum_ident {
+            /// Number of the variants, their discriminants are `0..VARIANT_COUNT`
+            pub const VARIANT_COUNT: usize = #variant_count;
+
             /// Returns size of the type
             pub const fn size(&self) -> usize {
                 match self {
-                    #fn_vars
+                    #size_vars
+                }
+            }
+
+            /// Decodes a value of this type, encoded to a slice of [`Self::size`] bytes
+            pub fn decode(&self, bytes: &[u8]) -> #holder_ident {
+                match self {
+                    #decode_vars
+                }
+            }
+        }
+
+        #size_asserts
+    }
+}
+
+fn generate_holder_impl(
+    holder_ident: &Ident,
+    enum_ident: &Ident,
+    error_ident: &Ident,
+    codec_ident: &Ident,
+    variants: &[Params],
+) -> TokenStream {
+    let type_vars: TokenStream = variants
+        .iter()
+        .map(|var| {
+            let ident = &var.ident;
+
+            quote! {
+                #holder_ident::#ident(_) => #enum_ident::#ident,
+            }
+        })
+        .collect();
+
+    let encode_vars: TokenStream = variants
+        .iter()
+        .map(|var| {
+            let ident = &var.ident;
+
+            quote! {
+                #holder_ident::#ident(value) => #codec_ident::encode(value, out),
+            }
+        })
+        .collect();
+
+    quote! {
+        impl #holder_ident {
+            /// Returns type of the stored value
+            pub fn data_type(&self) -> #enum_ident {
+                match self {
+                    #type_vars
+                }
+            }
+
+            /// Writes the encoding of the value to `out`, which should be as long as the size of
+            /// the type
+            pub fn encode(&self, out: &mut [u8]) -> Result<(), #error_ident> {
+                match self {
+                    #encode_vars
                 }
             }
         }
@@ -389,48 +343,43 @@ fn generate_try_from_impls(holder_enum: &Ident, variants: &[Params]) -> TokenStr
         .collect()
 }
 
-fn parse_attrs(attrs: &TokenStream) -> (Ident, Ident, Ident, TokenTree) {
-    let mut token_iterator = attrs.clone().into_iter();
-
-    let holder_ident = match token_iterator.next() {
-        Some(TokenTree::Ident(ident)) => ident,
-        Some(tokens) => panic!("Unexpected tokens: {}", tokens),
-        None => panic!("Not enough arguments"),
-    };
-
-    token_iterator.next(); // skip punct
-
-    let trait_ident = match token_iterator.next() {
-        Some(TokenTree::Ident(ident)) => ident,
-        Some(tokens) => panic!("Unexpected tokens: {}", tokens),
-        None => panic!("Not enough arguments"),
-    };
-
-    token_iterator.next(); // skip punct
-
-    let error_ident = match token_iterator.next() {
-        Some(TokenTree::Ident(ident)) => ident,
-        Some(tokens) => panic!("Unexpected tokens: {}", tokens),
-        None => panic!("Not enough arguments"),
-    };
+/// Arguments of the `generate_column_impls` attribute
+struct Args {
+    holder_ident: Ident,
+    error_ident: Ident,
+    codec_ident: Ident,
+    derives: TokenTree,
+}
 
-    token_iterator.next(); // skip punct
+fn parse_attrs(attrs: &TokenStream) -> syn::Result<Args> {
+    let parser = |input: ParseStream| {
+        let holder_ident: Ident = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let error_ident: Ident = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let codec_ident: Ident = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let keyword: Ident = input.parse()?;
+        if keyword != "derives" {
+            return Err(syn::Error::new(keyword.span(), "expected `derives(...)`"));
+        }
 
-    match token_iterator.next() {
-        Some(TokenTree::Ident(ident)) => {
-            if format!("{}", ident) != *"derives" {
-                panic!("Unexpected keyword: {}", ident)
-            }
+        let derives: TokenTree = input.parse()?;
+        if !matches!(derives, TokenTree::Group(_)) {
+            return Err(syn::Error::new(
+                derives.span(),
+                "expected a list of derives in parentheses",
+            ));
         }
-        Some(tokens) => panic!("Unexpected tokens: {}", tokens),
-        None => panic!("Not enough arguments"),
-    }
 
-    let derives = match token_iterator.next() {
-        Some(TokenTree::Group(group)) => TokenTree::Group(group),
-        Some(tokens) => panic!("Unexpected tokens: {}", tokens),
-        None => panic!("Not enough arguments"),
+        Ok(Args {
+            holder_ident,
+            error_ident,
+            codec_ident,
+            derives,
+        })
     };
 
-    (holder_ident, trait_ident, error_ident, derives)
+    parser.parse2(attrs.clone())
 }
@@ -1,36 +1,73 @@
-use slice_rbtree::Error;
 use solcery_impl_generator::generate_column_impls;
 
-pub trait Column {
-    fn get_key(&self, value: HolderName) -> Option<HolderName>;
-    fn get_value(&self, key: HolderName) -> Option<HolderName>;
-    fn set(&mut self, key: HolderName, value: HolderName) -> Result<Option<HolderName>, ErrorType>;
-    fn delete_by_key(&mut self, key: HolderName) -> bool;
-    fn delete_by_value(&mut self, value: HolderName) -> bool;
+pub trait Codec: Sized {
+    const MAX_ENCODED_SIZE: usize;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType>;
+    fn decode(bytes: &[u8]) -> Self;
 }
 
-pub enum ErrorType {
-    Test,
-    NonUniqueSecondaryKey,
+impl Codec for i32 {
+    const MAX_ENCODED_SIZE: usize = 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType> {
+        out.copy_from_slice(&(*self as u32 ^ (1 << 31)).to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        (u32::from_be_bytes(bytes.try_into().unwrap()) ^ (1 << 31)) as i32
+    }
 }
 
-pub enum ColumnType {
-    RBTree,
-    OneToOne,
+impl Codec for u64 {
+    const MAX_ENCODED_SIZE: usize = 8;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType> {
+        out.copy_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        u64::from_be_bytes(bytes.try_into().unwrap())
+    }
 }
 
-impl From<Error> for ErrorType {
-    fn from(_err: Error) -> Self {
-        Self::Test
+impl Codec for u16 {
+    const MAX_ENCODED_SIZE: usize = 2;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType> {
+        out.fill(0);
+        out[..2].copy_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        u16::from_be_bytes(bytes[..2].try_into().unwrap())
     }
 }
 
-#[generate_column_impls(HolderName, Column, ErrorType, derives(Debug))]
+pub enum ErrorType {}
+
+#[generate_column_impls(HolderName, ErrorType, Codec, derives(Debug))]
 pub enum Test {
     #[type_params(i32, 4)]
     Int,
     #[type_params(u64, 8)]
     Unsigned,
+    #[type_params(u16)]
+    Short,
 }
 
-fn main() {}
+fn main() {
+    assert_eq!(Test::VARIANT_COUNT, 3);
+    assert_eq!(Test::Int.size(), 4);
+    assert_eq!(Test::Unsigned.size(), 8);
+    // The size is taken from the codec, if it is omitted
+    assert_eq!(Test::Short.size(), 2);
+
+    let mut bytes = [0; 4];
+    let value = HolderName::Int(-7);
+    assert!(value.encode(&mut bytes).is_ok());
+    assert!(matches!(Test::Int.decode(&bytes), HolderName::Int(-7)));
+}
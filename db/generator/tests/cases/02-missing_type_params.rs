@@ -0,0 +1,12 @@
+#![allow(dead_code)]
+
+use solcery_impl_generator::generate_column_impls;
+
+#[generate_column_impls(HolderName, ErrorType, Codec, derives(Debug))]
+pub enum Test {
+    #[type_params(i32, 4)]
+    Int,
+    Unsigned,
+}
+
+fn main() {}
@@ -0,0 +1,57 @@
+#![allow(dead_code)]
+
+use solcery_impl_generator::generate_column_impls;
+
+pub trait Codec: Sized {
+    const MAX_ENCODED_SIZE: usize;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType>;
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+impl Codec for i32 {
+    const MAX_ENCODED_SIZE: usize = 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType> {
+        out.copy_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        i32::from_be_bytes(bytes.try_into().unwrap())
+    }
+}
+
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Codec for Point {
+    const MAX_ENCODED_SIZE: usize = 8;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), ErrorType> {
+        self.x.encode(&mut out[..4])?;
+        self.y.encode(&mut out[4..8])
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            x: i32::decode(&bytes[..4]),
+            y: i32::decode(&bytes[4..8]),
+        }
+    }
+}
+
+pub enum ErrorType {}
+
+#[generate_column_impls(HolderName, ErrorType, Codec, derives(Debug))]
+pub enum Test {
+    #[type_params(i32, 4)]
+    Int,
+    #[type_params(Point, 4)]
+    Point,
+}
+
+fn main() {}
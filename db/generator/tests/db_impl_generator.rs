@@ -4,4 +4,8 @@ use trybuild::TestCases;
 fn ui() {
     let t = TestCases::new();
     t.pass("tests/cases/01-correct_use.rs");
+    t.compile_fail("tests/cases/02-missing_type_params.rs");
+    t.compile_fail("tests/cases/03-duplicate_type_params.rs");
+    t.compile_fail("tests/cases/04-non_literal_size.rs");
+    t.compile_fail("tests/cases/05-size_below_max_encoded_size.rs");
 }
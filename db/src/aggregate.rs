@@ -0,0 +1,156 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::ops::Bound;
+
+use super::{ColumnId, ColumnView, Data, DataType, Error, DB};
+
+/// Aggregate function, computed by [`DB::aggregate`]
+#[derive(
+    PartialEq, Copy, Clone, Eq, Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub enum Agg {
+    /// Number of values, returned as [`Data::Int64`]
+    Count,
+    /// Sum of the numeric values, returned as [`Data::Int64`]
+    Sum,
+    /// The smallest value
+    Min,
+    /// The largest value
+    Max,
+    /// Average of the numeric values rounded towards zero, returned as [`Data::Int64`]
+    Avg,
+}
+
+/// Range of primary keys, the `start` bound is inclusive, the `end` bound is exclusive
+///
+/// Missing bound means that the range is unbounded from this side.
+#[derive(
+    PartialEq, Clone, Eq, Debug, Default, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct KeyRange {
+    /// The smallest key of the range
+    pub start: Option<Data>,
+    /// The first key after the range
+    pub end: Option<Data>,
+}
+
+impl KeyRange {
+    /// Range, which contains all the keys
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the range contains all the keys
+    pub fn is_full(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
+
+    /// Bounds of the range for the key seek, `None` if the range is empty
+    pub(crate) fn bounds(&self) -> Option<(Bound<Data>, Bound<Data>)> {
+        if let (Some(start), Some(end)) = (&self.start, &self.end) {
+            if start >= end {
+                return None;
+            }
+        }
+
+        let start = self.start.clone().map_or(Bound::Unbounded, Bound::Included);
+        let end = self.end.clone().map_or(Bound::Unbounded, Bound::Excluded);
+        Some((start, end))
+    }
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Computes the aggregate function `agg` over the values of the column `column_id`, which
+    /// primary keys are within the `range`.
+    ///
+    /// [`Agg::Sum`] and [`Agg::Avg`] are defined only for [`DataType::Int`] and
+    /// [`DataType::Int64`] columns. `None` is returned for [`Agg::Min`], [`Agg::Max`] and
+    /// [`Agg::Avg`] if there are no values in the range.
+    ///
+    /// Only the values within the `range` are visited, the first of them is found by a key search.
+    /// [`Agg::Min`] and [`Agg::Max`] over the full range never scan the column: a secondary key
+    /// column finds them in O(log n), as its values are ordered, and the trees of other columns
+    /// keep the extreme values of their subtrees.
+    pub fn aggregate(
+        &self,
+        column_id: ColumnId,
+        agg: Agg,
+        range: &KeyRange,
+    ) -> Result<Option<Data>, Error> {
+        let (_, header) = self.column_header(column_id)?;
+        let value_type = header.value_type();
+
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+
+        aggregate_column(&*accessed_columns[&column_id], value_type, agg, range)
+    }
+}
+
+/// Computes [`DB::aggregate`] over the `column`, which values are of `value_type`
+pub(crate) fn aggregate_column<C: ColumnView + ?Sized>(
+    column: &C,
+    value_type: DataType,
+    agg: Agg,
+    range: &KeyRange,
+) -> Result<Option<Data>, Error> {
+    if matches!(agg, Agg::Sum | Agg::Avg)
+        && value_type != DataType::Int
+        && value_type != DataType::Int64
+    {
+        return Err(Error::TypeMismatch {
+            expected: DataType::Int64,
+            got: value_type,
+        });
+    }
+
+    if range.is_full() {
+        match agg {
+            Agg::Min => return Ok(column.min_value()),
+            Agg::Max => return Ok(column.max_value()),
+            _ => {}
+        }
+    }
+
+    let values: Box<dyn Iterator<Item = Data>> = match range.bounds() {
+        Some(bounds) => Box::new(column.range(bounds)?.map(|(_, value)| value)),
+        None => Box::new(std::iter::empty()),
+    };
+
+    let result = match agg {
+        Agg::Count => Some(Data::Int64(values.count() as i64)),
+        Agg::Min => values.min(),
+        Agg::Max => values.max(),
+        Agg::Sum => Some(Data::Int64(sum(values)?.0)),
+        Agg::Avg => match sum(values)? {
+            (_, 0) => None,
+            (total, count) => Some(Data::Int64(total / count)),
+        },
+    };
+
+    Ok(result)
+}
+
+/// Returns the sum of numeric values and their count
+fn sum(values: impl Iterator<Item = Data>) -> Result<(i64, i64), Error> {
+    let mut total: i64 = 0;
+    let mut count: i64 = 0;
+
+    for value in values {
+        let value = match value {
+            Data::Int(value) => i64::from(value),
+            Data::Int64(value) => value,
+            other => {
+                return Err(Error::TypeMismatch {
+                    expected: DataType::Int64,
+                    got: other.data_type(),
+                })
+            }
+        };
+
+        total = total.checked_add(value).ok_or(Error::ArithmeticOverflow)?;
+        count += 1;
+    }
+
+    Ok((total, count))
+}
@@ -0,0 +1,354 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+use account_fs::SegmentId;
+
+use super::codec::{read_fixed, write_fixed, ByteCodec};
+use super::{ColumnId, Data, DataType, Error, DB};
+
+/// Handle of a variable-length payload, stored in its own segment
+///
+/// Columns of type [`DataType::Blob`] store only handles, payloads are accessed with
+/// [`DB::blob`] and [`DB::set_blob`].
+#[derive(
+    PartialEq,
+    Copy,
+    Clone,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct BlobHandle {
+    pubkey: Pubkey,
+    segment: u32,
+    len: u32,
+}
+
+impl BlobHandle {
+    /// [`SegmentId`] of the payload
+    pub fn segment_id(&self) -> SegmentId {
+        SegmentId {
+            pubkey: self.pubkey,
+            id: self.segment,
+        }
+    }
+
+    /// Length of the payload
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Checks if the payload is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl ByteCodec for BlobHandle {
+    const MAX_ENCODED_SIZE: usize = 40;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        let mut bytes = [0; 40];
+        bytes[..32].copy_from_slice(self.pubkey.as_ref());
+        bytes[32..36].copy_from_slice(&self.segment.to_be_bytes());
+        bytes[36..].copy_from_slice(&self.len.to_be_bytes());
+        write_fixed(&bytes, out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let bytes: [u8; 40] = read_fixed(bytes);
+        Self {
+            pubkey: Pubkey::new_from_array(read_fixed(&bytes[..32])),
+            segment: u32::from_be_bytes(read_fixed(&bytes[32..36])),
+            len: u32::from_be_bytes(read_fixed(&bytes[36..])),
+        }
+    }
+}
+
+/// Size of the header of the blob segments: the magic and the length of the payload
+const BLOB_HEADER_SIZE: usize = 12;
+/// Magic of the blob buffers, which are still being written
+const BUFFER_MAGIC: &[u8; 8] = b"BlobPart";
+/// Magic of the finalized blobs, which handles are stored in the columns
+const BLOB_MAGIC: &[u8; 8] = b"BlobData";
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Stores `payload` in a new segment and sets its handle in the `column_id` for the given
+    /// `primary_key`.
+    ///
+    /// The previous payload, if any, is deallocated. Payloads, which are too large to be passed
+    /// at once, are written in chunks, see [`DB::create_blob_buffer`].
+    pub fn set_blob(
+        &mut self,
+        primary_key: Data,
+        column_id: ColumnId,
+        payload: &[u8],
+    ) -> Result<(), Error> {
+        self.check_blob_column(&primary_key, column_id)?;
+
+        let buffer = self.create_blob_buffer(payload.len())?;
+        let result = self
+            .write_blob_chunk(&buffer, 0, payload)
+            .and_then(|()| self.finalize_blob(primary_key, column_id, buffer));
+
+        if let Err(err) = result {
+            self.discard_blob_buffer(&buffer)?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Allocates a zero-filled buffer for a payload of `len` bytes, returns its [`SegmentId`].
+    ///
+    /// The payload is written with [`DB::write_blob_chunk`] and then set in the column with
+    /// [`DB::finalize_blob`]. Unneeded buffers are deallocated with [`DB::discard_blob_buffer`].
+    pub fn create_blob_buffer(&mut self, len: usize) -> Result<SegmentId, Error> {
+        let encoded_len = u32::try_from(len).map_err(|_| Error::BlobOutOfBounds)?;
+
+        let mut fs = self.fs.borrow_mut();
+        let segment = fs.allocate_segment(BLOB_HEADER_SIZE + len)?;
+
+        // We've just successfully allocated this segment, so this operation is infailible;
+        let slice = fs.segment(&segment).unwrap();
+        slice[..BUFFER_MAGIC.len()].copy_from_slice(BUFFER_MAGIC);
+        slice[BUFFER_MAGIC.len()..BLOB_HEADER_SIZE].copy_from_slice(&encoded_len.to_be_bytes());
+        slice[BLOB_HEADER_SIZE..].fill(0);
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&segment);
+        }
+
+        Ok(segment)
+    }
+
+    /// Writes `chunk` to the blob `buffer` at `offset` bytes from the start of the payload.
+    ///
+    /// Fails with [`Error::BlobOutOfBounds`] if the chunk does not fit in the payload and with
+    /// [`Error::NotABlobBuffer`] if the buffer is already finalized.
+    pub fn write_blob_chunk(
+        &mut self,
+        buffer: &SegmentId,
+        offset: usize,
+        chunk: &[u8],
+    ) -> Result<(), Error> {
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(buffer)?;
+
+        let result = buffer_len(slice).and_then(|len| {
+            let end = offset
+                .checked_add(chunk.len())
+                .filter(|&end| end <= len)
+                .ok_or(Error::BlobOutOfBounds)?;
+            slice[BLOB_HEADER_SIZE + offset..BLOB_HEADER_SIZE + end].copy_from_slice(chunk);
+            Ok(())
+        });
+
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(buffer);
+        }
+
+        result
+    }
+
+    /// Sets the payload of the blob `buffer` in the `column_id` for the given `primary_key`.
+    ///
+    /// After that the buffer can not be written anymore. The previous payload, if any, is
+    /// deallocated. If the handle can not be set, the buffer is left intact.
+    pub fn finalize_blob(
+        &mut self,
+        primary_key: Data,
+        column_id: ColumnId,
+        buffer: SegmentId,
+    ) -> Result<(), Error> {
+        self.check_blob_column(&primary_key, column_id)?;
+
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+
+        let mut fs = self.fs.borrow_mut();
+        let len = set_blob_magic(fs.segment(&buffer)?, BUFFER_MAGIC, BLOB_MAGIC);
+        unsafe {
+            // # Safety
+            // The slice is not used after this point
+            fs.release_borrowed_segment(&buffer);
+        }
+        let len = len?;
+
+        let handle = BlobHandle {
+            pubkey: buffer.pubkey,
+            segment: buffer.id,
+            len: len as u32,
+        };
+
+        let column = accessed_columns.get_mut(&column_id).unwrap();
+        match column.set(primary_key, Data::Blob(handle)) {
+            Ok(Some(Data::Blob(old_handle))) => {
+                fs.deallocate_segment(&old_handle.segment_id())?;
+                Ok(())
+            }
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let result = set_blob_magic(fs.segment(&buffer)?, BLOB_MAGIC, BUFFER_MAGIC);
+                unsafe {
+                    // # Safety
+                    // The slice is not used after this point
+                    fs.release_borrowed_segment(&buffer);
+                }
+                result?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Deallocates the blob `buffer`, which is not finalized
+    pub fn discard_blob_buffer(&mut self, buffer: &SegmentId) -> Result<(), Error> {
+        let mut fs = self.fs.borrow_mut();
+        let len = buffer_len(fs.segment(buffer)?);
+        unsafe {
+            // # Safety
+            // The slice is not used after this point
+            fs.release_borrowed_segment(buffer);
+        }
+        len?;
+
+        fs.deallocate_segment(buffer)?;
+        Ok(())
+    }
+
+    /// Gets payload of the blob in the `column_id` by its `primary_key`.
+    pub fn blob(&self, primary_key: Data, column_id: ColumnId) -> Result<Option<Vec<u8>>, Error> {
+        let handle = match blob_handle(self.value(primary_key, column_id)?)? {
+            Some(handle) => handle,
+            None => return Ok(None),
+        };
+
+        let segment = handle.segment_id();
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(&segment)?;
+        let payload = read_payload(slice, &handle, 0, handle.len());
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&segment);
+        }
+
+        Ok(Some(payload))
+    }
+
+    /// Checks that the `column_id` is a blob column and the `primary_key` fits in the table
+    fn check_blob_column(&self, primary_key: &Data, column_id: ColumnId) -> Result<(), Error> {
+        self.check_primary_key(primary_key)?;
+
+        let (_, header) = self.column_header(column_id)?;
+        if header.value_type() != DataType::Blob {
+            return Err(Error::TypeMismatch {
+                expected: header.value_type(),
+                got: DataType::Blob,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Deallocates the payload of the blob in the `column_id` with the given `primary_key`
+    pub(crate) fn free_blob(&self, primary_key: Data, column_id: ColumnId) -> Result<(), Error> {
+        if let Some(Data::Blob(handle)) = self.value(primary_key, column_id)? {
+            self.fs
+                .borrow_mut()
+                .deallocate_segment(&handle.segment_id())?;
+        }
+
+        Ok(())
+    }
+
+    /// Deallocates payloads of all the blobs in the `column_id`
+    pub(crate) fn free_column_blobs(&self, column_id: ColumnId) -> Result<(), Error> {
+        let segments = self.column_blob_segments(column_id)?;
+
+        let mut fs = self.fs.borrow_mut();
+        for segment in segments {
+            fs.deallocate_segment(&segment)?;
+        }
+
+        Ok(())
+    }
+
+    /// Segments of the payloads of all the blobs in the `column_id`
+    pub(crate) fn column_blob_segments(
+        &self,
+        column_id: ColumnId,
+    ) -> Result<Vec<SegmentId>, Error> {
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+
+        let segments = accessed_columns[&column_id]
+            .pairs()
+            .filter_map(|(_, value)| match value {
+                Data::Blob(handle) => Some(handle.segment_id()),
+                _ => None,
+            })
+            .collect();
+
+        Ok(segments)
+    }
+}
+
+/// Extracts the handle from the `value` of a blob column
+pub(crate) fn blob_handle(value: Option<Data>) -> Result<Option<BlobHandle>, Error> {
+    match value {
+        Some(Data::Blob(handle)) => Ok(Some(handle)),
+        Some(other) => Err(Error::TypeMismatch {
+            expected: other.data_type(),
+            got: DataType::Blob,
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Copies at most `len` bytes of the payload, starting from `offset`, out of the blob `slice`
+pub(crate) fn read_payload(
+    slice: &[u8],
+    handle: &BlobHandle,
+    offset: usize,
+    len: usize,
+) -> Vec<u8> {
+    let start = offset.min(handle.len());
+    let end = offset.saturating_add(len).min(handle.len());
+    slice[BLOB_HEADER_SIZE + start..BLOB_HEADER_SIZE + end].to_vec()
+}
+
+/// Returns the length of the payload of the unfinished blob buffer
+fn buffer_len(slice: &[u8]) -> Result<usize, Error> {
+    if slice.len() < BLOB_HEADER_SIZE || &slice[..BUFFER_MAGIC.len()] != BUFFER_MAGIC {
+        return Err(Error::NotABlobBuffer);
+    }
+
+    let len = u32::from_be_bytes(read_fixed(&slice[BUFFER_MAGIC.len()..BLOB_HEADER_SIZE]));
+    let len = len as usize;
+    if slice.len() < BLOB_HEADER_SIZE + len {
+        return Err(Error::NotABlobBuffer);
+    }
+
+    Ok(len)
+}
+
+/// Replaces the magic `from` of the blob segment with `to`, returns the length of the payload
+fn set_blob_magic(slice: &mut [u8], from: &[u8; 8], to: &[u8; 8]) -> Result<usize, Error> {
+    if slice.len() < BLOB_HEADER_SIZE || &slice[..from.len()] != from {
+        return Err(Error::NotABlobBuffer);
+    }
+
+    slice[..to.len()].copy_from_slice(to);
+    let len = u32::from_be_bytes(read_fixed(&slice[to.len()..BLOB_HEADER_SIZE]));
+    Ok(len as usize)
+}
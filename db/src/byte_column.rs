@@ -0,0 +1,380 @@
+use solcery_reltab::byte_one_to_one::{byte_one_to_one_size, ByteOneToOne, TryInsertError};
+use solcery_reltab::byte_tree::{tree_size, ByteTree, TreeParams};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+use super::column::Pairs;
+use super::{Column, ColumnType, ColumnView, Data, DataType, Error, Inconsistency};
+
+/// Column container, which stores keys and values in their [`ByteCodec`](crate::ByteCodec)
+/// encodings
+///
+/// The containers take the sizes of keys and values at runtime, so a single instantiation of
+/// each container serves all the pairs of [`DataType`]s.
+///
+/// Over the containers, opened on a shared slice, it implements only the [`ColumnView`].
+pub(crate) struct ByteColumn<C> {
+    container: C,
+    key_type: DataType,
+    value_type: DataType,
+}
+
+impl<C> ByteColumn<C> {
+    pub(crate) fn new(container: C, key_type: DataType, value_type: DataType) -> Self {
+        Self {
+            container,
+            key_type,
+            value_type,
+        }
+    }
+
+    fn decode_pair(&self, (key, value): (&[u8], &[u8])) -> (Data, Data) {
+        (self.key_type.decode(key), self.value_type.decode(value))
+    }
+}
+
+/// Orders the encodings, they are memcomparable, so the bytes are compared as is
+fn compare_encodings(a: &[u8], b: &[u8]) -> Ordering {
+    a.cmp(b)
+}
+
+fn tree_params(key_type: DataType, value_type: DataType) -> TreeParams {
+    TreeParams {
+        k_size: key_type.size(),
+        v_size: value_type.size(),
+        value_extremes: false,
+    }
+}
+
+/// Returns the size of the column segment for `max_rows` pairs
+pub(crate) fn column_size(
+    key_type: DataType,
+    value_type: DataType,
+    column_type: ColumnType,
+    max_rows: usize,
+) -> usize {
+    match column_type {
+        ColumnType::RBTree => tree_size(tree_params(key_type, value_type), max_rows),
+        ColumnType::OneToOne => byte_one_to_one_size(key_type.size(), value_type.size(), max_rows),
+    }
+}
+
+/// Inits column object in the given slice
+pub(crate) fn init_column_slice<'a>(
+    key_type: DataType,
+    value_type: DataType,
+    column_type: ColumnType,
+    slice: &'a mut [u8],
+) -> Result<Box<dyn Column + 'a>, Error> {
+    let column: Box<dyn Column + 'a> = match column_type {
+        ColumnType::RBTree => Box::new(ByteColumn::new(
+            ByteTree::init_slice(slice, tree_params(key_type, value_type), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+        ColumnType::OneToOne => Box::new(ByteColumn::new(
+            ByteOneToOne::init_slice(slice, key_type.size(), value_type.size(), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+    };
+    Ok(column)
+}
+
+/// Gets column object from the given slice
+pub(crate) fn from_column_slice<'a>(
+    key_type: DataType,
+    value_type: DataType,
+    column_type: ColumnType,
+    slice: &'a mut [u8],
+) -> Result<Box<dyn Column + 'a>, Error> {
+    let column: Box<dyn Column + 'a> = match column_type {
+        ColumnType::RBTree => Box::new(ByteColumn::new(
+            ByteTree::from_slice(slice, tree_params(key_type, value_type), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+        ColumnType::OneToOne => Box::new(ByteColumn::new(
+            ByteOneToOne::from_slice(slice, key_type.size(), value_type.size(), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+    };
+    Ok(column)
+}
+
+/// Gets read-only column object from the given slice
+pub(crate) fn view_column_slice<'a>(
+    key_type: DataType,
+    value_type: DataType,
+    column_type: ColumnType,
+    slice: &'a [u8],
+) -> Result<Box<dyn ColumnView + 'a>, Error> {
+    let column: Box<dyn ColumnView + 'a> = match column_type {
+        ColumnType::RBTree => Box::new(ByteColumn::new(
+            ByteTree::from_slice(slice, tree_params(key_type, value_type), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+        ColumnType::OneToOne => Box::new(ByteColumn::new(
+            ByteOneToOne::view_slice(slice, key_type.size(), value_type.size(), compare_encodings)?,
+            key_type,
+            value_type,
+        )),
+    };
+    Ok(column)
+}
+
+/// Encodes `data` after checking that it has the `expected` type
+fn encode(data: &Data, expected: DataType) -> Result<Vec<u8>, Error> {
+    if data.data_type() != expected {
+        return Err(Error::TypeMismatch {
+            expected,
+            got: data.data_type(),
+        });
+    }
+
+    let mut bytes = vec![0; expected.size()];
+    data.encode(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Encodes the value of the `bound` after checking that it has the `expected` type
+fn encode_bound(bound: Bound<Data>, expected: DataType) -> Result<Bound<Vec<u8>>, Error> {
+    Ok(match bound {
+        Bound::Included(data) => Bound::Included(encode(&data, expected)?),
+        Bound::Excluded(data) => Bound::Excluded(encode(&data, expected)?),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
+fn as_slice(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(bytes) => Bound::Included(bytes.as_slice()),
+        Bound::Excluded(bytes) => Bound::Excluded(bytes.as_slice()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// A tree, ordered by keys only
+impl<S> ColumnView for ByteColumn<ByteTree<S>>
+where
+    S: AsRef<[u8]>,
+{
+    fn get_key(&self, value: Data) -> Result<Option<Data>, Error> {
+        encode(&value, self.value_type)?;
+        //TODO: implement a slow but working variant
+        Ok(None)
+    }
+
+    fn get_value(&self, key: Data) -> Result<Option<Data>, Error> {
+        let key = encode(&key, self.key_type)?;
+        Ok(self
+            .container
+            .get(&key)
+            .map(|value| self.value_type.decode(value)))
+    }
+
+    fn pairs<'b>(&'b self) -> Pairs<'b> {
+        Box::new(self.container.iter().map(|pair| self.decode_pair(pair)))
+    }
+
+    fn pairs_by_value<'b>(&'b self) -> Option<Pairs<'b>> {
+        None
+    }
+
+    fn range<'b>(&'b self, (start, end): (Bound<Data>, Bound<Data>)) -> Result<Pairs<'b>, Error> {
+        let start = encode_bound(start, self.key_type)?;
+        let end = encode_bound(end, self.key_type)?;
+        Ok(Box::new(
+            self.container
+                .range(as_slice(&start), as_slice(&end))
+                .map(|pair| self.decode_pair(pair)),
+        ))
+    }
+
+    fn range_by_value<'b>(
+        &'b self,
+        (start, end): (Bound<Data>, Bound<Data>),
+    ) -> Result<Option<Pairs<'b>>, Error> {
+        encode_bound(start, self.value_type)?;
+        encode_bound(end, self.value_type)?;
+        Ok(None)
+    }
+
+    fn min_value(&self) -> Option<Data> {
+        self.container
+            .min_value()
+            .map(|value| self.value_type.decode(value))
+    }
+
+    fn max_value(&self) -> Option<Data> {
+        self.container
+            .max_value()
+            .map(|value| self.value_type.decode(value))
+    }
+}
+
+impl<S> Column for ByteColumn<ByteTree<S>>
+where
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    fn set(&mut self, key: Data, value: Data) -> Result<Option<Data>, Error> {
+        let key = encode(&key, self.key_type)?;
+        let value = encode(&value, self.value_type)?;
+        let old_value = self.container.insert(&key, &value)?;
+        Ok(old_value.map(|old_value| self.value_type.decode(&old_value)))
+    }
+
+    fn delete_by_key(&mut self, key: Data) -> Result<bool, Error> {
+        let key = encode(&key, self.key_type)?;
+        Ok(self.container.remove(&key).is_some())
+    }
+
+    fn delete_by_value(&mut self, value: Data) -> Result<bool, Error> {
+        let value = encode(&value, self.value_type)?;
+        // Values are not ordered, so all the pairs are scanned
+        let keys: Vec<Vec<u8>> = self
+            .container
+            .iter()
+            .filter(|(_, pair_value)| *pair_value == value.as_slice())
+            .map(|(key, _)| key.to_vec())
+            .collect();
+        for key in keys.iter() {
+            self.container.remove(key);
+        }
+        Ok(!keys.is_empty())
+    }
+}
+
+/// A `OneToOne` container, ordered both by keys and by values
+impl<S> ColumnView for ByteColumn<ByteOneToOne<S>>
+where
+    S: AsRef<[u8]>,
+{
+    fn get_key(&self, value: Data) -> Result<Option<Data>, Error> {
+        let value = encode(&value, self.value_type)?;
+        Ok(self
+            .container
+            .get_key(&value)
+            .map(|key| self.key_type.decode(key)))
+    }
+
+    fn get_value(&self, key: Data) -> Result<Option<Data>, Error> {
+        let key = encode(&key, self.key_type)?;
+        Ok(self
+            .container
+            .get_value(&key)
+            .map(|value| self.value_type.decode(value)))
+    }
+
+    fn pairs<'b>(&'b self) -> Pairs<'b> {
+        Box::new(self.container.iter().map(|pair| self.decode_pair(pair)))
+    }
+
+    fn pairs_by_value<'b>(&'b self) -> Option<Pairs<'b>> {
+        Some(Box::new(
+            self.container
+                .range_by_value(Bound::Unbounded, Bound::Unbounded)
+                .map(|pair| {
+                    let (key, value) = self.decode_pair(pair);
+                    (value, key)
+                }),
+        ))
+    }
+
+    fn range<'b>(&'b self, (start, end): (Bound<Data>, Bound<Data>)) -> Result<Pairs<'b>, Error> {
+        let start = encode_bound(start, self.key_type)?;
+        let end = encode_bound(end, self.key_type)?;
+        Ok(Box::new(
+            self.container
+                .range_by_key(as_slice(&start), as_slice(&end))
+                .map(|pair| self.decode_pair(pair)),
+        ))
+    }
+
+    fn range_by_value<'b>(
+        &'b self,
+        (start, end): (Bound<Data>, Bound<Data>),
+    ) -> Result<Option<Pairs<'b>>, Error> {
+        let start = encode_bound(start, self.value_type)?;
+        let end = encode_bound(end, self.value_type)?;
+        Ok(Some(Box::new(
+            self.container
+                .range_by_value(as_slice(&start), as_slice(&end))
+                .map(|pair| {
+                    let (key, value) = self.decode_pair(pair);
+                    (value, key)
+                }),
+        )))
+    }
+
+    fn min_value(&self) -> Option<Data> {
+        self.container
+            .first_by_value()
+            .map(|(_, value)| self.value_type.decode(value))
+    }
+
+    fn max_value(&self) -> Option<Data> {
+        self.container
+            .last_by_value()
+            .map(|(_, value)| self.value_type.decode(value))
+    }
+}
+
+impl<S> Column for ByteColumn<ByteOneToOne<S>>
+where
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    fn set(&mut self, key: Data, value: Data) -> Result<Option<Data>, Error> {
+        let key = encode(&key, self.key_type)?;
+        let value = encode(&value, self.value_type)?;
+
+        match self.container.try_insert_unique(&key, &value) {
+            Ok(old_value) => Ok(old_value.map(|old_value| self.value_type.decode(&old_value))),
+            Err(TryInsertError::ValueOccupied(_)) => Err(Error::NonUniqueSecondaryKey),
+            Err(TryInsertError::Tree(err)) => Err(err.into()),
+        }
+    }
+
+    fn delete_by_key(&mut self, key: Data) -> Result<bool, Error> {
+        let key = encode(&key, self.key_type)?;
+        Ok(self.container.remove_by_key(&key).is_some())
+    }
+
+    fn delete_by_value(&mut self, value: Data) -> Result<bool, Error> {
+        let value = encode(&value, self.value_type)?;
+        Ok(self.container.remove_by_value(&value).is_some())
+    }
+
+    fn verify(&self) -> Vec<Inconsistency<Data, Data>> {
+        self.container
+            .verify()
+            .into_iter()
+            .map(|inconsistency| {
+                inconsistency.map(
+                    |key| self.key_type.decode(&key),
+                    |value| self.value_type.decode(&value),
+                )
+            })
+            .collect()
+    }
+
+    fn repair(&mut self) -> Vec<(Data, Data)> {
+        self.container
+            .repair()
+            .into_iter()
+            .map(|(key, value)| self.decode_pair((key.as_slice(), value.as_slice())))
+            .collect()
+    }
+}
+
+impl<C> fmt::Debug for ByteColumn<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteColumn")
+            .field("key_type", &self.key_type)
+            .field("value_type", &self.value_type)
+            .finish()
+    }
+}
@@ -0,0 +1,294 @@
+use bytemuck::{cast_mut, cast_ref, cast_slice, cast_slice_mut};
+use solana_program::msg;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+use tinyvec::SliceVec;
+
+use account_fs::{FSReader, SegmentId};
+
+use super::raw::catalog::{CatalogEntry, CatalogHeader};
+use super::{DBReader, DataType, Error, FSCell, TableOptions, DB};
+
+/// A database of tables, stored in the same [`FS`](account_fs::FS)
+///
+/// The catalog maps table names to the header segments of the [`DB`]s, so a single [`SegmentId`]
+/// of the catalog identifies all of them. Each table has its own primary key. Tables are
+/// created with [`DB::init_in_segment_with_options`], so they support foreign keys between each
+/// other.
+pub struct Catalog<'long: 'short, 'short> {
+    fs: FSCell<'long, 'short>,
+    header: &'short mut CatalogHeader,
+    entries: SliceVec<'short, CatalogEntry>,
+    segment: SegmentId,
+}
+
+impl<'long: 'short, 'short> Catalog<'long, 'short> {
+    /// Constructs [`Catalog`] struct, assuming that the catalog is placed in the `segment`
+    pub fn from_segment(fs: FSCell<'long, 'short>, segment: SegmentId) -> Result<Self, Error> {
+        let catalog_segment = fs.borrow_mut().segment(&segment)?;
+
+        let result = Self::from_slice(catalog_segment);
+        if result.is_err() {
+            unsafe {
+                // # Safety
+                // The segment was borrowed in this function and the borrow is not used anymore
+                fs.borrow_mut().release_borrowed_segment(&segment);
+            }
+        }
+
+        let (header, entries) = result?;
+
+        Ok(Self {
+            fs,
+            header,
+            entries,
+            segment,
+        })
+    }
+
+    /// Initializes an empty [`Catalog`] for at most `max_tables` tables in the first suitable
+    /// segment.
+    ///
+    /// On success, this function returns [`Catalog`] struct and [`SegmentId`] of the segment,
+    /// there it was initialized in.
+    pub fn init_in_segment(
+        fs: FSCell<'long, 'short>,
+        max_tables: usize,
+    ) -> Result<(Self, SegmentId), Error> {
+        if u16::try_from(max_tables).is_err() {
+            return Err(Error::NoTablesLeft);
+        }
+
+        let mut borrowed_fs = fs.borrow_mut();
+        let segment = borrowed_fs.allocate_segment(CatalogHeader::size(max_tables))?;
+
+        // We've just successfully allocated this segment, so this operation is infailible;
+        let catalog_slice = borrowed_fs.segment(&segment).unwrap();
+
+        drop(borrowed_fs);
+
+        let (header, entries) = catalog_slice.split_at_mut(mem::size_of::<CatalogHeader>());
+
+        let header: &mut [[u8; mem::size_of::<CatalogHeader>()]] = cast_slice_mut(header);
+        let header: &mut CatalogHeader = cast_mut(&mut header[0]);
+
+        unsafe {
+            header.fill(max_tables);
+        }
+
+        let entries: &mut [CatalogEntry] = cast_slice_mut(entries);
+        let entries = SliceVec::from_slice_len(entries, 0);
+
+        msg!(
+            "Initialized catalog in segment: {} {}",
+            segment.pubkey,
+            segment.id
+        );
+
+        Ok((
+            Self {
+                fs,
+                header,
+                entries,
+                segment,
+            },
+            segment,
+        ))
+    }
+
+    fn from_slice(
+        slice: &'short mut [u8],
+    ) -> Result<(&'short mut CatalogHeader, SliceVec<'short, CatalogEntry>), Error> {
+        if slice.len() < mem::size_of::<CatalogHeader>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (header, entries) = slice.split_at_mut(mem::size_of::<CatalogHeader>());
+
+        let header: &mut [[u8; mem::size_of::<CatalogHeader>()]] = cast_slice_mut(header);
+        let header: &mut CatalogHeader = cast_mut(&mut header[0]);
+
+        if !header.check_magic()
+            || entries.len() < header.entries_size()
+            || header.table_count() > header.table_max()
+        {
+            return Err(Error::WrongSegment);
+        }
+
+        let entries: &mut [CatalogEntry] = cast_slice_mut(&mut entries[..header.entries_size()]);
+        let entries = SliceVec::from_slice_len(entries, header.table_count());
+
+        Ok((header, entries))
+    }
+
+    /// Returns mapping from table names to [`SegmentId`]s of their headers
+    pub fn tables(&self) -> BTreeMap<String, SegmentId> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.name(), entry.segment_id()))
+            .collect()
+    }
+
+    /// Returns [`SegmentId`] of the header of the table `name`
+    pub fn table_segment(&self, name: &str) -> Result<SegmentId, Error> {
+        self.position(name)
+            .map(|position| self.entries[position].segment_id())
+            .ok_or(Error::NoSuchTable)
+    }
+
+    /// Opens the table `name`
+    pub fn open_table(&self, name: &str) -> Result<DB<'long, 'short>, Error> {
+        DB::from_segment(self.fs.clone(), self.table_segment(name)?)
+    }
+
+    /// Creates a new table `name` with the given parameters and registers it in the catalog.
+    ///
+    /// See [`DB::init_in_segment_with_options`] for the description of the parameters.
+    pub fn create_table(
+        &mut self,
+        name: &str,
+        max_columns: usize,
+        max_rows: usize,
+        primary_key_type: DataType,
+        options: TableOptions,
+    ) -> Result<DB<'long, 'short>, Error> {
+        if name.len() > CatalogEntry::MAX_NAME_LEN {
+            return Err(Error::NameTooLong);
+        }
+
+        if self.position(name).is_some() {
+            return Err(Error::TableAlreadyExists);
+        }
+
+        if self.entries.len() == self.header.table_max() {
+            return Err(Error::NoTablesLeft);
+        }
+
+        let (db, segment) = DB::init_in_segment_with_options(
+            self.fs.clone(),
+            name,
+            max_columns,
+            max_rows,
+            primary_key_type,
+            options,
+        )?;
+
+        self.entries
+            .push(unsafe { CatalogEntry::new(name, segment) });
+
+        unsafe {
+            self.header.set_table_count(self.entries.len());
+        }
+
+        Ok(db)
+    }
+
+    /// Completely deletes the table `name` with [`DB::drop_db`] and removes it from the catalog
+    pub fn drop_table(&mut self, name: &str) -> Result<(), Error> {
+        let db = self.open_table(name)?;
+        db.drop_db()?;
+
+        // `open_table` succeeded, so the table is present
+        let position = self.position(name).unwrap();
+        self.entries.remove(position);
+
+        unsafe {
+            self.header.set_table_count(self.entries.len());
+        }
+
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name() == name)
+    }
+}
+
+impl<'long, 'short> Drop for Catalog<'long, 'short> {
+    fn drop(&mut self) {
+        unsafe {
+            // # Safety
+            // The catalog segment was splitted into two parts: `header` and `entries`
+            // Both will be dropped, so there are no dangling pointers
+            self.fs.borrow_mut().release_borrowed_segment(&self.segment);
+        }
+    }
+}
+
+impl<'long, 'short> fmt::Debug for Catalog<'long, 'short> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Catalog")
+            .field("header", &self.header)
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+/// Read-only access to the [`Catalog`], which works on top of the [`FSReader`], see [`DBReader`]
+pub struct CatalogReader<'long: 'short, 'short> {
+    fs: Rc<FSReader<'long, 'short>>,
+    entries: &'short [CatalogEntry],
+}
+
+impl<'long: 'short, 'short> CatalogReader<'long, 'short> {
+    /// Constructs [`CatalogReader`], assuming that the catalog is placed in the `segment`
+    pub fn from_segment(
+        fs: Rc<FSReader<'long, 'short>>,
+        segment: SegmentId,
+    ) -> Result<Self, Error> {
+        let catalog_segment = fs.segment(&segment)?;
+
+        if catalog_segment.len() < mem::size_of::<CatalogHeader>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (header, entries) = catalog_segment.split_at(mem::size_of::<CatalogHeader>());
+
+        let header: &[[u8; mem::size_of::<CatalogHeader>()]] = cast_slice(header);
+        let header: &CatalogHeader = cast_ref(&header[0]);
+
+        if !header.check_magic()
+            || entries.len() < header.entries_size()
+            || header.table_count() > header.table_max()
+        {
+            return Err(Error::WrongSegment);
+        }
+
+        let entries: &[CatalogEntry] = cast_slice(&entries[..header.entries_size()]);
+        let entries = &entries[..header.table_count()];
+
+        Ok(Self { fs, entries })
+    }
+
+    /// Returns mapping from table names to [`SegmentId`]s of their headers
+    pub fn tables(&self) -> BTreeMap<String, SegmentId> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.name(), entry.segment_id()))
+            .collect()
+    }
+
+    /// Returns [`SegmentId`] of the header of the table `name`
+    pub fn table_segment(&self, name: &str) -> Result<SegmentId, Error> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name() == name)
+            .map(CatalogEntry::segment_id)
+            .ok_or(Error::NoSuchTable)
+    }
+
+    /// Opens the table `name` for reading
+    pub fn open_table(&self, name: &str) -> Result<DBReader<'long, 'short>, Error> {
+        DBReader::from_segment(self.fs.clone(), self.table_segment(name)?)
+    }
+}
+
+impl<'long, 'short> fmt::Debug for CatalogReader<'long, 'short> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CatalogReader")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
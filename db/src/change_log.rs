@@ -0,0 +1,171 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use account_fs::SegmentId;
+
+use super::raw::change_log::ChangeLog;
+use super::{ColumnId, Data, Error, FSCell, DB};
+
+/// Kind of the change, recorded in the change log
+#[derive(PartialEq, Copy, Clone, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub enum Operation {
+    /// The value was set
+    Set,
+    /// The value was deleted
+    Delete,
+}
+
+/// Entry of the change log, see [`DB::changes_since`]
+#[derive(PartialEq, Clone, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub struct Change {
+    /// Sequence number of the change, unique within the table
+    pub sequence: u64,
+    /// Kind of the change
+    pub operation: Operation,
+    /// Changed column
+    pub column: ColumnId,
+    /// Primary key of the changed row
+    pub key: Data,
+    /// Value before the change
+    pub old_value: Option<Data>,
+    /// Value after the change
+    pub new_value: Option<Data>,
+}
+
+/// Change log record, sequence number is stored by the [`ChangeLog`] itself
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Record {
+    operation: Operation,
+    column: ColumnId,
+    key: Data,
+    old_value: Option<Data>,
+    new_value: Option<Data>,
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Returns all the stored changes with sequence numbers not less than `sequence`.
+    ///
+    /// The change log is a ring buffer, so the oldest changes may be already evicted, use the
+    /// sequence number of the first returned [`Change`] to detect this.
+    pub fn changes_since(&self, sequence: u64) -> Result<Vec<Change>, Error> {
+        let log_segment = self.change_log_segment().ok_or(Error::NoChangeLog)?;
+
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(&log_segment)?;
+        let records = ChangeLog::from_slice(slice).map(|log| log.records_since(sequence));
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&log_segment);
+        }
+
+        records?
+            .into_iter()
+            .map(|(sequence, bytes)| {
+                let record =
+                    Record::deserialize(&mut bytes.as_slice()).map_err(|_| Error::WrongSegment)?;
+                Ok(Change {
+                    sequence,
+                    operation: record.operation,
+                    column: record.column,
+                    key: record.key,
+                    old_value: record.old_value,
+                    new_value: record.new_value,
+                })
+            })
+            .collect()
+    }
+
+    /// [`SegmentId`] of the change log, if it is enabled
+    pub(crate) fn change_log_segment(&self) -> Option<SegmentId> {
+        self.metadata.as_ref()?.change_log
+    }
+
+    /// Checks that any change of the column `column_id` fits in the change log
+    pub(crate) fn check_change_log(&self, column_id: ColumnId) -> Result<(), Error> {
+        let log_segment = match self.change_log_segment() {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+
+        let (_, header) = self.column_header(column_id)?;
+        // operation + column + key + two optional values, each `Data` has a one-byte tag
+        let max_record_len = 1
+            + 4
+            + (1 + self.index.primary_key_type().size())
+            + 2 * (2 + header.value_type().size());
+
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(&log_segment)?;
+        let fits = ChangeLog::from_slice(slice).map(|log| log.fits(max_record_len));
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&log_segment);
+        }
+
+        if fits? {
+            Ok(())
+        } else {
+            Err(Error::ChangeLogOverflow)
+        }
+    }
+
+    /// Appends the change to the change log, if it is enabled
+    pub(crate) fn log_change(
+        &self,
+        operation: Operation,
+        column: ColumnId,
+        key: Data,
+        old_value: Option<Data>,
+        new_value: Option<Data>,
+    ) -> Result<(), Error> {
+        let log_segment = match self.change_log_segment() {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+
+        let record = Record {
+            operation,
+            column,
+            key,
+            old_value,
+            new_value,
+        }
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible");
+
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(&log_segment)?;
+        let result = ChangeLog::from_slice(slice).and_then(|mut log| log.push(&record));
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&log_segment);
+        }
+
+        result.map(|_| ())
+    }
+}
+
+/// Allocates and initializes a change log segment with the given `capacity`
+pub(crate) fn init_change_log(fs: &FSCell<'_, '_>, capacity: usize) -> Result<SegmentId, Error> {
+    let mut fs = fs.borrow_mut();
+    let segment = fs.allocate_segment(ChangeLog::size(capacity))?;
+
+    // We've just successfully allocated this segment, so this operation is infailible;
+    let slice = fs.segment(&segment).unwrap();
+    let result = ChangeLog::init(slice).map(|_| ());
+    unsafe {
+        // # Safety
+        // `slice` is not used after this point
+        fs.release_borrowed_segment(&segment);
+    }
+
+    match result {
+        Ok(()) => Ok(segment),
+        Err(err) => {
+            fs.deallocate_segment(&segment)?;
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use solana_program::pubkey::Pubkey;
+
+use super::{Error, LongString, MediumString, ShortString};
+
+/// Fixed-size memcomparable encoding of the values, stored in the columns
+///
+/// Encoded values are compared as byte arrays, so the encoding must preserve the order of the
+/// values: `a < b` if and only if `encode(a) < encode(b)`. The whole `out` slice is written,
+/// unused bytes are zeroed.
+pub trait ByteCodec: Sized {
+    /// Maximal length of the encoding, it is the size of the [`DataType`](crate::DataType)
+    const MAX_ENCODED_SIZE: usize;
+
+    /// Writes the encoding of the value to `out`, returns [`Error::ValueTooLong`] if it does not
+    /// fit.
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error>;
+    /// Decodes the value, written by [`ByteCodec::encode`] to a slice of the same length
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+impl ByteCodec for i32 {
+    const MAX_ENCODED_SIZE: usize = 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        // Flipping the sign bit puts negative values before the positive ones
+        write_fixed(&(*self as u32 ^ (1 << 31)).to_be_bytes(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        (u32::from_be_bytes(read_fixed(bytes)) ^ (1 << 31)) as i32
+    }
+}
+
+impl ByteCodec for i64 {
+    const MAX_ENCODED_SIZE: usize = 8;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        write_fixed(&(*self as u64 ^ (1 << 63)).to_be_bytes(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        (u64::from_be_bytes(read_fixed(bytes)) ^ (1 << 63)) as i64
+    }
+}
+
+impl ByteCodec for Pubkey {
+    const MAX_ENCODED_SIZE: usize = 32;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        write_fixed(self.as_ref(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Pubkey::new_from_array(read_fixed(bytes))
+    }
+}
+
+// Lengths of the strings are limited by their `TryFrom` impls, the longest string is followed by
+// the length byte
+impl ByteCodec for ShortString {
+    const MAX_ENCODED_SIZE: usize = 16 - 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        encode_bytes(self.as_ref().as_bytes(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        ShortString::try_from(decode_str(bytes)).expect(CORRUPTED)
+    }
+}
+
+impl ByteCodec for MediumString {
+    const MAX_ENCODED_SIZE: usize = 64 - 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        encode_bytes(self.as_ref().as_bytes(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        MediumString::try_from(decode_str(bytes)).expect(CORRUPTED)
+    }
+}
+
+impl ByteCodec for LongString {
+    const MAX_ENCODED_SIZE: usize = 256 - 4;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        encode_bytes(self.as_ref().as_bytes(), out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        LongString::try_from(decode_str(bytes)).expect(CORRUPTED)
+    }
+}
+
+pub(crate) const CORRUPTED: &str = "Malformed column value, it is a sign of data corruption";
+
+/// Writes `bytes` to the beginning of `out` and zeroes the rest
+pub(crate) fn write_fixed(bytes: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    if bytes.len() > out.len() {
+        return Err(Error::ValueTooLong);
+    }
+
+    let (head, tail) = out.split_at_mut(bytes.len());
+    head.copy_from_slice(bytes);
+    tail.fill(0);
+    Ok(())
+}
+
+pub(crate) fn read_fixed<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes[..N].try_into().expect(CORRUPTED)
+}
+
+/// Encodes a variable-length byte string: the bytes are padded with zeros and followed by the
+/// length in the last byte of `out`.
+///
+/// If one string is a prefix of the other, they differ either in the first byte after the prefix,
+/// which is zero for the shorter string, or in the length, so the order is preserved.
+pub(crate) fn encode_bytes(bytes: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    let (len_byte, data) = out.split_last_mut().ok_or(Error::ValueTooLong)?;
+    let len = u8::try_from(bytes.len()).map_err(|_| Error::ValueTooLong)?;
+
+    write_fixed(bytes, data)?;
+    *len_byte = len;
+    Ok(())
+}
+
+pub(crate) fn decode_bytes(bytes: &[u8]) -> &[u8] {
+    let (&len, data) = bytes.split_last().expect(CORRUPTED);
+    &data[..len as usize]
+}
+
+fn decode_str(bytes: &[u8]) -> &str {
+    std::str::from_utf8(decode_bytes(bytes)).expect(CORRUPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataType;
+    use pretty_assertions::assert_eq;
+
+    fn encoded<T: ByteCodec, const N: usize>(value: &T) -> [u8; N] {
+        let mut bytes = [0xAA; N];
+        value.encode(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn integers() {
+        let values = [i64::MIN, -300, -1, 0, 1, 255, 256, i64::MAX];
+
+        let encodings: Vec<[u8; 8]> = values.iter().map(encoded).collect();
+        let mut sorted = encodings.clone();
+        sorted.sort();
+        assert_eq!(encodings, sorted);
+
+        for (value, bytes) in values.iter().zip(encodings.iter()) {
+            assert_eq!(i64::decode(bytes), *value);
+        }
+
+        let bytes: [u8; 8] = encoded(&-5_i32);
+        assert_eq!(&bytes[4..], &[0, 0, 0, 0]);
+        assert_eq!(i32::decode(&bytes), -5);
+    }
+
+    #[test]
+    fn strings() {
+        let short = |value: &str| ShortString::try_from(value).unwrap();
+        let values = [
+            short(""),
+            short("\0"),
+            short("\0\0"),
+            short("a"),
+            short("a\0"),
+            short("a\0b"),
+            short("ab"),
+            short("b"),
+        ];
+
+        let encodings: Vec<[u8; 16]> = values.iter().map(encoded).collect();
+        let mut sorted = encodings.clone();
+        sorted.sort();
+        assert_eq!(encodings, sorted);
+
+        for (value, bytes) in values.iter().zip(encodings.iter()) {
+            assert_eq!(&ShortString::decode(bytes), value);
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        // Sizes are picked from `MAX_ENCODED_SIZE`, a change of them changes the on-disk format
+        let sizes: Vec<usize> = [
+            DataType::Int,
+            DataType::Pubkey,
+            DataType::ShortString,
+            DataType::MediumString,
+            DataType::LongString,
+            DataType::Int64,
+            DataType::Composite,
+            DataType::Blob,
+        ]
+        .iter()
+        .map(|data_type| data_type.size())
+        .collect();
+        assert_eq!(sizes, vec![4, 32, 12, 60, 252, 8, 125, 40]);
+    }
+
+    #[test]
+    fn max_encoded_size() {
+        let value = ShortString::try_from("a".repeat(11).as_str()).unwrap();
+        let mut bytes = [0; ShortString::MAX_ENCODED_SIZE];
+        assert!(value.encode(&mut bytes).is_ok());
+
+        let value = LongString::try_from("a".repeat(251).as_str()).unwrap();
+        let mut bytes = [0; LongString::MAX_ENCODED_SIZE];
+        assert!(value.encode(&mut bytes).is_ok());
+    }
+
+    #[test]
+    fn too_long() {
+        let value = MediumString::try_from("too long for 16 bytes").unwrap();
+        let mut bytes = [0; 16];
+        assert_eq!(value.encode(&mut bytes).unwrap_err(), Error::ValueTooLong);
+
+        let mut bytes = [0; 2];
+        assert_eq!(1_i32.encode(&mut bytes).unwrap_err(), Error::ValueTooLong);
+    }
+}
@@ -1,26 +1,73 @@
 use std::fmt::Debug;
+use std::ops::Bound;
 
 use super::Data;
 use super::Error;
+use super::Inconsistency;
 
-/// An interface for operations with columns
+/// Boxed iterator over the pairs of a column
+pub type Pairs<'a> = Box<dyn Iterator<Item = (Data, Data)> + 'a>;
+
+/// An interface for read-only operations with columns
 ///
-/// Any data structure can be used as a column in the [`DB`](crate::DB), as long as it implements
-/// this trait
-pub trait Column: Debug {
-    fn get_key(&self, value: Data) -> Option<Data>;
-    fn get_value(&self, key: Data) -> Option<Data>;
-    fn set(&mut self, key: Data, value: Data) -> Result<Option<Data>, Error>;
-    fn delete_by_key(&mut self, key: Data) -> bool;
-    fn delete_by_value(&mut self, value: Data) -> bool;
+/// It is implemented both by the columns of the [`DB`](crate::DB) and by the read-only views of
+/// the column segments, used by the [`DBReader`](crate::DBReader).
+///
+/// Methods, taking [`Data`], return [`Error::TypeMismatch`] if its type does not match the type
+/// of the keys or values of the column.
+pub trait ColumnView: Debug {
+    fn get_key(&self, value: Data) -> Result<Option<Data>, Error>;
+    fn get_value(&self, key: Data) -> Result<Option<Data>, Error>;
+    /// Iterates over all `key - value` pairs of the column in ascending order of keys
+    fn pairs<'a>(&'a self) -> Pairs<'a>;
+    /// Iterates over all `value - key` pairs of the column in ascending order of values, if the
+    /// column is ordered by values
+    fn pairs_by_value<'a>(&'a self) -> Option<Pairs<'a>>;
+    /// Iterates over `key - value` pairs with the keys in `range` in ascending order of keys
+    ///
+    /// The iteration starts from the lower bound, found by a tree search, so the pairs before the
+    /// range are not visited.
+    fn range<'a>(&'a self, range: (Bound<Data>, Bound<Data>)) -> Result<Pairs<'a>, Error>;
+    /// Iterates over `value - key` pairs with the values in `range` in ascending order of values,
+    /// if the column is ordered by values
+    fn range_by_value<'a>(
+        &'a self,
+        range: (Bound<Data>, Bound<Data>),
+    ) -> Result<Option<Pairs<'a>>, Error>;
+    /// Returns the smallest value of the column
+    ///
+    /// It is O(log n) for the columns ordered by values and O(1) for the other columns.
+    fn min_value(&self) -> Option<Data>;
+    /// Returns the largest value of the column
+    ///
+    /// It is O(log n) for the columns ordered by values and O(1) for the other columns.
+    fn max_value(&self) -> Option<Data>;
     //fn keys(&self) -> KeysAscendingIterator;
     //fn values(&self) -> ValuesAscendingIterator;
-    //fn pairs(&self) -> PairsAscendingIterator;
     //fn keys_descending(&self) -> KeysDescendingIterator;
     //fn values_descending(&self) -> ValuesDescendingIterator;
     //fn pairs_descending(&self) -> PairsDescendingIterator;
 }
 
+/// An interface for operations with columns
+///
+/// Any data structure can be used as a column in the [`DB`](crate::DB), as long as it implements
+/// this trait
+pub trait Column: ColumnView {
+    fn set(&mut self, key: Data, value: Data) -> Result<Option<Data>, Error>;
+    fn delete_by_key(&mut self, key: Data) -> Result<bool, Error>;
+    fn delete_by_value(&mut self, value: Data) -> Result<bool, Error>;
+    /// Checks the internal consistency of the column, the columns with a single tree are always
+    /// consistent
+    fn verify(&self) -> Vec<Inconsistency<Data, Data>> {
+        Vec::new()
+    }
+    /// Restores the internal consistency of the column, returns the pairs, which were removed
+    fn repair(&mut self) -> Vec<(Data, Data)> {
+        Vec::new()
+    }
+}
+
 //pub struct KeysAscendingIterator {}
 //pub struct ValuesAscendingIterator {}
 //pub struct PairsAscendingIterator {}
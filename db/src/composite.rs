@@ -0,0 +1,243 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+use super::codec::{decode_bytes, encode_bytes, ByteCodec};
+use super::{Data, DataType, Error, LongString, MediumString, ShortString};
+
+/// Primary key, composed of several values
+///
+/// Components are stored in a memcomparable encoding, so the keys are ordered component by
+/// component and all the keys with a common prefix are stored next to each other. Each component
+/// is prefixed by its [`DataType`], integers are stored big-endian with the sign bit flipped,
+/// strings are terminated by `0x00 0x01` with `0x00` bytes escaped as `0x00 0xFF`.
+#[derive(
+    PartialEq,
+    Clone,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct CompositeKey(Vec<u8>);
+
+impl CompositeKey {
+    /// Maximal length of the encoded key, 128 is a maximal total length, 4 - is a u32-encoded
+    /// length of the key
+    pub const MAX_LEN: usize = 128 - 4;
+
+    /// Encodes the given components to the [`CompositeKey`]
+    pub fn new(components: &[Data]) -> Result<Self, Error> {
+        let mut bytes = Vec::new();
+        for component in components {
+            encode_component(component, &mut bytes)?;
+        }
+
+        if bytes.len() > Self::MAX_LEN {
+            return Err(Error::KeyTooLong);
+        }
+
+        Ok(Self(bytes))
+    }
+
+    /// Decodes components of the key
+    pub fn components(&self) -> Vec<Data> {
+        let mut bytes = self.0.as_slice();
+        let mut components = Vec::new();
+
+        while !bytes.is_empty() {
+            let component = decode_component(&mut bytes)
+                .expect("Malformed composite key, it is a sign of data corruption");
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Returns types of the components
+    pub fn schema(&self) -> Vec<DataType> {
+        self.components().iter().map(Data::data_type).collect()
+    }
+
+    /// Checks if the first components of the key are equal to the components of `prefix`
+    pub fn starts_with(&self, prefix: &CompositeKey) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+/// The key is already memcomparable, so it is just padded to the fixed size
+impl ByteCodec for CompositeKey {
+    const MAX_ENCODED_SIZE: usize = Self::MAX_LEN + 1;
+
+    fn encode(&self, out: &mut [u8]) -> Result<(), Error> {
+        encode_bytes(&self.0, out)
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self(decode_bytes(bytes).to_vec())
+    }
+}
+
+fn encode_component(component: &Data, out: &mut Vec<u8>) -> Result<(), Error> {
+    out.push(u8::from(component.data_type()));
+
+    match component {
+        Data::Int(value) => out.extend_from_slice(&(*value as u32 ^ (1 << 31)).to_be_bytes()),
+        Data::Int64(value) => out.extend_from_slice(&(*value as u64 ^ (1 << 63)).to_be_bytes()),
+        Data::Pubkey(value) => out.extend_from_slice(value.as_ref()),
+        Data::ShortString(value) => encode_str(value.as_ref(), out),
+        Data::MediumString(value) => encode_str(value.as_ref(), out),
+        Data::LongString(value) => encode_str(value.as_ref(), out),
+        Data::Composite(_) | Data::Blob(_) => return Err(Error::WrongKeySchema),
+    }
+
+    Ok(())
+}
+
+fn decode_component(bytes: &mut &[u8]) -> Option<Data> {
+    let (&tag, rest) = bytes.split_first()?;
+    *bytes = rest;
+
+    match DataType::try_from(tag).ok()? {
+        DataType::Int => {
+            let value = u32::from_be_bytes(take(bytes)?) ^ (1 << 31);
+            Some(Data::Int(value as i32))
+        }
+        DataType::Int64 => {
+            let value = u64::from_be_bytes(take(bytes)?) ^ (1 << 63);
+            Some(Data::Int64(value as i64))
+        }
+        DataType::Pubkey => Some(Data::Pubkey(Pubkey::new_from_array(take(bytes)?))),
+        DataType::ShortString => ShortString::try_from(decode_str(bytes)?.as_str())
+            .ok()
+            .map(Data::ShortString),
+        DataType::MediumString => MediumString::try_from(decode_str(bytes)?.as_str())
+            .ok()
+            .map(Data::MediumString),
+        DataType::LongString => LongString::try_from(decode_str(bytes)?.as_str())
+            .ok()
+            .map(Data::LongString),
+        DataType::Composite | DataType::Blob => None,
+    }
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    if bytes.len() < N {
+        return None;
+    }
+
+    let (head, rest) = bytes.split_at(N);
+    *bytes = rest;
+    head.try_into().ok()
+}
+
+fn encode_str(value: &str, out: &mut Vec<u8>) {
+    for &byte in value.as_bytes() {
+        out.push(byte);
+        if byte == 0x00 {
+            out.push(0xFF);
+        }
+    }
+    out.extend_from_slice(&[0x00, 0x01]);
+}
+
+fn decode_str(bytes: &mut &[u8]) -> Option<String> {
+    let mut result = Vec::new();
+
+    loop {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        if byte != 0x00 {
+            result.push(byte);
+            continue;
+        }
+
+        let (&marker, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        match marker {
+            0xFF => result.push(0x00),
+            0x01 => return String::from_utf8(result).ok(),
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ordering() {
+        let key = |game: i32, player: &str| {
+            CompositeKey::new(&[
+                Data::Int(game),
+                Data::ShortString(ShortString::try_from(player).unwrap()),
+            ])
+            .unwrap()
+        };
+
+        let mut keys = vec![
+            key(1, "bob"),
+            key(-5, "zed"),
+            key(1, "alice"),
+            key(0, ""),
+            key(1, "al"),
+            key(i32::MIN, "a"),
+            key(i32::MAX, "a"),
+        ];
+        keys.sort();
+
+        assert_eq!(
+            keys,
+            vec![
+                key(i32::MIN, "a"),
+                key(-5, "zed"),
+                key(0, ""),
+                key(1, "al"),
+                key(1, "alice"),
+                key(1, "bob"),
+                key(i32::MAX, "a"),
+            ]
+        );
+
+        let prefix = CompositeKey::new(&[Data::Int(1)]).unwrap();
+        assert!(key(1, "bob").starts_with(&prefix));
+        assert!(!key(-5, "zed").starts_with(&prefix));
+    }
+
+    #[test]
+    fn round_trip() {
+        let components = vec![
+            Data::Int64(-42),
+            Data::Pubkey(Pubkey::new_unique()),
+            Data::MediumString(MediumString::try_from("with\0zero").unwrap()),
+            Data::Int(7),
+        ];
+        let key = CompositeKey::new(&components).unwrap();
+
+        assert_eq!(key.components(), components);
+        assert_eq!(
+            key.schema(),
+            vec![
+                DataType::Int64,
+                DataType::Pubkey,
+                DataType::MediumString,
+                DataType::Int
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_keys() {
+        let inner = CompositeKey::new(&[Data::Int(0)]).unwrap();
+        let err = CompositeKey::new(&[Data::Composite(inner)]).unwrap_err();
+        assert_eq!(err, Error::WrongKeySchema);
+    }
+}
@@ -0,0 +1,118 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::ops::Bound;
+
+use super::{ColumnId, ColumnView, Data, Error, DB};
+
+/// Position of the paginated scan of a column, see [`DB::scan_from`]
+///
+/// The cursor stores the last seen primary key, so it stays valid if the rows are added or
+/// deleted between the pages.
+#[derive(PartialEq, Clone, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub struct Cursor {
+    column: ColumnId,
+    last_key: Option<Data>,
+}
+
+impl Cursor {
+    /// Cursor at the beginning of the column `column`
+    pub fn new(column: ColumnId) -> Self {
+        Self {
+            column,
+            last_key: None,
+        }
+    }
+
+    /// The scanned column
+    pub fn column(&self) -> ColumnId {
+        self.column
+    }
+}
+
+/// A page of `primary_key - value` pairs, returned by [`DB::scan_from`]
+#[derive(PartialEq, Clone, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub struct Page {
+    column: ColumnId,
+    /// Pairs in ascending order of the primary keys
+    pub pairs: Vec<(Data, Data)>,
+    /// Cursor of the next page, `None` if this page is the last one
+    pub next: Option<Cursor>,
+}
+
+impl Page {
+    /// Removes the last pairs from the page, until its serialized size is not greater than
+    /// `max_size`, the removed pairs will be returned in the next page.
+    ///
+    /// Returns [`Error::PageOverflow`] if even a page with a single pair is too large.
+    pub fn truncate_to_size(&mut self, max_size: usize) -> Result<(), Error> {
+        while self.serialized_size() > max_size {
+            if self.pairs.len() <= 1 {
+                return Err(Error::PageOverflow);
+            }
+
+            self.pairs.pop();
+            self.next = Some(Cursor {
+                column: self.column,
+                last_key: self.pairs.last().map(|(key, _)| key.clone()),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.try_to_vec()
+            .expect("Serialization to Vec should be infallible")
+            .len()
+    }
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Returns at most `limit` `primary_key - value` pairs of the column after the `cursor`
+    /// position, and the cursor of the next page.
+    ///
+    /// Returns [`Error::TypeMismatch`] if the cursor was made for a table with another primary key
+    /// type.
+    pub fn scan_from(&self, cursor: &Cursor, limit: usize) -> Result<Page, Error> {
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, cursor.column)?;
+
+        page_from(&*accessed_columns[&cursor.column], cursor, limit)
+    }
+}
+
+/// Reads the page of [`DB::scan_from`] from the `column` of the cursor
+pub(crate) fn page_from<C: ColumnView + ?Sized>(
+    column: &C,
+    cursor: &Cursor,
+    limit: usize,
+) -> Result<Page, Error> {
+    // The scan starts right after the last seen key, found by a tree search
+    let start = match &cursor.last_key {
+        Some(last_key) => Bound::Excluded(last_key.clone()),
+        None => Bound::Unbounded,
+    };
+
+    let mut pairs: Vec<(Data, Data)> = column
+        .range((start, Bound::Unbounded))?
+        .take(limit.saturating_add(1))
+        .collect();
+
+    let next = if pairs.len() > limit {
+        pairs.truncate(limit);
+        Some(Cursor {
+            column: cursor.column,
+            last_key: match pairs.last() {
+                Some((key, _)) => Some(key.clone()),
+                None => cursor.last_key.clone(),
+            },
+        })
+    } else {
+        None
+    };
+
+    Ok(Page {
+        column: cursor.column,
+        pairs,
+        next,
+    })
+}
@@ -3,9 +3,11 @@ use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
 use serde::{Deserialize, Serialize};
 use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
 
-use super::Column as ColumnTrait;
-use super::ColumnType;
+use super::BlobHandle;
+use super::ByteCodec;
+use super::CompositeKey;
 use super::Error;
 use solcery_impl_generator::generate_column_impls;
 
@@ -24,6 +26,12 @@ use solcery_impl_generator::generate_column_impls;
 /// Container for short-sized (16-byte) [`String`]
 pub struct ShortString(String);
 
+impl AsRef<str> for ShortString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl TryFrom<&str> for ShortString {
     type Error = TooLong;
 
@@ -53,6 +61,12 @@ impl TryFrom<&str> for ShortString {
 )]
 pub struct MediumString(String);
 
+impl AsRef<str> for MediumString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl TryFrom<&str> for MediumString {
     type Error = TooLong;
 
@@ -82,6 +96,12 @@ impl TryFrom<&str> for MediumString {
 )]
 pub struct LongString(String);
 
+impl AsRef<str> for LongString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl TryFrom<&str> for LongString {
     type Error = TooLong;
 
@@ -98,8 +118,8 @@ impl TryFrom<&str> for LongString {
 
 #[generate_column_impls(
     Data,
-    ColumnTrait,
     Error,
+    ByteCodec,
     derives(
         PartialEq,
         Clone,
@@ -128,22 +148,98 @@ impl TryFrom<&str> for LongString {
 )]
 #[repr(u8)]
 /// Data types, that can be stored in the [`DB`](crate::DB)
+///
+/// Values are stored in their [`ByteCodec`] encodings, the size of a type is its
+/// [`ByteCodec::MAX_ENCODED_SIZE`]. Column containers take the sizes at runtime, so a new type
+/// does not add container instantiations to the program.
 pub enum DataType {
     /// [`i32`]
-    #[type_params(i32, 4)]
+    #[type_params(i32)]
     Int,
     /// [`Pubkey`]
-    #[type_params(Pubkey, 64)]
+    #[type_params(Pubkey)]
     Pubkey,
     /// 16 bytes long [`String`]
-    #[type_params(ShortString, 16)]
+    #[type_params(ShortString)]
     ShortString,
     /// 64 bytes long [`String`]
-    #[type_params(MediumString, 64)]
+    #[type_params(MediumString)]
     MediumString,
     /// 256 bytes long [`String`]
-    #[type_params(LongString, 256)]
+    #[type_params(LongString)]
     LongString,
+    /// [`i64`]
+    #[type_params(i64)]
+    Int64,
+    /// [`CompositeKey`], a tuple of values, usable as a primary key
+    #[type_params(CompositeKey)]
+    Composite,
+    /// [`BlobHandle`] of a variable-length payload
+    #[type_params(BlobHandle)]
+    Blob,
+}
+
+impl Data {
+    /// Converts the value to the `target` [`DataType`].
+    ///
+    /// Integers can be widened, narrowed if the value fits, and converted to and from strings.
+    /// Strings can be converted to other string types if they fit, [`Pubkey`] can be converted to
+    /// and from its base58 string representation.
+    pub fn convert(self, target: DataType) -> Result<Data, Error> {
+        if self.data_type() == target {
+            return Ok(self);
+        }
+
+        match (self, target) {
+            (Data::Int(value), DataType::Int64) => Ok(Data::Int64(i64::from(value))),
+            (Data::Int64(value), DataType::Int) => i32::try_from(value)
+                .map(Data::Int)
+                .map_err(|_| Error::LossyConversion),
+            (Data::Int(value), target) => string_as(&value.to_string(), target),
+            (Data::Int64(value), target) => string_as(&value.to_string(), target),
+            (Data::Pubkey(value), target) => string_as(&value.to_string(), target),
+            (Data::ShortString(value), target) => str_as(value.as_ref(), target),
+            (Data::MediumString(value), target) => str_as(value.as_ref(), target),
+            (Data::LongString(value), target) => str_as(value.as_ref(), target),
+            (Data::Composite(_), _) | (Data::Blob(_), _) => Err(Error::UnsupportedConversion),
+        }
+    }
+}
+
+/// Converts string representation of a non-string value to one of the string types
+fn string_as(value: &str, target: DataType) -> Result<Data, Error> {
+    match target {
+        DataType::ShortString | DataType::MediumString | DataType::LongString => {
+            str_as(value, target)
+        }
+        _ => Err(Error::UnsupportedConversion),
+    }
+}
+
+fn str_as(value: &str, target: DataType) -> Result<Data, Error> {
+    match target {
+        DataType::Int => value
+            .parse()
+            .map(Data::Int)
+            .map_err(|_| Error::LossyConversion),
+        DataType::Int64 => value
+            .parse()
+            .map(Data::Int64)
+            .map_err(|_| Error::LossyConversion),
+        DataType::Pubkey => Pubkey::from_str(value)
+            .map(Data::Pubkey)
+            .map_err(|_| Error::LossyConversion),
+        DataType::ShortString => ShortString::try_from(value)
+            .map(Data::ShortString)
+            .map_err(|_| Error::LossyConversion),
+        DataType::MediumString => MediumString::try_from(value)
+            .map(Data::MediumString)
+            .map_err(|_| Error::LossyConversion),
+        DataType::LongString => LongString::try_from(value)
+            .map(Data::LongString)
+            .map_err(|_| Error::LossyConversion),
+        DataType::Composite | DataType::Blob => Err(Error::UnsupportedConversion),
+    }
 }
 
 /// This type will be thrown, if you try to convert a longer [`String`] to one of the
@@ -1,6 +1,14 @@
 use account_fs::FSError;
-use slice_rbtree::Error as RBTreeError;
 use solana_program::program_error::ProgramError;
+use solcery_reltab::byte_tree::Error as RBTreeError;
+
+use crate::DataType;
+
+// `TypeMismatch` packs both types into 4 bits of the error code
+const _: () = assert!(
+    DataType::VARIANT_COUNT <= 16,
+    "`DataType` does not fit in the `TypeMismatch` error code"
+);
 
 /// enum of possible errors
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -9,7 +17,7 @@ pub enum Error {
     FSError(FSError),
     /// No columns left in the [`DB`](crate::DB) header
     NoColumnsLeft,
-    /// Error in the [`RBTree`](slice_rbtree::tree::RBTree)
+    /// Error in the [`ByteTree`](solcery_reltab::byte_tree::ByteTree) of a column
     RBTreeError(RBTreeError),
     /// The given segment is not a valid [`DB`](crate::DB) header
     WrongSegment,
@@ -17,10 +25,79 @@ pub enum Error {
     NoSuchColumn,
     /// This error occurs, than set_value_secondary() is called on secondary key which does not have a corresponding primary key
     SecondaryKeyWithNonExistentPrimaryKey,
-    /// This error occurs, than not all columns are accessible during DB deletion
+    /// This error occurs, than not all segments of the DB are accessible during its deletion
     NotAllColumnsArePresent,
     /// A column, used as a secondary key, must contain only unique key-value pairs
     NonUniqueSecondaryKey,
+    /// Column name does not fit in the column header
+    NameTooLong,
+    /// The value can not be represented in the target [`DataType`](crate::DataType) without loss
+    LossyConversion,
+    /// There is no conversion between the given [`DataTypes`](crate::DataType)
+    UnsupportedConversion,
+    /// Primary key does not match the key schema of the table
+    WrongKeySchema,
+    /// Encoded [`CompositeKey`](crate::CompositeKey) is too long
+    KeyTooLong,
+    /// Table metadata does not fit in the space reserved for it
+    MetadataOverflow,
+    /// There are no column with such name
+    MissingColumn,
+    /// Row does not contain a value for the non-optional field
+    MissingValue,
+    /// Value type does not match the column type
+    TypeMismatch {
+        /// Type of the column
+        expected: DataType,
+        /// Type of the given value
+        got: DataType,
+    },
+    /// Value of the foreign key column references a non-existent row
+    ForeignKeyViolation,
+    /// The row or the table is referenced by a foreign key with [`OnDelete::Restrict`](crate::OnDelete::Restrict) policy
+    RowIsReferenced,
+    /// This operation needs table metadata, i.e. the table should be created with
+    /// [`TableOptions`](crate::TableOptions)
+    NoMetadata,
+    /// Foreign key is not present, is already present or references the table itself
+    WrongForeignKey,
+    /// Blob columns can be modified only with [`DB::set_blob`](crate::DB::set_blob)
+    BlobColumn,
+    /// Result of the aggregation does not fit in [`Data::Int64`](crate::Data::Int64)
+    ArithmeticOverflow,
+    /// The change does not fit in the change log of the table
+    ChangeLogOverflow,
+    /// The table was created without a change log, see
+    /// [`TableOptions::change_log_capacity`](crate::TableOptions::change_log_capacity)
+    NoChangeLog,
+    /// The table has no expiry column, see
+    /// [`DB::set_expiry_column`](crate::DB::set_expiry_column)
+    NoExpiryColumn,
+    /// There is no table with such name in the [`Catalog`](crate::Catalog)
+    NoSuchTable,
+    /// There is already a table with such name in the [`Catalog`](crate::Catalog)
+    TableAlreadyExists,
+    /// No tables left in the [`Catalog`](crate::Catalog)
+    NoTablesLeft,
+    /// A single `key - value` pair does not fit in the size limit of the [`Page`](crate::Page)
+    PageOverflow,
+    /// The value is already present in the unique column, see
+    /// [`DB::make_unique`](crate::DB::make_unique)
+    UniqueViolation,
+    /// The value does not fit in the size of its [`DataType`](crate::DataType)
+    ValueTooLong,
+    /// The result of the query does not fit in the size limit of the response
+    ResultTooLarge,
+    /// The table was created by an incompatible version of the [`DB`](crate::DB)
+    UnsupportedVersion,
+    /// The table has unique indexes, a change log or foreign keys, so its columns can not be
+    /// repaired, see [`DB::check_integrity`](crate::DB::check_integrity)
+    RepairNotSupported,
+    /// The segment is not an unfinished blob buffer, see
+    /// [`DB::create_blob_buffer`](crate::DB::create_blob_buffer)
+    NotABlobBuffer,
+    /// The chunk does not fit in the blob buffer
+    BlobOutOfBounds,
 }
 
 impl From<FSError> for Error {
@@ -49,6 +126,38 @@ impl From<Error> for ProgramError {
             SecondaryKeyWithNonExistentPrimaryKey => 6,
             NotAllColumnsArePresent => 7,
             NonUniqueSecondaryKey => 8,
+            NameTooLong => 9,
+            LossyConversion => 10,
+            UnsupportedConversion => 11,
+            WrongKeySchema => 12,
+            KeyTooLong => 13,
+            MetadataOverflow => 14,
+            MissingColumn => 15,
+            MissingValue => 16,
+            // Both types fit in 4 bits, so they can be recovered from the error code
+            TypeMismatch { expected, got } => {
+                (17 << 8) + (u16::from(u8::from(expected)) << 4) + u16::from(u8::from(got))
+            }
+            ForeignKeyViolation => 18,
+            RowIsReferenced => 19,
+            NoMetadata => 20,
+            WrongForeignKey => 21,
+            BlobColumn => 22,
+            ArithmeticOverflow => 23,
+            ChangeLogOverflow => 24,
+            NoChangeLog => 25,
+            NoExpiryColumn => 26,
+            NoSuchTable => 27,
+            TableAlreadyExists => 28,
+            NoTablesLeft => 29,
+            PageOverflow => 30,
+            UniqueViolation => 31,
+            ValueTooLong => 32,
+            ResultTooLarge => 33,
+            UnsupportedVersion => 34,
+            RepairNotSupported => 35,
+            NotABlobBuffer => 36,
+            BlobOutOfBounds => 37,
         };
 
         let error_code = ((DB_IDENT as u32) << 24) + errno as u32;
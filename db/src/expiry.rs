@@ -0,0 +1,107 @@
+use super::{ColumnId, Data, DataType, Error, DB};
+
+/// Result of [`DB::purge_expired`]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct PurgeReport {
+    /// Number of deleted rows
+    pub deleted: usize,
+    /// Number of expired rows, which were not deleted, because rows of other tables reference
+    /// them with [`OnDelete::Restrict`](crate::OnDelete::Restrict)
+    pub skipped: usize,
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Returns the expiry column of the table, see [`DB::set_expiry_column`]
+    pub fn expiry_column(&self) -> Option<ColumnId> {
+        self.metadata.as_ref()?.expiry_column
+    }
+
+    /// Designates the column `column_id` as the expiry column of the table, or removes the
+    /// designation if `column_id` is `None`.
+    ///
+    /// Values of the expiry column are Unix timestamps, after which the rows are deleted by
+    /// [`DB::purge_expired`]. Only [`DataType::Int`] and [`DataType::Int64`] columns can be used
+    /// and the table should be created with [`TableOptions`](crate::TableOptions).
+    pub fn set_expiry_column(&mut self, column_id: Option<ColumnId>) -> Result<(), Error> {
+        if self.metadata.is_none() {
+            return Err(Error::NoMetadata);
+        }
+
+        if let Some(column_id) = column_id {
+            let value_type = self.column_header(column_id)?.1.value_type();
+            if value_type != DataType::Int && value_type != DataType::Int64 {
+                return Err(Error::TypeMismatch {
+                    expected: DataType::Int64,
+                    got: value_type,
+                });
+            }
+        }
+
+        let old_column = self.metadata_mut()?.expiry_column;
+        self.metadata_mut()?.expiry_column = column_id;
+
+        if let Err(err) = self.save_metadata() {
+            self.metadata_mut()?.expiry_column = old_column;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes at most `limit` rows, which timestamps in the expiry column are not greater than
+    /// `now`.
+    ///
+    /// Rows are deleted with [`DB::delete_row`], so it fails if not all columns are accessible.
+    /// Expired rows, which are still referenced, are skipped and counted in the report, they do
+    /// not count towards the `limit`. If the expiry column is a secondary key, only the expired
+    /// rows are visited, otherwise the whole column is scanned.
+    pub fn purge_expired(&mut self, now: i64, limit: usize) -> Result<PurgeReport, Error> {
+        let column_id = self.expiry_column().ok_or(Error::NoExpiryColumn)?;
+
+        let expired_keys: Vec<Data> = {
+            let mut accessed_columns = self.accessed_columns.borrow_mut();
+            self.load_column(&mut accessed_columns, column_id)?;
+            let column = &accessed_columns[&column_id];
+
+            let is_expired = |value: &Data| timestamp(value).map_or(false, |time| time <= now);
+
+            let keys = match column.pairs_by_value() {
+                Some(pairs) => pairs
+                    .take_while(|(value, _)| is_expired(value))
+                    .map(|(_, key)| key)
+                    .collect(),
+                None => column
+                    .pairs()
+                    .filter(|(_, value)| is_expired(value))
+                    .map(|(key, _)| key)
+                    .collect(),
+            };
+
+            keys
+        };
+
+        let mut report = PurgeReport::default();
+        for key in expired_keys {
+            if report.deleted == limit {
+                break;
+            }
+
+            // Deletion is planned before any changes, so a referenced row is left intact
+            match self.delete_row(key) {
+                Ok(()) => report.deleted += 1,
+                Err(Error::RowIsReferenced) => report.skipped += 1,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+fn timestamp(value: &Data) -> Option<i64> {
+    match *value {
+        Data::Int(value) => Some(i64::from(value)),
+        Data::Int64(value) => Some(value),
+        _ => None,
+    }
+}
@@ -0,0 +1,86 @@
+use super::{ColumnId, Data, Error, Inconsistency, DB};
+
+/// Result of [`DB::check_integrity`]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct IntegrityReport {
+    /// Inconsistencies, found in the columns
+    pub inconsistencies: Vec<(ColumnId, Inconsistency<Data, Data>)>,
+    /// `key - value` pairs, removed from the columns during the repair
+    pub removed: Vec<(ColumnId, Data, Data)>,
+}
+
+impl IntegrityReport {
+    /// Checks if no inconsistencies were found
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Checks that the containers of all the columns are internally consistent.
+    ///
+    /// [`ColumnType::OneToOne`](crate::ColumnType::OneToOne) columns consist of two trees, which
+    /// must mirror each other. If `repair` is `true`, the converse trees of the inconsistent
+    /// columns are rebuilt from the direct ones. Pairs with duplicate values are removed during
+    /// the rebuild, they are listed in the report, but the other values of their rows are kept.
+    ///
+    /// The removals bypass the unique indexes, the change log and the foreign keys, so the repair
+    /// of the tables with any of them is refused with [`Error::RepairNotSupported`].
+    pub fn check_integrity(&mut self, repair: bool) -> Result<IntegrityReport, Error> {
+        if repair {
+            self.check_repairable()?;
+        }
+
+        let column_ids: Vec<ColumnId> = self
+            .column_headers
+            .iter()
+            .map(|header| header.id())
+            .collect();
+
+        let mut report = IntegrityReport::default();
+
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        for column_id in column_ids {
+            self.load_column(&mut accessed_columns, column_id)?;
+            let column = accessed_columns
+                .get_mut(&column_id)
+                .expect("The column was loaded above");
+
+            let inconsistencies = column.verify();
+
+            if repair && !inconsistencies.is_empty() {
+                let removed = column.repair();
+                report.removed.extend(
+                    removed
+                        .into_iter()
+                        .map(|(key, value)| (column_id, key, value)),
+                );
+            }
+
+            report.inconsistencies.extend(
+                inconsistencies
+                    .into_iter()
+                    .map(|inconsistency| (column_id, inconsistency)),
+            );
+        }
+
+        Ok(report)
+    }
+
+    fn check_repairable(&self) -> Result<(), Error> {
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
+
+        if !metadata.unique_indexes.is_empty()
+            || metadata.change_log.is_some()
+            || !metadata.foreign_keys.is_empty()
+            || !metadata.referrers.is_empty()
+        {
+            return Err(Error::RepairNotSupported);
+        }
+
+        Ok(())
+    }
+}
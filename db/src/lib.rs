@@ -7,31 +7,63 @@
 use bytemuck::{cast_mut, cast_slice_mut};
 use solana_program::msg;
 use std::cell::RefCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::mem;
+use std::ops::Bound;
 use std::rc::Rc;
 use tinyvec::SliceVec;
 
 use account_fs::{SegmentId, FS};
-use slice_rbtree::tree::{tree_size, TreeParams};
-use solcery_reltab::one_to_one::one_to_one_size;
 
+mod aggregate;
+mod blob;
+mod byte_column;
+mod catalog;
+mod change_log;
+mod codec;
 mod column;
+mod composite;
+mod cursor;
 mod data;
 mod error;
+mod expiry;
+mod integrity;
 mod params;
+mod query;
 mod raw;
+mod reader;
+mod row;
+mod unique;
 
-use column::Column;
-use data::{from_column_slice, init_column_slice};
+use byte_column::{column_size, from_column_slice, init_column_slice};
+use change_log::init_change_log;
+use column::{Column, ColumnView};
 use raw::column::ColumnHeader;
-use raw::index::Index;
-
+use raw::hash_index::value_hash;
+use raw::index::{Index, METADATA_VERSION};
+use raw::metadata::{Metadata, Referrer};
+
+pub use aggregate::{Agg, KeyRange};
+pub use blob::BlobHandle;
+pub use catalog::{Catalog, CatalogReader};
+pub use change_log::{Change, Operation};
+pub use codec::ByteCodec;
+pub use composite::CompositeKey;
+pub use cursor::{Cursor, Page};
 pub use data::*;
 pub use error::Error;
-pub use params::{ColumnParams, ColumnType};
+pub use expiry::PurgeReport;
+pub use integrity::IntegrityReport;
+pub use params::{ColumnInfo, ColumnParams, ColumnType, ForeignKey, OnDelete, TableOptions};
+pub use query::{Predicate, Select, SelectedRow};
 pub use raw::column_id::ColumnId;
+pub use reader::DBReader;
+#[doc(hidden)]
+pub use row::{lookup_column, take_value};
+pub use row::{ColumnSchema, ColumnValue, DbRow};
+pub use solcery_db_derive::DbRow;
+pub use solcery_reltab::one_to_one::Inconsistency;
 
 type FSCell<'long, 'short> = Rc<RefCell<FS<'long, 'short>>>;
 
@@ -42,6 +74,8 @@ pub struct DB<'long: 'short, 'short> {
     column_headers: SliceVec<'short, ColumnHeader>,
     accessed_columns: RefCell<BTreeMap<ColumnId, Box<dyn Column + 'short>>>,
     segment: SegmentId,
+    metadata: Option<Metadata>,
+    metadata_slice: &'short mut [u8],
 }
 
 impl<'long: 'short, 'short> DB<'long, 'short> {
@@ -63,6 +97,22 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
             return Err(Error::WrongSegment);
         }
 
+        if !index.is_supported() {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        if columns.len() < index.columns_size() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (columns, metadata_slice) = columns.split_at_mut(index.columns_size());
+
+        let metadata = if index.has_metadata() {
+            Some(Metadata::read(metadata_slice)?)
+        } else {
+            None
+        };
+
         let columns: &mut [ColumnHeader] = cast_slice_mut(columns);
 
         let column_headers = SliceVec::from_slice_len(columns, index.column_count());
@@ -73,6 +123,8 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
             column_headers,
             accessed_columns: RefCell::new(BTreeMap::new()),
             segment,
+            metadata,
+            metadata_slice,
         })
     }
 
@@ -87,7 +139,81 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         max_rows: usize,
         primary_key_type: DataType,
     ) -> Result<(Self, SegmentId), Error> {
-        let index_size = Index::size(max_columns);
+        if primary_key_type == DataType::Composite || primary_key_type == DataType::Blob {
+            return Err(Error::WrongKeySchema);
+        }
+
+        Self::init(
+            fs,
+            table_name,
+            max_columns,
+            max_rows,
+            primary_key_type,
+            None,
+        )
+    }
+
+    /// Initializes [`DB`] with the given parameters and [`TableOptions`] in the first suitable
+    /// segment.
+    ///
+    /// Unlike [`DB::init_in_segment`], this function reserves space for the table metadata right
+    /// after the column headers.
+    pub fn init_in_segment_with_options(
+        fs: FSCell<'long, 'short>,
+        table_name: &str,
+        max_columns: usize,
+        max_rows: usize,
+        primary_key_type: DataType,
+        options: TableOptions,
+    ) -> Result<(Self, SegmentId), Error> {
+        let is_composite = primary_key_type == DataType::Composite;
+        if is_composite == options.key_schema.is_empty()
+            || options.key_schema.contains(&DataType::Composite)
+            || options.key_schema.contains(&DataType::Blob)
+            || primary_key_type == DataType::Blob
+        {
+            return Err(Error::WrongKeySchema);
+        }
+
+        let change_log = match options.change_log_capacity {
+            Some(capacity) => Some(init_change_log(&fs, capacity as usize)?),
+            None => None,
+        };
+
+        let metadata = Metadata {
+            key_schema: options.key_schema,
+            change_log,
+            ..Default::default()
+        };
+
+        let result = Self::init(
+            fs.clone(),
+            table_name,
+            max_columns,
+            max_rows,
+            primary_key_type,
+            Some(metadata),
+        );
+
+        if let (Err(_), Some(log_segment)) = (&result, change_log) {
+            fs.borrow_mut().deallocate_segment(&log_segment)?;
+        }
+
+        result
+    }
+
+    fn init(
+        fs: FSCell<'long, 'short>,
+        table_name: &str,
+        max_columns: usize,
+        max_rows: usize,
+        primary_key_type: DataType,
+        metadata: Option<Metadata>,
+    ) -> Result<(Self, SegmentId), Error> {
+        let index_size = match &metadata {
+            Some(metadata) => Index::size(max_columns) + metadata.reserved_size(max_columns)?,
+            None => Index::size(max_columns),
+        };
         let mut borrowed_fs = fs.borrow_mut();
         let segment = borrowed_fs.allocate_segment(index_size)?;
 
@@ -106,6 +232,26 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
             index.fill(table_name, primary_key_type, max_columns, max_rows);
         }
 
+        let (columns, metadata_slice) = columns.split_at_mut(index.columns_size());
+
+        if let Some(metadata) = &metadata {
+            unsafe {
+                index.set_version(METADATA_VERSION);
+            }
+
+            if let Err(err) = metadata.write(metadata_slice) {
+                let mut borrowed_fs = fs.borrow_mut();
+                unsafe {
+                    // # Safety
+                    // The segment was borrowed in this function and the borrow is not used
+                    // anymore
+                    borrowed_fs.release_borrowed_segment(&segment);
+                }
+                borrowed_fs.deallocate_segment(&segment)?;
+                return Err(err);
+            }
+        }
+
         let columns: &mut [ColumnHeader] = cast_slice_mut(columns);
 
         let column_headers = SliceVec::from_slice_len(columns, 0);
@@ -123,6 +269,8 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
                 column_headers,
                 accessed_columns: RefCell::new(BTreeMap::new()),
                 segment,
+                metadata,
+                metadata_slice,
             },
             segment,
         ))
@@ -141,22 +289,16 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
             return Err(Error::NoColumnsLeft);
         }
 
+        if name.len() > ColumnHeader::MAX_NAME_LEN {
+            return Err(Error::NameTooLong);
+        }
+
         let mut borrowed_fs = self.fs.borrow_mut();
 
         let size = if is_secondary_key {
-            one_to_one_size(
-                self.index.primary_key_type().size(),
-                dtype.size(),
-                self.index.max_rows(),
-            )
+            self.column_size(dtype, ColumnType::OneToOne)
         } else {
-            tree_size(
-                TreeParams {
-                    k_size: self.index.primary_key_type().size(),
-                    v_size: dtype.size(),
-                },
-                self.index.max_rows(),
-            )
+            self.column_size(dtype, ColumnType::RBTree)
         };
         let segment = borrowed_fs.allocate_segment(size)?;
 
@@ -201,8 +343,90 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         Ok(id)
     }
 
+    /// Adds columns, described by the `schema`, for example, obtained by [`DbRow::schema`].
+    ///
+    /// On success, this function returns mapping from column names to their [`ColumnId`]s. On
+    /// failure, none of the columns are added.
+    pub fn add_columns(
+        &mut self,
+        schema: &[ColumnSchema],
+    ) -> Result<BTreeMap<String, ColumnId>, Error> {
+        if self.index.column_count() + schema.len() > self.index.column_max() {
+            return Err(Error::NoColumnsLeft);
+        }
+
+        if schema
+            .iter()
+            .any(|column| column.name.len() > ColumnHeader::MAX_NAME_LEN)
+        {
+            return Err(Error::NameTooLong);
+        }
+
+        let mut added = Vec::with_capacity(schema.len());
+        for column in schema {
+            match self.add_column(&column.name, column.data_type, column.is_secondary_key) {
+                Ok(id) => added.push((column.name.clone(), id)),
+                Err(err) => {
+                    // Everything else is checked above, so only the allocation of the segment
+                    // may fail, the columns, added so far, are removed
+                    for (_, id) in added {
+                        self.remove_column(id)?;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(added.into_iter().collect())
+    }
+
+    /// Returns descriptions of all the columns of the [`DB`]
+    pub fn columns(&self) -> Vec<ColumnInfo> {
+        self.column_headers
+            .iter()
+            .map(|header| ColumnInfo {
+                id: header.id(),
+                name: header.name(),
+                data_type: header.value_type(),
+                column_type: header.column_type(),
+            })
+            .collect()
+    }
+
+    /// Returns mapping from column names to their [`ColumnId`]s
+    pub fn column_ids(&self) -> BTreeMap<String, ColumnId> {
+        self.column_headers
+            .iter()
+            .map(|header| (header.name(), header.id()))
+            .collect()
+    }
+
+    /// Returns the name of the table
+    pub fn table_name(&self) -> String {
+        self.index.table_name()
+    }
+
+    /// Returns the type of the primary key
+    pub fn primary_key_type(&self) -> DataType {
+        self.index.primary_key_type()
+    }
+
     /// Removes column from the [`DB`]
     pub fn remove_column(&mut self, column_id: ColumnId) -> Result<(), Error> {
+        if self.foreign_key(column_id).is_some() {
+            self.remove_foreign_key(column_id)?;
+        }
+
+        if self.expiry_column() == Some(column_id) {
+            self.set_expiry_column(None)?;
+        }
+
+        self.drop_unique(column_id)?;
+
+        if self.column_header(column_id)?.1.value_type() == DataType::Blob {
+            self.free_column_blobs(column_id)?;
+        }
+
         let (index, segment_id) = self
             .column_headers
             .iter()
@@ -232,12 +456,157 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         Ok(())
     }
 
+    /// Renames column `column_id` to `name`
+    pub fn rename_column(&mut self, column_id: ColumnId, name: &str) -> Result<(), Error> {
+        if name.len() > ColumnHeader::MAX_NAME_LEN {
+            return Err(Error::NameTooLong);
+        }
+
+        let (position, header) = self.column_header(column_id)?;
+
+        self.column_headers[position] = unsafe {
+            ColumnHeader::new(
+                name,
+                column_id,
+                header.segment_id(),
+                header.value_type(),
+                header.column_type(),
+            )
+        };
+
+        Ok(())
+    }
+
+    /// Changes [`DataType`] of the column `column_id` to `new_type`.
+    ///
+    /// The column is rebuilt in a fresh segment, each value is passed through the `converter`.
+    /// If any conversion fails or returns a value, which is not of `new_type`, the column is left
+    /// untouched and the error is returned.
+    pub fn alter_column_type<F>(
+        &mut self,
+        column_id: ColumnId,
+        new_type: DataType,
+        mut converter: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Data) -> Result<Data, Error>,
+    {
+        let (position, header) = self.column_header(column_id)?;
+
+        if header.value_type() == DataType::Blob || new_type == DataType::Blob {
+            return Err(Error::UnsupportedConversion);
+        }
+
+        let converted_pairs = {
+            let mut accessed_columns = self.accessed_columns.borrow_mut();
+            self.load_column(&mut accessed_columns, column_id)?;
+
+            let converted_pairs = accessed_columns[&column_id]
+                .pairs()
+                .map(|(key, value)| {
+                    let value = converter(value)?;
+                    if value.data_type() != new_type {
+                        return Err(Error::TypeMismatch {
+                            expected: new_type,
+                            got: value.data_type(),
+                        });
+                    }
+                    Ok((key, value))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            converted_pairs
+        };
+
+        // Checking uniqueness before the column is rebuilt, so it is left untouched on failure
+        let unique_pairs = match self.unique_index(column_id) {
+            Some(_) => {
+                let values: BTreeSet<&Data> =
+                    converted_pairs.iter().map(|(_, value)| value).collect();
+                if values.len() != converted_pairs.len() {
+                    return Err(Error::UniqueViolation);
+                }
+                Some(converted_pairs.clone())
+            }
+            None => None,
+        };
+
+        let size = self.column_size(new_type, header.column_type());
+
+        let mut borrowed_fs = self.fs.borrow_mut();
+        let segment = borrowed_fs.allocate_segment(size)?;
+
+        // We've just successfully allocated this segment, so this operation is infailible;
+        let container = borrowed_fs.segment(&segment).unwrap();
+
+        drop(borrowed_fs);
+
+        // init_column may only fail in case of wrong-sized slice. Here we generate the correct
+        // value, so this invocation is infailible.
+        let mut column = init_column_slice(
+            self.index.primary_key_type(),
+            new_type,
+            header.column_type(),
+            container,
+        )
+        .unwrap();
+
+        let fill_result = converted_pairs
+            .into_iter()
+            .try_for_each(|(key, value)| column.set(key, value).map(|_| ()));
+
+        if let Err(err) = fill_result {
+            drop(column);
+            let mut fs = self.fs.borrow_mut();
+            unsafe {
+                // # Safety
+                // The only borrow of this segment was the column we've just dropped
+                fs.release_borrowed_segment(&segment);
+            }
+            fs.deallocate_segment(&segment)?;
+            return Err(err);
+        }
+
+        let old_segment = header.segment_id();
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        accessed_columns.remove(&column_id);
+
+        let mut fs = self.fs.borrow_mut();
+        unsafe {
+            // # Safety
+            // The only mutable borrow of this segment was storred in `accessed_columns` and
+            // we've just removed it,
+            fs.release_borrowed_segment(&old_segment);
+        }
+        fs.deallocate_segment(&old_segment)?;
+        drop(fs);
+
+        self.column_headers[position] = unsafe {
+            ColumnHeader::new(
+                &header.name(),
+                column_id,
+                segment,
+                new_type,
+                header.column_type(),
+            )
+        };
+
+        accessed_columns.insert(column_id, column);
+        drop(accessed_columns);
+
+        if let (Some(pairs), Some(index_segment)) = (unique_pairs, self.unique_index(column_id)) {
+            self.fill_hash_index(index_segment, pairs)?;
+        }
+
+        Ok(())
+    }
+
     /// Gets value in the `column_id` by its `primary_key`.
     pub fn value(&self, primary_key: Data, column_id: ColumnId) -> Result<Option<Data>, Error> {
         let mut accessed_columns = self.accessed_columns.borrow_mut();
 
         if let Some(column) = accessed_columns.get(&column_id) {
-            Ok(column.get_value(primary_key))
+            column.get_value(primary_key)
         } else {
             let column_header = self
                 .column_headers
@@ -258,7 +627,7 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
 
             accessed_columns.insert(column_header.id(), column);
 
-            Ok(value)
+            value
         }
     }
 
@@ -284,9 +653,25 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         column_id: ColumnId,
         value: Data,
     ) -> Result<Option<Data>, Error> {
+        self.check_primary_key(&primary_key)?;
+
+        if self.column_header(column_id)?.1.value_type() == DataType::Blob {
+            return Err(Error::BlobColumn);
+        }
+
+        self.check_foreign_key(column_id, &value)?;
+        self.check_change_log(column_id)?;
+        let unique_update = self
+            .check_unique(&primary_key, column_id, &value)?
+            .map(|(old_hash, new_hash)| (primary_key.clone(), old_hash, new_hash));
+
+        let change = self
+            .change_log_segment()
+            .map(|_| (primary_key.clone(), value.clone()));
+
         let mut accessed_columns = self.accessed_columns.borrow_mut();
 
-        if let Some(column) = accessed_columns.get_mut(&column_id) {
+        let old_value = if let Some(column) = accessed_columns.get_mut(&column_id) {
             column.set(primary_key, value)
         } else {
             let column_header = self
@@ -309,7 +694,25 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
             accessed_columns.insert(column_header.id(), column);
 
             result
+        }?;
+
+        drop(accessed_columns);
+
+        if let Some((key, old_hash, new_hash)) = unique_update {
+            self.update_unique_index(column_id, &key, old_hash, Some(new_hash))?;
         }
+
+        if let Some((key, new_value)) = change {
+            self.log_change(
+                Operation::Set,
+                column_id,
+                key,
+                old_value.clone(),
+                Some(new_value),
+            )?;
+        }
+
+        Ok(old_value)
     }
 
     /// Sets `primary_key - value` pair in the `column_id`, where `primary_key` is optained from `secondary_key` in `key_column_id`.
@@ -330,10 +733,31 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
 
     /// Deletes `primary_key - value` pair in the `column_id`.
     pub fn delete_value(&mut self, primary_key: Data, column_id: ColumnId) -> Result<bool, Error> {
+        self.check_primary_key(&primary_key)?;
+
+        let old_value = match self.change_log_segment() {
+            Some(_) => {
+                self.check_change_log(column_id)?;
+                self.value(primary_key.clone(), column_id)?
+            }
+            None => None,
+        };
+
+        let unique_hash = match self.unique_index(column_id) {
+            Some(_) => self
+                .value(primary_key.clone(), column_id)?
+                .map(|value| value_hash(&value)),
+            None => None,
+        };
+
+        if self.column_header(column_id)?.1.value_type() == DataType::Blob {
+            self.free_blob(primary_key.clone(), column_id)?;
+        }
+
         let mut accessed_columns = self.accessed_columns.borrow_mut();
 
-        if let Some(column) = accessed_columns.get_mut(&column_id) {
-            Ok(column.delete_by_key(primary_key))
+        let was_value_present = if let Some(column) = accessed_columns.get_mut(&column_id) {
+            column.delete_by_key(primary_key.clone())?
         } else {
             let column_header = self
                 .column_headers
@@ -350,12 +774,24 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
                 column_slice,
             )?;
 
-            let was_value_present = column.delete_by_key(primary_key);
+            let was_value_present = column.delete_by_key(primary_key.clone());
 
             accessed_columns.insert(column_header.id(), column);
 
-            Ok(was_value_present)
+            was_value_present?
+        };
+
+        drop(accessed_columns);
+
+        if unique_hash.is_some() {
+            self.update_unique_index(column_id, &primary_key, unique_hash, None)?;
+        }
+
+        if old_value.is_some() {
+            self.log_change(Operation::Delete, column_id, primary_key, old_value, None)?;
         }
+
+        Ok(was_value_present)
     }
 
     /// Deletes `primary_key - value` pair in the `column_id`, where `primary_key` is optained from `secondary_key` in `key_column_id`.
@@ -380,6 +816,8 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
     where
         Row: IntoIterator<Item = (ColumnId, Data)>,
     {
+        self.check_primary_key(&primary_key)?;
+
         //FIXME: this opretion is not atomic
         row.into_iter()
             .map(|(column, value)| {
@@ -404,7 +842,7 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
                 let column_id = column_header.id();
 
                 if let Some(column) = accessed_columns.get(&column_id) {
-                    Ok((column_id, column.get_value(primary_key.clone())))
+                    Ok((column_id, column.get_value(primary_key.clone())?))
                 } else {
                     let column_slice = self.fs.borrow_mut().segment(&column_header.segment_id())?;
 
@@ -419,12 +857,54 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
 
                     accessed_columns.insert(column_header.id(), column);
 
-                    Ok((column_id, value))
+                    Ok((column_id, value?))
                 }
             })
             .collect()
     }
 
+    /// Sets values of the typed `row` with the given `primary_key`, columns are matched by names.
+    ///
+    /// The values of the columns, which correspond to `None` fields, are deleted.
+    ///
+    /// Returns `true` if there were any old values in the row, otherwise returns `false`.
+    pub fn set_typed_row<Row: DbRow>(
+        &mut self,
+        primary_key: Data,
+        row: &Row,
+    ) -> Result<bool, Error> {
+        self.check_primary_key(&primary_key)?;
+
+        let columns = self.column_ids();
+        let values = row.to_row(&columns)?;
+
+        // `to_row` skips `None` fields, so the columns, missing in `values`, are emptied
+        let mut empty_columns = Vec::new();
+        for column in Row::schema() {
+            let column_id = lookup_column(&columns, &column.name)?;
+            if values.iter().all(|(id, _)| *id != column_id) {
+                empty_columns.push(column_id);
+            }
+        }
+
+        let mut had_old_values = if values.is_empty() {
+            false
+        } else {
+            self.set_row(primary_key.clone(), values)?
+        };
+
+        for column_id in empty_columns {
+            had_old_values |= self.delete_value(primary_key.clone(), column_id)?;
+        }
+
+        Ok(had_old_values)
+    }
+
+    /// Gets typed row for a given `primary_key`, columns are matched by names.
+    pub fn typed_row<Row: DbRow>(&self, primary_key: Data) -> Result<Row, Error> {
+        Row::from_row(self.row(primary_key)?, &self.column_ids())
+    }
+
     /// Gets [`BTreeMap`] of `column <-> value`, there `primary_key` is derived from the
     /// `secondary_key` in the `key_column_id`.
     pub fn row_secondary_key(
@@ -442,20 +922,25 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
 
     /// Deletes all values, assosiated with the wiven `primary_key`, will return `Err(_)` if not
     /// all columns are accessible.
+    ///
+    /// Rows of other tables, referencing this row, are deleted or protect this row according to
+    /// their [`OnDelete`] policies. All such rows are checked before anything is deleted, so
+    /// either the whole cascade is applied or nothing changes.
     pub fn delete_row(&mut self, primary_key: Data) -> Result<(), Error> {
-        let fs = self.fs.borrow();
-        let mut columns = Vec::with_capacity(self.column_headers.len());
-        for &header in self.column_headers.iter() {
-            if !fs.is_accessible(&header.segment_id()) {
-                return Err(Error::NotAllColumnsArePresent);
+        self.check_primary_key(&primary_key)?;
+
+        let mut tables = BTreeMap::new();
+        let plan = self.plan_deletion(primary_key, &mut tables)?;
+
+        for (segment, key) in plan {
+            if segment == self.segment {
+                self.delete_row_values(key)?;
+            } else {
+                tables
+                    .get_mut(&segment)
+                    .expect("all the tables of the plan were opened while planning")
+                    .delete_row_values(key)?;
             }
-            columns.push(header.id());
-        }
-
-        drop(fs);
-
-        for id in columns {
-            self.delete_value(primary_key.clone(), id)?;
         }
 
         Ok(())
@@ -476,18 +961,217 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         }
     }
 
+    /// Returns types of the primary key components.
+    ///
+    /// For the tables with a simple primary key, it is just the primary key type.
+    pub fn key_schema(&self) -> Vec<DataType> {
+        match &self.metadata {
+            Some(metadata) if !metadata.key_schema.is_empty() => metadata.key_schema.clone(),
+            _ => vec![self.index.primary_key_type()],
+        }
+    }
+
+    /// Gets all `primary_key - value` pairs in the `column_id`, which composite primary keys start
+    /// with the components of the `prefix`.
+    ///
+    /// Pairs are returned in ascending order of the primary keys.
+    pub fn prefix_scan(
+        &self,
+        column_id: ColumnId,
+        prefix: &[Data],
+    ) -> Result<Vec<(Data, Data)>, Error> {
+        let schema = self.key_schema();
+        if self.index.primary_key_type() != DataType::Composite
+            || prefix.len() > schema.len()
+            || prefix
+                .iter()
+                .zip(&schema)
+                .any(|(component, &dtype)| component.data_type() != dtype)
+        {
+            return Err(Error::WrongKeySchema);
+        }
+
+        let prefix = CompositeKey::new(prefix)?;
+
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+
+        // The prefix is not greater than any key, which starts with it, so the scan starts from it
+        let start = Bound::Included(Data::Composite(prefix.clone()));
+        let pairs = accessed_columns[&column_id]
+            .range((start, Bound::Unbounded))?
+            .take_while(|(key, _)| matches!(key, Data::Composite(key) if key.starts_with(&prefix)))
+            .collect();
+
+        Ok(pairs)
+    }
+
+    /// Checks if there are any values with the given `primary_key`
+    pub fn contains_row(&self, primary_key: Data) -> Result<bool, Error> {
+        Ok(self.row(primary_key)?.values().any(Option::is_some))
+    }
+
+    /// Returns all foreign keys of the [`DB`]
+    pub fn foreign_keys(&self) -> Vec<ForeignKey> {
+        self.metadata
+            .as_ref()
+            .map(|metadata| metadata.foreign_keys.clone())
+            .unwrap_or_default()
+    }
+
+    /// Declares column `column_id` as a foreign key, referencing the primary key of the [`DB`],
+    /// located in the `table` segment of the same [`FS`].
+    ///
+    /// After that, values written to the column must be primary keys of the existing rows of the
+    /// referenced table, and [`DB::delete_row`] in the referenced table follows the `on_delete`
+    /// policy. Both tables should be created with [`TableOptions`]. The referenced table is
+    /// opened internally, so it should not be opened elsewhere, while the referencing table is
+    /// modified.
+    pub fn add_foreign_key(
+        &mut self,
+        column_id: ColumnId,
+        table: SegmentId,
+        on_delete: OnDelete,
+    ) -> Result<(), Error> {
+        if table == self.segment || self.foreign_key(column_id).is_some() {
+            return Err(Error::WrongForeignKey);
+        }
+
+        if self.metadata.is_none() {
+            return Err(Error::NoMetadata);
+        }
+
+        let (_, header) = self.column_header(column_id)?;
+
+        let mut target = DB::from_segment(self.fs.clone(), table)?;
+
+        let key_type = target.index.primary_key_type();
+        if header.value_type() != key_type {
+            return Err(Error::TypeMismatch {
+                expected: key_type,
+                got: header.value_type(),
+            });
+        }
+
+        let values: Vec<Data> = {
+            let mut accessed_columns = self.accessed_columns.borrow_mut();
+            self.load_column(&mut accessed_columns, column_id)?;
+
+            let values = accessed_columns[&column_id]
+                .pairs()
+                .map(|(_, value)| value)
+                .collect();
+
+            values
+        };
+
+        for value in values {
+            if !target.contains_row(value)? {
+                return Err(Error::ForeignKeyViolation);
+            }
+        }
+
+        self.metadata_mut()?.foreign_keys.push(ForeignKey {
+            column: column_id,
+            table,
+            on_delete,
+        });
+
+        if let Err(err) = self.save_metadata() {
+            self.metadata_mut()?.foreign_keys.pop();
+            return Err(err);
+        }
+
+        target.metadata_mut()?.referrers.push(Referrer {
+            table: self.segment,
+            column: column_id,
+            on_delete,
+        });
+
+        if let Err(err) = target.save_metadata() {
+            target.metadata_mut()?.referrers.pop();
+            self.metadata_mut()?.foreign_keys.pop();
+            self.save_metadata()?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Removes foreign key constraint from the column `column_id`
+    pub fn remove_foreign_key(&mut self, column_id: ColumnId) -> Result<(), Error> {
+        let foreign_key = self.foreign_key(column_id).ok_or(Error::WrongForeignKey)?;
+
+        let mut target = DB::from_segment(self.fs.clone(), foreign_key.table)?;
+        let segment = self.segment;
+
+        target
+            .metadata_mut()?
+            .referrers
+            .retain(|referrer| !(referrer.table == segment && referrer.column == column_id));
+        target.save_metadata()?;
+
+        self.metadata_mut()?
+            .foreign_keys
+            .retain(|foreign_key| foreign_key.column != column_id);
+        self.save_metadata()
+    }
+
     /// Colmpeletely deletes [`DB`] by deallocating all the used [segments](SegmentId)
-    pub fn drop_db(self) -> Result<(), Error> {
-        let mut fs = self.fs.borrow_mut();
+    pub fn drop_db(mut self) -> Result<(), Error> {
+        if let Some(metadata) = &self.metadata {
+            if !metadata.referrers.is_empty() {
+                return Err(Error::RowIsReferenced);
+            }
+        }
 
-        self.accessed_columns.borrow_mut().clear();
+        // All the checks are done before the first change, so a failed deletion leaves the table
+        // intact
+        self.check_accessible(self.column_headers.iter().map(|header| header.segment_id()))?;
 
+        let mut blob_segments = Vec::new();
         for &header in self.column_headers.iter() {
-            if !fs.is_accessible(&header.segment_id()) {
-                return Err(Error::NotAllColumnsArePresent);
+            if header.value_type() == DataType::Blob {
+                blob_segments.extend(self.column_blob_segments(header.id())?);
             }
         }
 
+        let foreign_keys = self.foreign_keys();
+        let unique_indexes = self
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.unique_indexes.clone())
+            .unwrap_or_default();
+
+        self.check_accessible(
+            blob_segments
+                .iter()
+                .copied()
+                .chain(foreign_keys.iter().map(|foreign_key| foreign_key.table))
+                .chain(self.change_log_segment())
+                .chain(unique_indexes.iter().map(|&(_, segment)| segment)),
+        )?;
+
+        for foreign_key in foreign_keys {
+            self.remove_foreign_key(foreign_key.column)?;
+        }
+
+        let mut fs = self.fs.borrow_mut();
+
+        for segment in blob_segments {
+            fs.deallocate_segment(&segment)?;
+        }
+
+        self.accessed_columns.borrow_mut().clear();
+
+        if let Some(log_segment) = self.change_log_segment() {
+            fs.deallocate_segment(&log_segment)?;
+        }
+
+        for (_, index_segment) in unique_indexes {
+            fs.deallocate_segment(&index_segment)?;
+        }
+
         for &header in self.column_headers.iter() {
             let segment_id = header.segment_id();
             unsafe {
@@ -510,6 +1194,227 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         Ok(())
     }
 
+    /// Returns [`Error::NotAllColumnsArePresent`] if some of the `segments` are not accessible
+    fn check_accessible(&self, mut segments: impl Iterator<Item = SegmentId>) -> Result<(), Error> {
+        let fs = self.fs.borrow();
+        if segments.all(|segment| fs.is_accessible(&segment)) {
+            Ok(())
+        } else {
+            Err(Error::NotAllColumnsArePresent)
+        }
+    }
+
+    /// Checks that the `primary_key` has the type of the primary key of the table, and the
+    /// components of the composite keys match its key schema
+    fn check_primary_key(&self, primary_key: &Data) -> Result<(), Error> {
+        let key_type = self.index.primary_key_type();
+        if primary_key.data_type() != key_type {
+            return Err(Error::TypeMismatch {
+                expected: key_type,
+                got: primary_key.data_type(),
+            });
+        }
+
+        match primary_key {
+            Data::Composite(key) if key.schema() != self.key_schema() => Err(Error::WrongKeySchema),
+            _ => Ok(()),
+        }
+    }
+
+    fn foreign_key(&self, column_id: ColumnId) -> Option<ForeignKey> {
+        self.metadata
+            .as_ref()?
+            .foreign_keys
+            .iter()
+            .find(|foreign_key| foreign_key.column == column_id)
+            .copied()
+    }
+
+    fn check_foreign_key(&self, column_id: ColumnId, value: &Data) -> Result<(), Error> {
+        let foreign_key = match self.foreign_key(column_id) {
+            Some(foreign_key) => foreign_key,
+            None => return Ok(()),
+        };
+
+        let target = DB::from_segment(self.fs.clone(), foreign_key.table)?;
+
+        if target.contains_row(value.clone())? {
+            Ok(())
+        } else {
+            Err(Error::ForeignKeyViolation)
+        }
+    }
+
+    /// Collects all the rows, which have to be deleted together with the row `primary_key`
+    /// according to the deletion policies of the foreign keys, and checks that all of them can be
+    /// deleted.
+    ///
+    /// The rows are returned in the order of deletion, starting with the row itself. Referencing
+    /// tables are opened only once and kept in `tables`, this table is never opened again, so
+    /// self-references and cyclic references between tables are supported.
+    fn plan_deletion(
+        &self,
+        primary_key: Data,
+        tables: &mut BTreeMap<SegmentId, DB<'long, 'short>>,
+    ) -> Result<Vec<(SegmentId, Data)>, Error> {
+        let mut plan = vec![(self.segment, primary_key.clone())];
+        let mut planned = BTreeSet::from([(self.segment, primary_key)]);
+        let mut next = 0;
+
+        while let Some((segment, key)) = plan.get(next).cloned() {
+            next += 1;
+
+            let referrers = {
+                let table = if segment == self.segment {
+                    self
+                } else {
+                    &tables[&segment]
+                };
+                table.check_columns_accessible()?;
+                match &table.metadata {
+                    Some(metadata) => metadata.referrers.clone(),
+                    None => continue,
+                }
+            };
+
+            for referrer in referrers {
+                if referrer.table != self.segment && !tables.contains_key(&referrer.table) {
+                    let table = DB::from_segment(self.fs.clone(), referrer.table)?;
+                    tables.insert(referrer.table, table);
+                }
+
+                let table = if referrer.table == self.segment {
+                    self
+                } else {
+                    &tables[&referrer.table]
+                };
+                let keys = table.keys_with_value(referrer.column, &key)?;
+
+                match referrer.on_delete {
+                    OnDelete::Restrict if !keys.is_empty() => return Err(Error::RowIsReferenced),
+                    OnDelete::Restrict => {}
+                    OnDelete::Cascade => {
+                        for key in keys {
+                            if planned.insert((referrer.table, key.clone())) {
+                                plan.push((referrer.table, key));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    fn check_columns_accessible(&self) -> Result<(), Error> {
+        let fs = self.fs.borrow();
+        for header in self.column_headers.iter() {
+            if !fs.is_accessible(&header.segment_id()) {
+                return Err(Error::NotAllColumnsArePresent);
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes values of the row without applying deletion policies of the foreign keys
+    fn delete_row_values(&mut self, primary_key: Data) -> Result<(), Error> {
+        let columns: Vec<ColumnId> = self
+            .column_headers
+            .iter()
+            .map(|header| header.id())
+            .collect();
+
+        for id in columns {
+            self.delete_value(primary_key.clone(), id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns primary keys of all the rows, which have `value` in the column `column_id`
+    ///
+    /// `OneToOne` columns are looked up by value, unique `RBTree` columns are looked up in their
+    /// hash indexes. Other `RBTree` columns are ordered by keys only, so they are scanned.
+    fn keys_with_value(&self, column_id: ColumnId, value: &Data) -> Result<Vec<Data>, Error> {
+        let (_, header) = self.column_header(column_id)?;
+
+        if let Some(keys) = self.unique_keys_with_value(column_id, value)? {
+            return Ok(keys);
+        }
+
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+        let column = &accessed_columns[&column_id];
+
+        let keys = match header.column_type() {
+            ColumnType::OneToOne => column.get_key(value.clone())?.into_iter().collect(),
+            ColumnType::RBTree => column
+                .pairs()
+                .filter(|(_, column_value)| column_value == value)
+                .map(|(key, _)| key)
+                .collect(),
+        };
+
+        Ok(keys)
+    }
+
+    fn metadata_mut(&mut self) -> Result<&mut Metadata, Error> {
+        self.metadata.as_mut().ok_or(Error::NoMetadata)
+    }
+
+    fn save_metadata(&mut self) -> Result<(), Error> {
+        match &self.metadata {
+            Some(metadata) => metadata.write(self.metadata_slice),
+            None => Err(Error::NoMetadata),
+        }
+    }
+
+    fn column_header(&self, column_id: ColumnId) -> Result<(usize, ColumnHeader), Error> {
+        self.column_headers
+            .iter()
+            .enumerate()
+            .find(|(_, col)| col.id() == column_id)
+            .map(|(position, &col)| (position, col))
+            .ok_or(Error::NoSuchColumn)
+    }
+
+    /// Opens the column `column_id` and puts it to the `accessed_columns`, if it was not opened
+    /// already
+    fn load_column(
+        &self,
+        accessed_columns: &mut BTreeMap<ColumnId, Box<dyn Column + 'short>>,
+        column_id: ColumnId,
+    ) -> Result<(), Error> {
+        if accessed_columns.contains_key(&column_id) {
+            return Ok(());
+        }
+
+        let (_, column_header) = self.column_header(column_id)?;
+
+        let column_slice = self.fs.borrow_mut().segment(&column_header.segment_id())?;
+
+        let column = from_column_slice(
+            self.index.primary_key_type(),
+            column_header.value_type(),
+            column_header.column_type(),
+            column_slice,
+        )?;
+
+        accessed_columns.insert(column_id, column);
+
+        Ok(())
+    }
+
+    fn column_size(&self, dtype: DataType, column_type: ColumnType) -> usize {
+        column_size(
+            self.index.primary_key_type(),
+            dtype,
+            column_type,
+            self.index.max_rows(),
+        )
+    }
+
     fn get_primary_key(
         &self,
         key_column_id: ColumnId,
@@ -518,7 +1423,7 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
         let mut accessed_columns = self.accessed_columns.borrow_mut();
 
         if let Some(key_column) = accessed_columns.get(&key_column_id) {
-            Ok(key_column.get_key(secondary_key))
+            key_column.get_key(secondary_key)
         } else {
             let column_header = self
                 .column_headers
@@ -539,7 +1444,7 @@ impl<'long: 'short, 'short> DB<'long, 'short> {
 
             accessed_columns.insert(column_header.id(), key_column);
 
-            Ok(primary_key)
+            primary_key
         }
     }
 }
@@ -548,10 +1453,10 @@ impl<'long, 'short> Drop for DB<'long, 'short> {
     fn drop(&mut self) {
         let DB {
             fs,
-            index: _,
             column_headers,
             accessed_columns,
             segment,
+            ..
         } = self;
 
         let mut fs = fs.borrow_mut();
@@ -621,6 +1526,7 @@ impl<'long, 'short> fmt::Debug for DB<'long, 'short> {
         f.debug_struct("DB")
             .field("index", &self.index)
             .field("column_headers", &self.column_headers)
+            .field("metadata", &self.metadata)
             .field("columns", &accessed_columns)
             .finish()
     }
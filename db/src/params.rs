@@ -3,7 +3,9 @@ use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
 use serde::{Deserialize, Serialize};
 
-use super::DataType;
+use account_fs::SegmentId;
+
+use super::{ColumnId, DataType};
 
 /// Parameters of the column to be inserted in the [`DB`](crate::DB)
 #[derive(PartialEq, Clone, Eq, Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
@@ -13,6 +15,65 @@ pub struct ColumnParams {
     column_type: ColumnType,
 }
 
+impl ColumnParams {
+    /// Parameters of the column `name` with values of `data_type`, secondary key columns are stored
+    /// in [`ColumnType::OneToOne`] containers, the others in [`ColumnType::RBTree`]
+    pub fn new(name: &str, data_type: DataType, is_secondary_key: bool) -> Self {
+        let column_type = if is_secondary_key {
+            ColumnType::OneToOne
+        } else {
+            ColumnType::RBTree
+        };
+
+        Self {
+            name: name.to_string(),
+            data_type,
+            column_type,
+        }
+    }
+
+    /// Column name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Type of the values in the column
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    /// Should the column be created as a secondary key
+    pub fn is_secondary_key(&self) -> bool {
+        self.column_type == ColumnType::OneToOne
+    }
+}
+
+/// Description of a column of the existing [`DB`](crate::DB)
+#[derive(PartialEq, Clone, Eq, Debug)]
+pub struct ColumnInfo {
+    /// Column identifier
+    pub id: ColumnId,
+    /// Column name
+    pub name: String,
+    /// Type of the values in the column
+    pub data_type: DataType,
+    /// Container, used to store the column
+    pub column_type: ColumnType,
+}
+
+/// Optional parameters of the table, see
+/// [`DB::init_in_segment_with_options`](crate::DB::init_in_segment_with_options)
+#[derive(
+    PartialEq, Clone, Eq, Debug, Default, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct TableOptions {
+    /// Types of the components of the primary key, should be set if and only if the primary key
+    /// type is [`DataType::Composite`]
+    pub key_schema: Vec<DataType>,
+    /// Size of the change log ring buffer in bytes, the change log is disabled if it is `None`
+    pub change_log_capacity: Option<u32>,
+}
+
 /// Possible containers for the column data
 #[derive(
     PartialEq,
@@ -29,11 +90,12 @@ pub struct ColumnParams {
 )]
 #[repr(u8)]
 pub enum ColumnType {
-    /// if data is stored in the [`RBTree`](slice_rbtree::tree::RBTree), it can not be used as a
-    /// secondary  key
+    /// if data is stored in the [`ByteTree`](solcery_reltab::byte_tree::ByteTree), it can not be
+    /// used as a secondary  key
     RBTree,
-    /// if key-value pairs is stored in the [`OneToOne`](solcery_reltab::one_to_one::OneToOne)
-    /// container, they can be fetched by both key and value
+    /// if key-value pairs is stored in the
+    /// [`ByteOneToOne`](solcery_reltab::byte_one_to_one::ByteOneToOne) container, they can be
+    /// fetched by both key and value
     OneToOne,
     // This types are not implemented yet
     //OneToMany,
@@ -41,3 +103,36 @@ pub enum ColumnType {
     //ManyToMany,
     //RBSet,
 }
+
+/// What happens with the referencing rows, when the referenced row is deleted
+#[derive(
+    PartialEq,
+    Copy,
+    Clone,
+    Eq,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    TryFromPrimitive,
+    IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum OnDelete {
+    /// Deletion of the referenced row fails
+    Restrict,
+    /// Referencing rows are deleted too
+    Cascade,
+}
+
+/// Column, which values are primary keys of the other [`DB`](crate::DB)
+#[derive(PartialEq, Copy, Clone, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub struct ForeignKey {
+    /// Referencing column
+    pub column: ColumnId,
+    /// Segment of the referenced [`DB`](crate::DB)
+    pub table: SegmentId,
+    /// Deletion policy
+    pub on_delete: OnDelete,
+}
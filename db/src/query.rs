@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+use super::column::Column;
+use super::{
+    ColumnId, ColumnType, Data, DataType, Error, LongString, MediumString, ShortString, DB,
+};
+
+/// Matching row with its primary key, returned by [`Select::rows`]
+pub type SelectedRow = (Data, BTreeMap<ColumnId, Option<Data>>);
+
+/// Boxed iterator over the primary keys of the candidate rows
+type Keys<'a> = Box<dyn Iterator<Item = Data> + 'a>;
+
+/// Condition on the column value, used in [`Select::filter`]
+#[derive(PartialEq, Clone, Eq, Debug)]
+pub enum Predicate {
+    /// Value is equal to the given one
+    Eq(Data),
+    /// Value is less than the given one
+    Lt(Data),
+    /// Value is greater than the given one
+    Gt(Data),
+    /// Value is between the given ones, both bounds are inclusive
+    Between(Data, Data),
+    /// String value starts with the given prefix
+    Prefix(String),
+}
+
+impl Predicate {
+    /// Checks if the `value` satisfies the predicate
+    pub fn matches(&self, value: &Data) -> bool {
+        match self {
+            Predicate::Eq(other) => value == other,
+            Predicate::Lt(other) => value < other,
+            Predicate::Gt(other) => value > other,
+            Predicate::Between(low, high) => low <= value && value <= high,
+            Predicate::Prefix(prefix) => match value {
+                Data::ShortString(value) => value.as_ref().starts_with(prefix.as_str()),
+                Data::MediumString(value) => value.as_ref().starts_with(prefix.as_str()),
+                Data::LongString(value) => value.as_ref().starts_with(prefix.as_str()),
+                _ => false,
+            },
+        }
+    }
+
+    /// Checks that the values of the predicate have the type of the filtered column
+    fn check_type(&self, value_type: DataType) -> Result<(), Error> {
+        let values = match self {
+            Predicate::Eq(value) | Predicate::Lt(value) | Predicate::Gt(value) => vec![value],
+            Predicate::Between(low, high) => vec![low, high],
+            Predicate::Prefix(prefix) => match value_type {
+                DataType::ShortString | DataType::MediumString | DataType::LongString => Vec::new(),
+                _ => {
+                    return Err(Error::TypeMismatch {
+                        expected: value_type,
+                        got: string_type(prefix),
+                    })
+                }
+            },
+        };
+
+        for value in values {
+            if value.data_type() != value_type {
+                return Err(Error::TypeMismatch {
+                    expected: value_type,
+                    got: value.data_type(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Range of the values, starting with the first value, which may match the predicate, or
+    /// `None` if no value of the type `value_type` can match it
+    fn value_range(&self, value_type: DataType) -> Option<(Bound<Data>, Bound<Data>)> {
+        match self {
+            Predicate::Eq(value) => Some((Included(value.clone()), Included(value.clone()))),
+            Predicate::Lt(value) => Some((Unbounded, Excluded(value.clone()))),
+            Predicate::Gt(value) => Some((Excluded(value.clone()), Unbounded)),
+            Predicate::Between(low, high) if low <= high => {
+                Some((Included(low.clone()), Included(high.clone())))
+            }
+            Predicate::Between(_, _) => None,
+            // The prefix itself is the smallest string starting with it
+            Predicate::Prefix(prefix) => {
+                let prefix = match value_type {
+                    DataType::ShortString => {
+                        Data::ShortString(ShortString::try_from(prefix.as_str()).ok()?)
+                    }
+                    DataType::MediumString => {
+                        Data::MediumString(MediumString::try_from(prefix.as_str()).ok()?)
+                    }
+                    DataType::LongString => {
+                        Data::LongString(LongString::try_from(prefix.as_str()).ok()?)
+                    }
+                    _ => return None,
+                };
+                Some((Included(prefix), Unbounded))
+            }
+        }
+    }
+}
+
+/// Query builder, created by [`DB::select`]
+///
+/// A row matches the query, if it matches all the filters. If one of the filtered columns is a
+/// secondary key, the candidate rows are found by a range search over its ordered values,
+/// otherwise the first filtered column is scanned.
+///
+/// Values of the predicates must have the type of the filtered column and [`Predicate::Prefix`]
+/// needs a string column, otherwise [`Error::TypeMismatch`] is returned.
+#[derive(Debug)]
+pub struct Select<'db, 'long: 'short, 'short> {
+    db: &'db DB<'long, 'short>,
+    filters: Vec<(ColumnId, Predicate)>,
+    limit: Option<usize>,
+}
+
+impl<'db, 'long: 'short, 'short> Select<'db, 'long, 'short> {
+    /// Adds a filter on the column `column_id`
+    pub fn filter(mut self, column_id: ColumnId, predicate: Predicate) -> Self {
+        self.filters.push((column_id, predicate));
+        self
+    }
+
+    /// Limits the number of returned rows
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns primary keys of the matching rows
+    ///
+    /// The candidate rows are checked one by one, so the scan stops as soon as the limit is
+    /// reached.
+    pub fn keys(self) -> Result<Vec<Data>, Error> {
+        let mut index_filter = None;
+        for (position, (column_id, predicate)) in self.filters.iter().enumerate() {
+            let (_, header) = self.db.column_header(*column_id)?;
+            predicate.check_type(header.value_type())?;
+
+            if index_filter.is_none() && header.column_type() == ColumnType::OneToOne {
+                index_filter = Some(position);
+            }
+        }
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+
+        if self.filters.is_empty() {
+            let mut keys = self.db.all_keys()?;
+            keys.truncate(limit);
+            return Ok(keys);
+        }
+
+        let mut accessed_columns = self.db.accessed_columns.borrow_mut();
+        for (column_id, _) in self.filters.iter() {
+            self.db.load_column(&mut accessed_columns, *column_id)?;
+        }
+        let columns = &*accessed_columns;
+
+        let used_filter = index_filter.unwrap_or(0);
+        let (column_id, predicate) = &self.filters[used_filter];
+        let column = columns[column_id].as_ref();
+        let candidates = match index_filter {
+            Some(_) => {
+                let (_, header) = self.db.column_header(*column_id)?;
+                index_scan(column, header.value_type(), predicate)?
+            }
+            None => column_scan(column, predicate),
+        };
+
+        let mut keys = Vec::new();
+        for key in candidates {
+            if keys.len() >= limit {
+                break;
+            }
+
+            let mut is_matching = true;
+            for (position, (column_id, predicate)) in self.filters.iter().enumerate() {
+                if position == used_filter {
+                    continue;
+                }
+
+                match columns[column_id].get_value(key.clone())? {
+                    Some(value) if predicate.matches(&value) => {}
+                    _ => {
+                        is_matching = false;
+                        break;
+                    }
+                }
+            }
+
+            if is_matching {
+                keys.push(key);
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Returns matching rows with their primary keys
+    pub fn rows(self) -> Result<Vec<SelectedRow>, Error> {
+        let db = self.db;
+        self.keys()?
+            .into_iter()
+            .map(|key| db.row(key.clone()).map(|row| (key, row)))
+            .collect()
+    }
+}
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Starts a query over the rows of the [`DB`]
+    pub fn select(&self) -> Select<'_, 'long, 'short> {
+        Select {
+            db: self,
+            filters: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Primary keys of all the rows, which have at least one value
+    fn all_keys(&self) -> Result<Vec<Data>, Error> {
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        let mut keys = BTreeSet::new();
+
+        for header in self.column_headers.iter() {
+            self.load_column(&mut accessed_columns, header.id())?;
+            keys.extend(accessed_columns[&header.id()].pairs().map(|(key, _)| key));
+        }
+
+        Ok(keys.into_iter().collect())
+    }
+}
+
+/// Primary keys of the rows matching the `predicate`, obtained by a range search over the
+/// ordered values of the secondary key `column`
+fn index_scan<'a>(
+    column: &'a dyn Column,
+    value_type: DataType,
+    predicate: &'a Predicate,
+) -> Result<Keys<'a>, Error> {
+    if let Predicate::Eq(value) = predicate {
+        return Ok(Box::new(column.get_key(value.clone())?.into_iter()));
+    }
+
+    let range = match predicate.value_range(value_type) {
+        Some(range) => range,
+        None => return Ok(Box::new(std::iter::empty())),
+    };
+
+    // All the predicates select a contiguous range of ordered values
+    let keys: Keys<'a> = match column.range_by_value(range)? {
+        Some(pairs) => Box::new(
+            pairs
+                .take_while(move |(value, _)| predicate.matches(value))
+                .map(|(_, key)| key),
+        ),
+        None => Box::new(std::iter::empty()),
+    };
+
+    Ok(keys)
+}
+
+/// Primary keys of the rows matching the `predicate`, obtained by the full `column` scan
+fn column_scan<'a>(column: &'a dyn Column, predicate: &'a Predicate) -> Keys<'a> {
+    Box::new(
+        column
+            .pairs()
+            .filter(move |(_, value)| predicate.matches(value))
+            .map(|(key, _)| key),
+    )
+}
+
+/// The smallest string type, which can hold the `prefix`
+fn string_type(prefix: &str) -> DataType {
+    if ShortString::try_from(prefix).is_ok() {
+        DataType::ShortString
+    } else if MediumString::try_from(prefix).is_ok() {
+        DataType::MediumString
+    } else {
+        DataType::LongString
+    }
+}
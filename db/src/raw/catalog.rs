@@ -0,0 +1,157 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+use std::mem;
+
+use account_fs::SegmentId;
+
+const CATALOG_MAGIC: [u8; 16] = *b"Solcery_DB_Catlg";
+const NAME_LEN: usize = 64;
+
+#[repr(C)]
+#[derive(Pod, Clone, Copy, Zeroable)]
+pub struct CatalogHeader {
+    magic: [u8; 16],
+    table_count: [u8; 2],
+    table_max: [u8; 2],
+}
+
+impl CatalogHeader {
+    pub fn check_magic(&self) -> bool {
+        self.magic == CATALOG_MAGIC
+    }
+
+    pub fn table_count(&self) -> usize {
+        u16::from_be_bytes(self.table_count) as usize
+    }
+
+    pub fn table_max(&self) -> usize {
+        u16::from_be_bytes(self.table_max) as usize
+    }
+
+    pub const fn size(table_max: usize) -> usize {
+        mem::size_of::<Self>() + mem::size_of::<CatalogEntry>() * table_max
+    }
+
+    pub const fn entries_size(&self) -> usize {
+        mem::size_of::<CatalogEntry>() * u16::from_be_bytes(self.table_max) as usize
+    }
+
+    pub unsafe fn set_table_count(&mut self, count: usize) {
+        assert!(u16::try_from(count).is_ok());
+        self.table_count = u16::to_be_bytes(count as u16);
+    }
+
+    pub unsafe fn fill(&mut self, table_max: usize) {
+        assert!(u16::try_from(table_max).is_ok());
+        self.magic = CATALOG_MAGIC;
+        self.table_count = u16::to_be_bytes(0);
+        self.table_max = u16::to_be_bytes(table_max as u16);
+    }
+}
+
+impl fmt::Debug for CatalogHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CatalogHeader")
+            .field("table_count", &self.table_count())
+            .field("table_max", &self.table_max())
+            .finish()
+    }
+}
+
+/// Name of the table and the segment of its [`Index`](super::index::Index)
+#[repr(C)]
+#[derive(Pod, Clone, Copy, Zeroable)]
+pub struct CatalogEntry {
+    name: [u8; NAME_LEN],
+    account_pubkey: [u8; 32],
+    segment_id: [u8; 4],
+}
+
+impl CatalogEntry {
+    /// 4 bytes of the name field are used as string length inside BorshSerialize
+    pub const MAX_NAME_LEN: usize = NAME_LEN - 4;
+
+    pub fn name(&self) -> String {
+        String::deserialize(&mut self.name.as_slice()).unwrap()
+    }
+
+    pub fn segment_id(&self) -> SegmentId {
+        SegmentId {
+            pubkey: Pubkey::new_from_array(self.account_pubkey),
+            id: u32::from_be_bytes(self.segment_id),
+        }
+    }
+
+    pub unsafe fn new(name: &str, segment_id: SegmentId) -> Self {
+        assert!(name.len() <= Self::MAX_NAME_LEN);
+        let mut name_bytes: [u8; NAME_LEN] = [0; NAME_LEN];
+        name.serialize(&mut name_bytes.as_mut_slice()).unwrap();
+
+        Self {
+            name: name_bytes,
+            account_pubkey: segment_id.pubkey.to_bytes(),
+            segment_id: segment_id.id.to_be_bytes(),
+        }
+    }
+}
+
+impl fmt::Debug for CatalogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CatalogEntry")
+            .field("name", &self.name())
+            .field("segment_id", &self.segment_id())
+            .finish()
+    }
+}
+
+impl Default for CatalogEntry {
+    fn default() -> Self {
+        Self {
+            name: [0; NAME_LEN],
+            account_pubkey: [0; 32],
+            segment_id: [0; 4],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::cast;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn header() {
+        let header = [0_u8; mem::size_of::<CatalogHeader>()];
+        let mut header: CatalogHeader = cast(header);
+        assert!(!header.check_magic());
+
+        unsafe {
+            header.fill(12);
+        }
+        assert!(header.check_magic());
+        assert_eq!(header.table_count(), 0);
+        assert_eq!(header.table_max(), 12);
+        assert_eq!(header.entries_size(), 12 * mem::size_of::<CatalogEntry>());
+
+        unsafe {
+            header.set_table_count(3);
+        }
+        assert_eq!(header.table_count(), 3);
+    }
+
+    #[test]
+    fn entry() {
+        let segment_id = SegmentId {
+            pubkey: Pubkey::new_unique(),
+            id: 7,
+        };
+
+        let entry = unsafe { CatalogEntry::new("Players", segment_id) };
+
+        assert_eq!(entry.name(), "Players".to_string());
+        assert_eq!(entry.segment_id(), segment_id);
+    }
+}
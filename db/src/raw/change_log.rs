@@ -0,0 +1,245 @@
+use bytemuck::{cast_mut, cast_slice_mut, Pod, Zeroable};
+use std::fmt;
+use std::mem;
+
+use crate::Error;
+
+const CHANGE_LOG_MAGIC: [u8; 16] = *b"Solcery_DB_ChLog";
+
+/// Size of the length prefix of each record
+const LEN_SIZE: usize = mem::size_of::<u16>();
+
+#[repr(C)]
+#[derive(Pod, Clone, Copy, Zeroable)]
+pub struct ChangeLogHeader {
+    magic: [u8; 16],
+    capacity: [u8; 4],
+    head: [u8; 4],
+    len: [u8; 4],
+    first_sequence: [u8; 8],
+    next_sequence: [u8; 8],
+}
+
+/// Ring buffer of length-prefixed records with consecutive sequence numbers
+///
+/// If there is not enough space for the new record, the oldest records are evicted.
+pub struct ChangeLog<'a> {
+    header: &'a mut ChangeLogHeader,
+    ring: &'a mut [u8],
+}
+
+impl<'a> ChangeLog<'a> {
+    /// Size of the segment, needed to store ring buffer of the given `capacity`
+    pub const fn size(capacity: usize) -> usize {
+        mem::size_of::<ChangeLogHeader>() + capacity
+    }
+
+    pub fn init(slice: &'a mut [u8]) -> Result<Self, Error> {
+        let log = Self::split(slice)?;
+
+        assert!(u32::try_from(log.ring.len()).is_ok());
+        log.header.magic = CHANGE_LOG_MAGIC;
+        log.header.capacity = u32::to_be_bytes(log.ring.len() as u32);
+        log.header.head = u32::to_be_bytes(0);
+        log.header.len = u32::to_be_bytes(0);
+        log.header.first_sequence = u64::to_be_bytes(0);
+        log.header.next_sequence = u64::to_be_bytes(0);
+
+        Ok(log)
+    }
+
+    pub fn from_slice(slice: &'a mut [u8]) -> Result<Self, Error> {
+        let log = Self::split(slice)?;
+
+        if log.header.magic != CHANGE_LOG_MAGIC || log.capacity() > log.ring.len() {
+            return Err(Error::WrongSegment);
+        }
+
+        Ok(log)
+    }
+
+    fn split(slice: &'a mut [u8]) -> Result<Self, Error> {
+        if slice.len() < mem::size_of::<ChangeLogHeader>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (header, ring) = slice.split_at_mut(mem::size_of::<ChangeLogHeader>());
+
+        let header: &mut [[u8; mem::size_of::<ChangeLogHeader>()]] = cast_slice_mut(header);
+        let header: &mut ChangeLogHeader = cast_mut(&mut header[0]);
+
+        Ok(Self { header, ring })
+    }
+
+    pub fn capacity(&self) -> usize {
+        u32::from_be_bytes(self.header.capacity) as usize
+    }
+
+    /// Checks if the record of the given length can be stored in the buffer
+    pub fn fits(&self, record_len: usize) -> bool {
+        record_len <= u16::MAX as usize && LEN_SIZE + record_len <= self.capacity()
+    }
+
+    /// Sequence number of the oldest stored record
+    pub fn first_sequence(&self) -> u64 {
+        u64::from_be_bytes(self.header.first_sequence)
+    }
+
+    /// Sequence number of the next record
+    pub fn next_sequence(&self) -> u64 {
+        u64::from_be_bytes(self.header.next_sequence)
+    }
+
+    /// Appends the `record` to the buffer and returns its sequence number
+    pub fn push(&mut self, record: &[u8]) -> Result<u64, Error> {
+        if !self.fits(record.len()) {
+            return Err(Error::ChangeLogOverflow);
+        }
+
+        let total_len = LEN_SIZE + record.len();
+        while self.capacity() - self.len() < total_len {
+            self.evict();
+        }
+
+        let tail = (self.head() + self.len()) % self.capacity();
+        self.write_at(tail, &(record.len() as u16).to_be_bytes());
+        self.write_at((tail + LEN_SIZE) % self.capacity(), record);
+        self.set_len(self.len() + total_len);
+
+        let sequence = self.next_sequence();
+        self.header.next_sequence = u64::to_be_bytes(sequence + 1);
+
+        Ok(sequence)
+    }
+
+    /// Returns all the stored records with sequence numbers not less than `sequence`
+    pub fn records_since(&self, sequence: u64) -> Vec<(u64, Vec<u8>)> {
+        let mut records = Vec::new();
+        let mut position = self.head();
+        let mut remaining = self.len();
+        let mut record_sequence = self.first_sequence();
+
+        while remaining > 0 {
+            let record_len = self.record_len_at(position);
+            if record_sequence >= sequence {
+                let record = self.read_at((position + LEN_SIZE) % self.capacity(), record_len);
+                records.push((record_sequence, record));
+            }
+
+            position = (position + LEN_SIZE + record_len) % self.capacity();
+            remaining -= LEN_SIZE + record_len;
+            record_sequence += 1;
+        }
+
+        records
+    }
+
+    fn head(&self) -> usize {
+        u32::from_be_bytes(self.header.head) as usize
+    }
+
+    fn len(&self) -> usize {
+        u32::from_be_bytes(self.header.len) as usize
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.header.len = u32::to_be_bytes(len as u32);
+    }
+
+    /// Removes the oldest record
+    fn evict(&mut self) {
+        let total_len = LEN_SIZE + self.record_len_at(self.head());
+
+        self.header.head = u32::to_be_bytes(((self.head() + total_len) % self.capacity()) as u32);
+        self.set_len(self.len() - total_len);
+        self.header.first_sequence = u64::to_be_bytes(self.first_sequence() + 1);
+    }
+
+    fn record_len_at(&self, position: usize) -> usize {
+        let mut len = [0; LEN_SIZE];
+        len.copy_from_slice(&self.read_at(position, LEN_SIZE));
+        u16::from_be_bytes(len) as usize
+    }
+
+    fn write_at(&mut self, position: usize, bytes: &[u8]) {
+        let capacity = self.capacity();
+        let first_part = bytes.len().min(capacity - position);
+
+        self.ring[position..position + first_part].copy_from_slice(&bytes[..first_part]);
+        self.ring[..bytes.len() - first_part].copy_from_slice(&bytes[first_part..]);
+    }
+
+    fn read_at(&self, position: usize, len: usize) -> Vec<u8> {
+        let capacity = self.capacity();
+        let first_part = len.min(capacity - position);
+
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(&self.ring[position..position + first_part]);
+        bytes.extend_from_slice(&self.ring[..len - first_part]);
+        bytes
+    }
+}
+
+impl<'a> fmt::Debug for ChangeLog<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeLog")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .field("first_sequence", &self.first_sequence())
+            .field("next_sequence", &self.next_sequence())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn push_and_read() {
+        let mut slice = vec![0; ChangeLog::size(16)];
+        let mut log = ChangeLog::init(&mut slice).unwrap();
+
+        assert_eq!(log.push(&[1, 2, 3]).unwrap(), 0);
+        assert_eq!(log.push(&[4, 5]).unwrap(), 1);
+        assert_eq!(
+            log.records_since(0),
+            vec![(0, vec![1, 2, 3]), (1, vec![4, 5])]
+        );
+        assert_eq!(log.records_since(1), vec![(1, vec![4, 5])]);
+        assert_eq!(log.records_since(2), vec![]);
+
+        let log = ChangeLog::from_slice(&mut slice).unwrap();
+        assert_eq!(log.next_sequence(), 2);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut slice = vec![0; ChangeLog::size(16)];
+        let mut log = ChangeLog::init(&mut slice).unwrap();
+
+        // Each record occupies 6 bytes, so only two of them fit in the buffer
+        for sequence in 0..10_u8 {
+            let record = [sequence; 4];
+            assert_eq!(log.push(&record).unwrap(), sequence as u64);
+        }
+
+        assert_eq!(log.first_sequence(), 8);
+        assert_eq!(log.records_since(0), vec![(8, vec![8; 4]), (9, vec![9; 4])]);
+
+        // This record wraps around the end of the buffer
+        assert_eq!(log.push(&[10; 9]).unwrap(), 10);
+        assert_eq!(log.records_since(0), vec![(10, vec![10; 9])]);
+
+        let err = log.push(&[0; 15]).unwrap_err();
+        assert_eq!(err, Error::ChangeLogOverflow);
+    }
+
+    #[test]
+    fn wrong_segment() {
+        let mut slice = vec![0; ChangeLog::size(16)];
+        let err = ChangeLog::from_slice(&mut slice).unwrap_err();
+        assert_eq!(err, Error::WrongSegment);
+    }
+}
@@ -21,6 +21,9 @@ pub struct ColumnHeader {
 }
 
 impl ColumnHeader {
+    /// 4 bytes of the name field are used as string length inside BorshSerialize
+    pub const MAX_NAME_LEN: usize = NAME_LEN - 4;
+
     pub fn name(&self) -> String {
         String::deserialize(&mut self.name.as_slice()).unwrap()
     }
@@ -53,8 +56,7 @@ impl ColumnHeader {
         value_type: DataType,
         column_type: ColumnType,
     ) -> Self {
-        assert!(name.len() <= NAME_LEN - 4); // 4 bytes will be used as string length inside
-                                             // BorshSerialize
+        assert!(name.len() <= Self::MAX_NAME_LEN);
         let mut name_bytes: [u8; NAME_LEN] = [0; NAME_LEN];
         name.serialize(&mut name_bytes.as_mut_slice()).unwrap();
         let value_type = u8::from(value_type);
@@ -0,0 +1,397 @@
+use borsh::BorshSerialize;
+use bytemuck::{cast_mut, cast_slice_mut, Pod, Zeroable};
+use std::fmt;
+use std::mem;
+
+use crate::{Data, Error};
+
+const HASH_INDEX_MAGIC: [u8; 16] = *b"Solcery_DB_HashI";
+
+/// Stored hash of an empty slot
+const EMPTY: u32 = 0;
+/// Stored hash of a slot, which pair was removed
+const DELETED: u32 = 1;
+
+/// Size of the stored hash, the beginning of each slot
+const HASH_SIZE: usize = mem::size_of::<u32>();
+
+#[repr(C)]
+#[derive(Pod, Clone, Copy, Zeroable)]
+pub struct HashIndexHeader {
+    magic: [u8; 16],
+    capacity: [u8; 4],
+    len: [u8; 4],
+    tombstones: [u8; 4],
+    key_size: [u8; 4],
+}
+
+/// Open-addressing hash multimap from value hashes to primary keys with linear probing
+///
+/// It is used to enforce uniqueness of the column values. The index only narrows the search down
+/// to the rows with the same hash, the values of these rows are compared by the caller, so hash
+/// collisions don't produce false violations.
+///
+/// Each slot is a 32-bit hash followed by the encoded primary key. The 64-bit hashes are folded
+/// to 32 bits, the values `0` and `1` mark empty and deleted slots, so there is no separate state
+/// byte. The table is filled up to 80%: for `max_rows` rows there are `max_rows + max_rows / 4 +
+/// 1` slots, and at least one of them is always empty.
+///
+/// Deleted slots are marked with tombstones. When they take more than a quarter of the slots, or
+/// the last empty slot is about to be taken, the index is rebuilt in place without allocations, so
+/// the probe sequences don't grow with the number of deletions.
+pub struct HashIndex<'a> {
+    header: &'a mut HashIndexHeader,
+    slots: &'a mut [u8],
+}
+
+impl<'a> HashIndex<'a> {
+    /// Size of the segment, needed to store hashes of `max_rows` values with primary keys of
+    /// `key_size` bytes
+    pub const fn size(max_rows: usize, key_size: usize) -> usize {
+        mem::size_of::<HashIndexHeader>() + (HASH_SIZE + key_size) * Self::capacity_for(max_rows)
+    }
+
+    /// The table is kept at most 80% full, with at least one empty slot, which ends the probe
+    /// sequences
+    const fn capacity_for(max_rows: usize) -> usize {
+        max_rows + max_rows / 4 + 1
+    }
+
+    pub fn init(slice: &'a mut [u8], key_size: usize) -> Result<Self, Error> {
+        let mut index = Self::split(slice, key_size)?;
+
+        let capacity = index.slots.len() / (HASH_SIZE + key_size);
+        if capacity == 0 || u32::try_from(capacity).is_err() || u32::try_from(key_size).is_err() {
+            return Err(Error::WrongSegment);
+        }
+
+        index.header.magic = HASH_INDEX_MAGIC;
+        index.header.capacity = u32::to_be_bytes(capacity as u32);
+        index.header.key_size = u32::to_be_bytes(key_size as u32);
+        index.clear();
+
+        Ok(index)
+    }
+
+    pub fn from_slice(slice: &'a mut [u8]) -> Result<Self, Error> {
+        if slice.len() < mem::size_of::<HashIndexHeader>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let key_size = {
+            let header: &HashIndexHeader =
+                bytemuck::from_bytes(&slice[..mem::size_of::<HashIndexHeader>()]);
+            u32::from_be_bytes(header.key_size) as usize
+        };
+
+        let index = Self::split(slice, key_size)?;
+
+        if index.header.magic != HASH_INDEX_MAGIC
+            || index.capacity() == 0
+            || index.capacity() * index.slot_size() != index.slots.len()
+        {
+            return Err(Error::WrongSegment);
+        }
+
+        Ok(index)
+    }
+
+    fn split(slice: &'a mut [u8], key_size: usize) -> Result<Self, Error> {
+        if slice.len() < mem::size_of::<HashIndexHeader>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (header, slots) = slice.split_at_mut(mem::size_of::<HashIndexHeader>());
+
+        let header: &mut [[u8; mem::size_of::<HashIndexHeader>()]] = cast_slice_mut(header);
+        let header: &mut HashIndexHeader = cast_mut(&mut header[0]);
+
+        let slot_size = HASH_SIZE + key_size;
+        let slot_count = slots.len() / slot_size;
+        let slots = &mut slots[..slot_count * slot_size];
+
+        Ok(Self { header, slots })
+    }
+
+    pub fn capacity(&self) -> usize {
+        u32::from_be_bytes(self.header.capacity) as usize
+    }
+
+    pub fn len(&self) -> usize {
+        u32::from_be_bytes(self.header.len) as usize
+    }
+
+    pub fn key_size(&self) -> usize {
+        u32::from_be_bytes(self.header.key_size) as usize
+    }
+
+    fn tombstones(&self) -> usize {
+        u32::from_be_bytes(self.header.tombstones) as usize
+    }
+
+    fn slot_size(&self) -> usize {
+        HASH_SIZE + self.key_size()
+    }
+
+    /// Encoded primary keys of the rows, which values have the given `hash`
+    pub fn keys(&self, hash: u64) -> impl Iterator<Item = &[u8]> + '_ {
+        let hash = stored_hash(hash);
+        self.probe(hash)
+            .take_while(move |&position| self.hash_at(position) != EMPTY)
+            .filter(move |&position| self.hash_at(position) == hash)
+            .map(move |position| self.key_at(position))
+    }
+
+    /// Adds the `hash - key` pair to the index, the pair should not be present in it
+    pub fn insert(&mut self, hash: u64, key: &[u8]) {
+        assert_eq!(key.len(), self.key_size(), "Wrong size of the encoded key");
+        assert!(
+            self.len() + 1 < self.capacity(),
+            "Hash index is full, it is a sign of data corruption"
+        );
+
+        // The pair may take the last empty slot, so the tombstones are cleaned up beforehand
+        if self.len() + self.tombstones() + 1 >= self.capacity() {
+            self.rebuild();
+        }
+
+        let hash = stored_hash(hash);
+        let position = self
+            .probe(hash)
+            .find(|&position| matches!(self.hash_at(position), EMPTY | DELETED))
+            // There is at least one empty slot, so the probe sequence will find it
+            .unwrap();
+
+        if self.hash_at(position) == DELETED {
+            self.header.tombstones = u32::to_be_bytes(self.tombstones() as u32 - 1);
+        }
+
+        self.set_hash(position, hash);
+        let slot_size = self.slot_size();
+        self.slots[position * slot_size + HASH_SIZE..(position + 1) * slot_size]
+            .copy_from_slice(key);
+        self.header.len = u32::to_be_bytes(self.len() as u32 + 1);
+    }
+
+    /// Removes the `hash - key` pair from the index, returns `false` if it was not present
+    pub fn remove(&mut self, hash: u64, key: &[u8]) -> bool {
+        let hash = stored_hash(hash);
+        let position = self
+            .probe(hash)
+            .take_while(|&position| self.hash_at(position) != EMPTY)
+            .find(|&position| self.hash_at(position) == hash && self.key_at(position) == key);
+
+        let position = match position {
+            Some(position) => position,
+            None => return false,
+        };
+
+        self.set_hash(position, DELETED);
+        self.header.len = u32::to_be_bytes(self.len() as u32 - 1);
+        self.header.tombstones = u32::to_be_bytes(self.tombstones() as u32 + 1);
+
+        if 4 * self.tombstones() > self.capacity() {
+            self.rebuild();
+        }
+
+        true
+    }
+
+    /// Removes all the pairs from the index
+    pub fn clear(&mut self) {
+        for position in 0..self.capacity() {
+            self.set_hash(position, EMPTY);
+        }
+        self.header.len = u32::to_be_bytes(0);
+        self.header.tombstones = u32::to_be_bytes(0);
+    }
+
+    /// Removes the tombstones in place.
+    ///
+    /// Tombstones become empty slots, which may cut the probe sequences of the pairs after them.
+    /// The pairs are then visited in the probe order, starting after a slot, that was empty
+    /// before, so no probe sequence passes through it. Each pair is moved to the first empty slot
+    /// of its probe sequence, if it comes before the current slot of the pair.
+    fn rebuild(&mut self) {
+        let start = (0..self.capacity())
+            .find(|&position| self.hash_at(position) == EMPTY)
+            // There is always at least one empty slot
+            .unwrap();
+
+        for position in 0..self.capacity() {
+            if self.hash_at(position) == DELETED {
+                self.set_hash(position, EMPTY);
+            }
+        }
+        self.header.tombstones = u32::to_be_bytes(0);
+
+        let capacity = self.capacity();
+        let slot_size = self.slot_size();
+        for offset in 1..capacity {
+            let position = (start + offset) % capacity;
+            let hash = self.hash_at(position);
+            if hash == EMPTY {
+                continue;
+            }
+
+            let target = self
+                .probe(hash)
+                .find(|&target| target == position || self.hash_at(target) == EMPTY)
+                // The probe sequence visits all the slots
+                .unwrap();
+
+            if target != position {
+                self.slots.copy_within(
+                    position * slot_size..(position + 1) * slot_size,
+                    target * slot_size,
+                );
+                self.set_hash(position, EMPTY);
+            }
+        }
+    }
+
+    fn hash_at(&self, position: usize) -> u32 {
+        let offset = position * self.slot_size();
+        u32::from_be_bytes(self.slots[offset..offset + HASH_SIZE].try_into().unwrap())
+    }
+
+    fn key_at(&self, position: usize) -> &[u8] {
+        let slot_size = self.slot_size();
+        &self.slots[position * slot_size + HASH_SIZE..(position + 1) * slot_size]
+    }
+
+    fn set_hash(&mut self, position: usize, hash: u32) {
+        let offset = position * self.slot_size();
+        self.slots[offset..offset + HASH_SIZE].copy_from_slice(&hash.to_be_bytes());
+    }
+
+    /// Positions of the slots, where the stored `hash` may be located, each slot is visited once
+    fn probe(&self, hash: u32) -> impl Iterator<Item = usize> {
+        let capacity = self.capacity();
+        let start = hash as usize % capacity;
+        (0..capacity).map(move |offset| (start + offset) % capacity)
+    }
+}
+
+impl<'a> fmt::Debug for HashIndex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashIndex")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .field("key_size", &self.key_size())
+            .finish()
+    }
+}
+
+/// 64-bit FNV-1a hash of the borsh-serialized `value`
+pub fn value_hash(value: &Data) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    value
+        .try_to_vec()
+        .expect("Serialization to Vec should be infallible")
+        .iter()
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// 64-bit `hash` folded to 32 bits, avoiding the values of empty and deleted slots
+fn stored_hash(hash: u64) -> u32 {
+    match (hash >> 32) as u32 ^ hash as u32 {
+        hash @ (EMPTY | DELETED) => hash + 2,
+        hash => hash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn insert_and_remove() {
+        let mut slice = vec![0; HashIndex::size(4, 2)];
+        let mut index = HashIndex::init(&mut slice, 2).unwrap();
+
+        assert_eq!(index.capacity(), 6);
+
+        // All these hashes start probing from the same slot
+        index.insert(3, &[0, 1]);
+        index.insert(9, &[0, 2]);
+        index.insert(15, &[0, 3]);
+        // Pairs with the same hash are distinguished by keys
+        index.insert(9, &[0, 4]);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.keys(9).collect::<Vec<_>>(), vec![&[0, 2], &[0, 4]]);
+
+        assert!(index.remove(9, &[0, 2]));
+        assert!(!index.remove(9, &[0, 2]));
+        assert_eq!(index.keys(9).collect::<Vec<_>>(), vec![&[0, 4]]);
+        // The tombstone does not break probe sequences
+        assert_eq!(index.keys(15).collect::<Vec<_>>(), vec![&[0, 3]]);
+        assert_eq!(index.keys(21).count(), 0);
+
+        let index = HashIndex::from_slice(&mut slice).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.key_size(), 2);
+        assert_eq!(index.keys(3).collect::<Vec<_>>(), vec![&[0, 1]]);
+    }
+
+    #[test]
+    fn rebuild_keeps_probe_sequences() {
+        let mut slice = vec![0; HashIndex::size(8, 1)];
+        let mut index = HashIndex::init(&mut slice, 1).unwrap();
+
+        assert_eq!(index.capacity(), 11);
+
+        // The cluster wraps around the end of the table
+        for (hash, key) in [(9, 1), (20, 2), (31, 3), (10, 4), (42, 5)] {
+            index.insert(hash, &[key]);
+        }
+        assert!(index.remove(9, &[1]));
+        assert!(index.remove(31, &[3]));
+        index.rebuild();
+
+        assert_eq!(index.tombstones(), 0);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.keys(20).collect::<Vec<_>>(), vec![&[2]]);
+        assert_eq!(index.keys(10).collect::<Vec<_>>(), vec![&[4]]);
+        assert_eq!(index.keys(42).collect::<Vec<_>>(), vec![&[5]]);
+        // The pairs are moved to the beginning of their probe sequences
+        assert_eq!(index.hash_at(9), 20);
+        assert_eq!(index.hash_at(10), 10);
+        assert_eq!(index.hash_at(0), 42);
+    }
+
+    #[test]
+    fn tombstones_are_compacted() {
+        let mut slice = vec![0; HashIndex::size(4, 1)];
+        let mut index = HashIndex::init(&mut slice, 1).unwrap();
+
+        index.insert(5, &[5]);
+        for round in 0..20 {
+            index.insert(round, &[1]);
+            assert!(index.remove(round, &[1]));
+            assert!(4 * index.tombstones() <= index.capacity());
+        }
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.keys(5).collect::<Vec<_>>(), vec![&[5]]);
+    }
+
+    #[test]
+    fn value_hashes() {
+        assert_eq!(value_hash(&Data::Int(1)), value_hash(&Data::Int(1)));
+        assert!(value_hash(&Data::Int(1)) != value_hash(&Data::Int(2)));
+        assert!(value_hash(&Data::Int(1)) != value_hash(&Data::Int64(1)));
+    }
+
+    #[test]
+    fn wrong_segment() {
+        let mut slice = vec![0; HashIndex::size(4, 2)];
+        let err = HashIndex::from_slice(&mut slice).unwrap_err();
+        assert_eq!(err, Error::WrongSegment);
+    }
+}
@@ -8,7 +8,12 @@ use super::column::ColumnHeader;
 use crate::DataType;
 
 const INDEX_MAGIC: [u8; 16] = *b"Solcery_DB_Index";
-const CURRENT_VERSION: u16 = 0;
+/// Tables of the earlier versions store borsh-encoded values or fixed-size trees in the columns
+/// instead of the [`ByteTree`](solcery_reltab::byte_tree::ByteTree)s of the
+/// [`ByteCodec`](crate::ByteCodec) encodings, so they can't be opened
+const CURRENT_VERSION: u16 = 4;
+/// Tables of this version have [`Metadata`](super::metadata::Metadata) after the column headers
+pub const METADATA_VERSION: u16 = 5;
 
 #[repr(C)]
 #[derive(Pod, Clone, Copy, Zeroable)]
@@ -51,6 +56,18 @@ impl Index {
         u16::from_be_bytes(self.db_version)
     }
 
+    pub fn is_supported(&self) -> bool {
+        self.version() >= CURRENT_VERSION
+    }
+
+    pub fn has_metadata(&self) -> bool {
+        self.version() >= METADATA_VERSION
+    }
+
+    pub unsafe fn set_version(&mut self, version: u16) {
+        self.db_version = u16::to_be_bytes(version);
+    }
+
     pub fn generate_id(&mut self) -> u32 {
         let id = u32::from_be_bytes(self.column_id_autoincrement);
         self.column_id_autoincrement = u32::to_be_bytes(id + 1);
@@ -133,6 +150,8 @@ mod tests {
         assert_eq!(index.table_name(), table_name.to_string());
         assert_eq!(index.max_rows(), max_rows);
         assert_eq!(index.version(), CURRENT_VERSION);
+        assert!(index.is_supported());
+        assert!(!index.has_metadata());
         assert_eq!(index.column_id_autoincrement, u32::to_be_bytes(0));
 
         assert_eq!(index.generate_id(), 0);
@@ -144,5 +163,22 @@ mod tests {
         }
 
         assert_eq!(index.column_count(), 235);
+
+        unsafe {
+            index.set_version(METADATA_VERSION);
+        }
+        assert!(index.is_supported());
+        assert!(index.has_metadata());
+
+        unsafe {
+            index.set_version(1);
+        }
+        assert!(!index.is_supported());
+
+        // Tables with metadata, created before the columns were moved to `ByteTree`s
+        unsafe {
+            index.set_version(3);
+        }
+        assert!(!index.is_supported());
     }
 }
@@ -0,0 +1,143 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use account_fs::SegmentId;
+
+use crate::{ColumnId, DataType, Error, ForeignKey, OnDelete};
+
+/// Size of the serialized [`ColumnId`]
+const COLUMN_ID_SIZE: usize = 4;
+/// Size of the serialized [`SegmentId`]
+const SEGMENT_ID_SIZE: usize = 32 + 4;
+/// Size of the serialized [`ForeignKey`]
+const FOREIGN_KEY_SIZE: usize = COLUMN_ID_SIZE + SEGMENT_ID_SIZE + 1;
+/// Size of the serialized [`Referrer`]
+const REFERRER_SIZE: usize = SEGMENT_ID_SIZE + COLUMN_ID_SIZE + 1;
+/// Size of the serialized entry of [`Metadata::unique_indexes`]
+const UNIQUE_INDEX_SIZE: usize = COLUMN_ID_SIZE + SEGMENT_ID_SIZE;
+
+/// Table properties, which do not fit in the fixed-sized [`Index`](super::index::Index)
+///
+/// It is stored borsh-serialized right after the column headers, only in tables created with
+/// [`TableOptions`](crate::TableOptions).
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    pub key_schema: Vec<DataType>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub referrers: Vec<Referrer>,
+    /// Segment of the [`ChangeLog`](super::change_log::ChangeLog), if it is enabled
+    pub change_log: Option<SegmentId>,
+    /// Column with expiration timestamps of the rows, see
+    /// [`DB::purge_expired`](crate::DB::purge_expired)
+    pub expiry_column: Option<ColumnId>,
+    /// Segments of the [`HashIndex`](super::hash_index::HashIndex)es of the unique columns, see
+    /// [`DB::make_unique`](crate::DB::make_unique)
+    pub unique_indexes: Vec<(ColumnId, SegmentId)>,
+}
+
+/// Column of the other table, which references this table
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Referrer {
+    pub table: SegmentId,
+    pub column: ColumnId,
+    pub on_delete: OnDelete,
+}
+
+impl Metadata {
+    /// Space reserved for the metadata of a table with `max_columns` columns right after the
+    /// column headers, when the table is created with this metadata
+    ///
+    /// Each column may get a foreign key, a unique index and one referrer, and the expiry column
+    /// may be set later. A table, referenced by more columns than it has, may still get
+    /// [`Error::MetadataOverflow`].
+    pub fn reserved_size(&self, max_columns: usize) -> Result<usize, Error> {
+        let size = self
+            .try_to_vec()
+            .map_err(|_| Error::MetadataOverflow)?
+            .len();
+        let expiry_column = if self.expiry_column.is_none() {
+            COLUMN_ID_SIZE
+        } else {
+            0
+        };
+
+        Ok(size
+            + expiry_column
+            + max_columns * (FOREIGN_KEY_SIZE + REFERRER_SIZE + UNIQUE_INDEX_SIZE))
+    }
+
+    pub fn read(slice: &[u8]) -> Result<Self, Error> {
+        Self::deserialize(&mut &slice[..]).map_err(|_| Error::WrongSegment)
+    }
+
+    pub fn write(&self, slice: &mut [u8]) -> Result<(), Error> {
+        let bytes = self.try_to_vec().map_err(|_| Error::MetadataOverflow)?;
+
+        if bytes.len() > slice.len() {
+            return Err(Error::MetadataOverflow);
+        }
+
+        slice[..bytes.len()].copy_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn read_write() {
+        let mut slice = [0; 256];
+        assert_eq!(Metadata::read(&slice).unwrap(), Metadata::default());
+
+        let metadata = Metadata {
+            key_schema: vec![DataType::Int, DataType::ShortString],
+            ..Default::default()
+        };
+        metadata.write(&mut slice).unwrap();
+        assert_eq!(Metadata::read(&slice).unwrap(), metadata);
+
+        let mut small_slice = [0; 4];
+        let err = metadata.write(&mut small_slice).unwrap_err();
+        assert_eq!(err, Error::MetadataOverflow);
+    }
+
+    #[test]
+    fn reserved_size() {
+        let segment = SegmentId {
+            pubkey: Default::default(),
+            id: 0,
+        };
+        let full = Metadata {
+            key_schema: vec![DataType::Int, DataType::ShortString],
+            foreign_keys: vec![
+                ForeignKey {
+                    column: ColumnId::new(0),
+                    table: segment,
+                    on_delete: OnDelete::Restrict,
+                };
+                2
+            ],
+            referrers: vec![
+                Referrer {
+                    table: segment,
+                    column: ColumnId::new(0),
+                    on_delete: OnDelete::Cascade,
+                };
+                2
+            ],
+            change_log: Some(segment),
+            expiry_column: Some(ColumnId::new(1)),
+            unique_indexes: vec![(ColumnId::new(0), segment); 2],
+        };
+        let initial = Metadata {
+            key_schema: full.key_schema.clone(),
+            change_log: full.change_log,
+            ..Default::default()
+        };
+
+        let full_size = full.try_to_vec().unwrap().len();
+        assert_eq!(initial.reserved_size(2).unwrap(), full_size);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod catalog;
+pub mod change_log;
 pub mod column;
 pub mod column_id;
+pub mod hash_index;
 pub mod index;
+pub mod metadata;
@@ -0,0 +1,253 @@
+use bytemuck::{cast_ref, cast_slice};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+use account_fs::{FSReader, SegmentId};
+
+use super::aggregate::aggregate_column;
+use super::blob::{blob_handle, read_payload};
+use super::byte_column::view_column_slice;
+use super::column::ColumnView;
+use super::cursor::page_from;
+use super::raw::column::ColumnHeader;
+use super::raw::index::Index;
+use super::raw::metadata::Metadata;
+use super::{Agg, ColumnId, ColumnInfo, Cursor, Data, DataType, DbRow, Error, KeyRange, Page};
+
+/// Read-only access to the [`DB`](crate::DB), which works on top of the [`FSReader`]
+///
+/// Account data is never borrowed mutably, so the FS accounts don't need to be writable. It
+/// allows other programs to read tables without taking write locks on the accounts.
+///
+/// Columns are opened as read-only views directly over the borrowed segments, nothing is copied.
+pub struct DBReader<'long: 'short, 'short> {
+    fs: Rc<FSReader<'long, 'short>>,
+    index: &'short Index,
+    column_headers: &'short [ColumnHeader],
+    metadata: Option<Metadata>,
+}
+
+impl<'long: 'short, 'short> DBReader<'long, 'short> {
+    /// Constructs [`DBReader`], assuming that the DB header is placed in the `segment`
+    pub fn from_segment(
+        fs: Rc<FSReader<'long, 'short>>,
+        segment: SegmentId,
+    ) -> Result<Self, Error> {
+        let db_segment = fs.segment(&segment)?;
+
+        if db_segment.len() < mem::size_of::<Index>() {
+            return Err(Error::WrongSegment);
+        }
+
+        let (index, columns) = db_segment.split_at(mem::size_of::<Index>());
+
+        let index: &[[u8; mem::size_of::<Index>()]] = cast_slice(index);
+        let index: &Index = cast_ref(&index[0]);
+
+        if !index.check_magic() || columns.len() < index.columns_size() {
+            return Err(Error::WrongSegment);
+        }
+
+        if !index.is_supported() {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        let (columns, metadata_slice) = columns.split_at(index.columns_size());
+
+        let metadata = if index.has_metadata() {
+            Some(Metadata::read(metadata_slice)?)
+        } else {
+            None
+        };
+
+        let columns: &[ColumnHeader] = cast_slice(columns);
+        let column_headers = &columns[..index.column_count()];
+
+        Ok(Self {
+            fs,
+            index,
+            column_headers,
+            metadata,
+        })
+    }
+
+    /// Returns descriptions of all the columns
+    pub fn columns(&self) -> Vec<ColumnInfo> {
+        self.column_headers
+            .iter()
+            .map(|header| ColumnInfo {
+                id: header.id(),
+                name: header.name(),
+                data_type: header.value_type(),
+                column_type: header.column_type(),
+            })
+            .collect()
+    }
+
+    /// Returns mapping from column names to their [`ColumnId`]s
+    pub fn column_ids(&self) -> BTreeMap<String, ColumnId> {
+        self.column_headers
+            .iter()
+            .map(|header| (header.name(), header.id()))
+            .collect()
+    }
+
+    /// Returns the name of the table
+    pub fn table_name(&self) -> String {
+        self.index.table_name()
+    }
+
+    /// Returns the type of the primary key
+    pub fn primary_key_type(&self) -> DataType {
+        self.index.primary_key_type()
+    }
+
+    /// Returns types of the primary key components, see [`DB::key_schema`](crate::DB::key_schema)
+    pub fn key_schema(&self) -> Vec<DataType> {
+        match &self.metadata {
+            Some(metadata) if !metadata.key_schema.is_empty() => metadata.key_schema.clone(),
+            _ => vec![self.index.primary_key_type()],
+        }
+    }
+
+    /// Gets value in the `column_id` by its `primary_key`.
+    pub fn value(&self, primary_key: Data, column_id: ColumnId) -> Result<Option<Data>, Error> {
+        self.with_column(column_id, |column| column.get_value(primary_key))?
+    }
+
+    /// Gets value in the `column_id` by its `secondary_key`, located in `key_column_id`.
+    pub fn value_secondary(
+        &self,
+        key_column_id: ColumnId,
+        secondary_key: Data,
+        column_id: ColumnId,
+    ) -> Result<Option<Data>, Error> {
+        match self.with_column(key_column_id, |column| column.get_key(secondary_key))?? {
+            Some(key) => self.value(key, column_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets payload of the blob in the `column_id` by its `primary_key`.
+    pub fn blob(&self, primary_key: Data, column_id: ColumnId) -> Result<Option<Vec<u8>>, Error> {
+        self.blob_chunk(primary_key, column_id, 0, usize::MAX)
+    }
+
+    /// Gets at most `len` bytes of the blob payload, starting from `offset`.
+    ///
+    /// The chunk is empty if the `offset` is beyond the end of the payload. It allows reading
+    /// payloads, which do not fit in a single response, chunk by chunk.
+    pub fn blob_chunk(
+        &self,
+        primary_key: Data,
+        column_id: ColumnId,
+        offset: usize,
+        len: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let handle = match blob_handle(self.value(primary_key, column_id)?)? {
+            Some(handle) => handle,
+            None => return Ok(None),
+        };
+
+        let slice = self.fs.segment(&handle.segment_id())?;
+        Ok(Some(read_payload(slice, &handle, offset, len)))
+    }
+
+    /// Gets [`BTreeMap`] of `column <-> value` for a given `primary_key`.
+    pub fn row(&self, primary_key: Data) -> Result<BTreeMap<ColumnId, Option<Data>>, Error> {
+        self.column_headers
+            .iter()
+            .map(|header| {
+                let value = self.value(primary_key.clone(), header.id())?;
+                Ok((header.id(), value))
+            })
+            .collect()
+    }
+
+    /// Gets [`BTreeMap`] of `column <-> value`, there `primary_key` is derived from the
+    /// `secondary_key` in the `key_column_id`.
+    pub fn row_secondary_key(
+        &self,
+        key_column_id: ColumnId,
+        secondary_key: Data,
+    ) -> Result<BTreeMap<ColumnId, Option<Data>>, Error> {
+        match self.with_column(key_column_id, |column| column.get_key(secondary_key))?? {
+            Some(key) => self.row(key),
+            None => Err(Error::SecondaryKeyWithNonExistentPrimaryKey),
+        }
+    }
+
+    /// Gets typed row for a given `primary_key`, columns are matched by names.
+    pub fn typed_row<Row: DbRow>(&self, primary_key: Data) -> Result<Row, Error> {
+        Row::from_row(self.row(primary_key)?, &self.column_ids())
+    }
+
+    /// Gets all `primary_key - value` pairs in the `column_id` with primary keys in the `range`.
+    ///
+    /// Pairs are returned in ascending order of the primary keys.
+    pub fn scan(&self, column_id: ColumnId, range: &KeyRange) -> Result<Vec<(Data, Data)>, Error> {
+        let bounds = match range.bounds() {
+            Some(bounds) => bounds,
+            None => return Ok(Vec::new()),
+        };
+
+        self.with_column(column_id, |column| Ok(column.range(bounds)?.collect()))?
+    }
+
+    /// Computes the aggregate function `agg` over the values of the column `column_id`, see
+    /// [`DB::aggregate`](crate::DB::aggregate)
+    pub fn aggregate(
+        &self,
+        column_id: ColumnId,
+        agg: Agg,
+        range: &KeyRange,
+    ) -> Result<Option<Data>, Error> {
+        let value_type = self.column_header(column_id)?.value_type();
+        self.with_column(column_id, |column| {
+            aggregate_column(column, value_type, agg, range)
+        })?
+    }
+
+    /// Returns a page of the column after the `cursor` position, see
+    /// [`DB::scan_from`](crate::DB::scan_from)
+    pub fn scan_from(&self, cursor: &Cursor, limit: usize) -> Result<Page, Error> {
+        self.with_column(cursor.column(), |column| page_from(column, cursor, limit))?
+    }
+
+    fn column_header(&self, column_id: ColumnId) -> Result<&'short ColumnHeader, Error> {
+        self.column_headers
+            .iter()
+            .find(|header| header.id() == column_id)
+            .ok_or(Error::NoSuchColumn)
+    }
+
+    /// Opens the column `column_id` as a read-only view and applies `f` to it
+    fn with_column<F, R>(&self, column_id: ColumnId, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&dyn ColumnView) -> R,
+    {
+        let header = self.column_header(column_id)?;
+
+        let slice = self.fs.segment(&header.segment_id())?;
+        let column = view_column_slice(
+            self.index.primary_key_type(),
+            header.value_type(),
+            header.column_type(),
+            slice,
+        )?;
+
+        Ok(f(column.as_ref()))
+    }
+}
+
+impl<'long, 'short> fmt::Debug for DBReader<'long, 'short> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DBReader")
+            .field("index", &self.index)
+            .field("column_headers", &self.column_headers)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use solana_program::pubkey::Pubkey;
+
+use super::{ColumnId, CompositeKey, Data, DataType, Error, LongString, MediumString, ShortString};
+
+/// Description of a column, derived from a [`DbRow`] struct field
+#[derive(PartialEq, Clone, Eq, Debug)]
+pub struct ColumnSchema {
+    /// Column name
+    pub name: String,
+    /// Type of the values in the column
+    pub data_type: DataType,
+    /// Should the column be created as a secondary key
+    pub is_secondary_key: bool,
+}
+
+/// Rust types, which can be stored in the [`DB`](crate::DB) column
+pub trait ColumnValue: Sized {
+    /// [`DataType`] of the column, storing this type
+    const DATA_TYPE: DataType;
+
+    /// Packs the value into [`Data`]
+    fn to_data(&self) -> Data;
+
+    /// Unpacks the value from [`Data`]
+    fn from_data(data: Data) -> Result<Self, Error>;
+}
+
+macro_rules! impl_column_value {
+    ($($typ:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl ColumnValue for $typ {
+                const DATA_TYPE: DataType = DataType::$variant;
+
+                fn to_data(&self) -> Data {
+                    Data::$variant(Clone::clone(self))
+                }
+
+                fn from_data(data: Data) -> Result<Self, Error> {
+                    match data {
+                        Data::$variant(value) => Ok(value),
+                        other => Err(Error::TypeMismatch {
+                            expected: DataType::$variant,
+                            got: other.data_type(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_column_value!(
+    i32 => Int,
+    i64 => Int64,
+    Pubkey => Pubkey,
+    ShortString => ShortString,
+    MediumString => MediumString,
+    LongString => LongString,
+    CompositeKey => Composite,
+);
+
+/// A struct, which fields are stored in the columns of the [`DB`](crate::DB)
+///
+/// Usually it is implemented with `#[derive(DbRow)]`.
+pub trait DbRow: Sized {
+    /// Columns, needed to store the struct
+    fn schema() -> Vec<ColumnSchema>;
+
+    /// Converts the struct to a row, suitable for [`DB::set_row`](crate::DB::set_row).
+    ///
+    /// `columns` maps column names to their [`ColumnId`]s.
+    fn to_row(&self, columns: &BTreeMap<String, ColumnId>) -> Result<Vec<(ColumnId, Data)>, Error>;
+
+    /// Builds the struct from a row, obtained by [`DB::row`](crate::DB::row)
+    ///
+    /// `columns` maps column names to their [`ColumnId`]s.
+    fn from_row(
+        row: BTreeMap<ColumnId, Option<Data>>,
+        columns: &BTreeMap<String, ColumnId>,
+    ) -> Result<Self, Error>;
+}
+
+#[doc(hidden)]
+pub fn lookup_column(columns: &BTreeMap<String, ColumnId>, name: &str) -> Result<ColumnId, Error> {
+    columns.get(name).copied().ok_or(Error::MissingColumn)
+}
+
+#[doc(hidden)]
+pub fn take_value(
+    row: &mut BTreeMap<ColumnId, Option<Data>>,
+    columns: &BTreeMap<String, ColumnId>,
+    name: &str,
+) -> Result<Option<Data>, Error> {
+    let column_id = lookup_column(columns, name)?;
+    row.remove(&column_id).ok_or(Error::MissingColumn)
+}
@@ -0,0 +1,267 @@
+use account_fs::SegmentId;
+use std::collections::BTreeSet;
+
+use super::raw::hash_index::{value_hash, HashIndex};
+use super::{ColumnId, ColumnType, Data, DataType, Error, FSCell, DB};
+
+impl<'long: 'short, 'short> DB<'long, 'short> {
+    /// Returns `true` if the values of the column `column_id` are unique.
+    ///
+    /// Values of [`ColumnType::OneToOne`] columns are always unique, [`ColumnType::RBTree`]
+    /// columns become unique with [`DB::make_unique`].
+    pub fn is_unique(&self, column_id: ColumnId) -> Result<bool, Error> {
+        let (_, header) = self.column_header(column_id)?;
+        Ok(header.column_type() == ColumnType::OneToOne || self.unique_index(column_id).is_some())
+    }
+
+    /// Forbids equal values in the [`ColumnType::RBTree`] column `column_id`.
+    ///
+    /// Unlike [`ColumnType::OneToOne`], the column does not get a reverse tree: uniqueness is
+    /// enforced by a hash index of the values, stored in its own segment. Each slot holds the
+    /// hash and the primary key of the row, so values with colliding hashes are told apart by
+    /// comparing them. It takes 4 bytes plus the size of the primary key per slot with five slots
+    /// per four rows, and the values are not ordered. The table should be created with
+    /// [`TableOptions`](crate::TableOptions).
+    ///
+    /// Returns [`Error::UniqueViolation`] if the column already contains equal values.
+    pub fn make_unique(&mut self, column_id: ColumnId) -> Result<(), Error> {
+        if self.metadata.is_none() {
+            return Err(Error::NoMetadata);
+        }
+
+        if self.column_header(column_id)?.1.value_type() == DataType::Blob {
+            return Err(Error::BlobColumn);
+        }
+
+        if self.is_unique(column_id)? {
+            return Ok(());
+        }
+
+        let pairs = self.column_pairs(column_id)?;
+
+        let segment = init_hash_index(
+            &self.fs,
+            self.index.max_rows(),
+            self.index.primary_key_type().size(),
+        )?;
+
+        let result = self.fill_hash_index(segment, pairs).and_then(|()| {
+            self.metadata_mut()?
+                .unique_indexes
+                .push((column_id, segment));
+            self.save_metadata()
+        });
+
+        if let Err(err) = result {
+            self.metadata_mut()?
+                .unique_indexes
+                .retain(|&(column, _)| column != column_id);
+            self.fs.borrow_mut().deallocate_segment(&segment)?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the unique constraint, added by [`DB::make_unique`], and frees its hash index
+    pub fn drop_unique(&mut self, column_id: ColumnId) -> Result<(), Error> {
+        let segment = match self.unique_index(column_id) {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+
+        self.metadata_mut()?
+            .unique_indexes
+            .retain(|&(column, _)| column != column_id);
+        self.save_metadata()?;
+
+        self.fs.borrow_mut().deallocate_segment(&segment)?;
+
+        Ok(())
+    }
+
+    /// [`SegmentId`] of the hash index of the column `column_id`, if it is unique
+    pub(crate) fn unique_index(&self, column_id: ColumnId) -> Option<SegmentId> {
+        self.metadata
+            .as_ref()?
+            .unique_indexes
+            .iter()
+            .find(|&&(column, _)| column == column_id)
+            .map(|&(_, segment)| segment)
+    }
+
+    /// Checks that setting `value` for the `primary_key` does not violate the unique constraint.
+    ///
+    /// Returns hashes of the old and the new values, if the hash index should be updated with
+    /// [`DB::update_unique_index`] after the value is set.
+    pub(crate) fn check_unique(
+        &self,
+        primary_key: &Data,
+        column_id: ColumnId,
+        value: &Data,
+    ) -> Result<Option<(Option<u64>, u64)>, Error> {
+        if self.unique_index(column_id).is_none() {
+            return Ok(None);
+        }
+
+        let old_value = self.value(primary_key.clone(), column_id)?;
+        if old_value.as_ref() == Some(value) {
+            return Ok(None);
+        }
+
+        let is_taken = self
+            .unique_keys_with_value(column_id, value)?
+            .into_iter()
+            .flatten()
+            .any(|key| &key != primary_key);
+        if is_taken {
+            return Err(Error::UniqueViolation);
+        }
+
+        Ok(Some((
+            old_value.map(|old_value| value_hash(&old_value)),
+            value_hash(value),
+        )))
+    }
+
+    /// Primary keys of the rows with `value` in the unique column `column_id`, found by the hash
+    /// index, `None` if the column has no hash index
+    pub(crate) fn unique_keys_with_value(
+        &self,
+        column_id: ColumnId,
+        value: &Data,
+    ) -> Result<Option<Vec<Data>>, Error> {
+        let segment = match self.unique_index(column_id) {
+            Some(segment) => segment,
+            None => return Ok(None),
+        };
+
+        let key_type = self.index.primary_key_type();
+        let candidates: Vec<Data> = self.with_hash_index(segment, |index| {
+            index
+                .keys(value_hash(value))
+                .map(|key| key_type.decode(key))
+                .collect()
+        })?;
+
+        // Different values may have the same hash, so the values of the candidates are compared
+        let mut keys = Vec::new();
+        for key in candidates {
+            if self.value(key.clone(), column_id)?.as_ref() == Some(value) {
+                keys.push(key);
+            }
+        }
+
+        Ok(Some(keys))
+    }
+
+    /// Replaces the `old_hash - primary_key` pair with the `new_hash - primary_key` pair in the
+    /// hash index of the column `column_id`
+    pub(crate) fn update_unique_index(
+        &self,
+        column_id: ColumnId,
+        primary_key: &Data,
+        old_hash: Option<u64>,
+        new_hash: Option<u64>,
+    ) -> Result<(), Error> {
+        let segment = match self.unique_index(column_id) {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+
+        let key = self.encode_key(primary_key)?;
+
+        self.with_hash_index(segment, |index| {
+            if let Some(hash) = old_hash {
+                index.remove(hash, &key);
+            }
+            if let Some(hash) = new_hash {
+                index.insert(hash, &key);
+            }
+        })
+    }
+
+    /// Replaces the content of the hash index in the `segment` with the `primary_key - value`
+    /// `pairs`, returns [`Error::UniqueViolation`] if some of the values are equal
+    pub(crate) fn fill_hash_index(
+        &self,
+        segment: SegmentId,
+        pairs: Vec<(Data, Data)>,
+    ) -> Result<(), Error> {
+        let distinct_values: BTreeSet<&Data> = pairs.iter().map(|(_, value)| value).collect();
+        if distinct_values.len() != pairs.len() {
+            return Err(Error::UniqueViolation);
+        }
+
+        let pairs = pairs
+            .iter()
+            .map(|(key, value)| Ok((value_hash(value), self.encode_key(key)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.with_hash_index(segment, |index| {
+            index.clear();
+            for (hash, key) in pairs {
+                index.insert(hash, &key);
+            }
+        })
+    }
+
+    /// All the `primary_key - value` pairs of the column `column_id`
+    fn column_pairs(&self, column_id: ColumnId) -> Result<Vec<(Data, Data)>, Error> {
+        let mut accessed_columns = self.accessed_columns.borrow_mut();
+        self.load_column(&mut accessed_columns, column_id)?;
+
+        Ok(accessed_columns[&column_id].pairs().collect())
+    }
+
+    /// Encoding of the primary key, stored in the hash indexes
+    fn encode_key(&self, primary_key: &Data) -> Result<Vec<u8>, Error> {
+        let mut key = vec![0; self.index.primary_key_type().size()];
+        primary_key.encode(&mut key)?;
+        Ok(key)
+    }
+
+    fn with_hash_index<F, R>(&self, segment: SegmentId, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut HashIndex<'_>) -> R,
+    {
+        let mut fs = self.fs.borrow_mut();
+        let slice = fs.segment(&segment)?;
+        let result = HashIndex::from_slice(slice).map(|mut index| f(&mut index));
+        unsafe {
+            // # Safety
+            // `slice` is not used after this point
+            fs.release_borrowed_segment(&segment);
+        }
+
+        result
+    }
+}
+
+/// Allocates and initializes an empty hash index segment for a table with `max_rows` rows and
+/// primary keys of `key_size` bytes
+fn init_hash_index(
+    fs: &FSCell<'_, '_>,
+    max_rows: usize,
+    key_size: usize,
+) -> Result<SegmentId, Error> {
+    let mut fs = fs.borrow_mut();
+    let segment = fs.allocate_segment(HashIndex::size(max_rows, key_size))?;
+
+    // We've just successfully allocated this segment, so this operation is infailible;
+    let slice = fs.segment(&segment).unwrap();
+    let result = HashIndex::init(slice, key_size).map(|_| ());
+    unsafe {
+        // # Safety
+        // `slice` is not used after this point
+        fs.release_borrowed_segment(&segment);
+    }
+
+    match result {
+        Ok(()) => Ok(segment),
+        Err(err) => {
+            fs.deallocate_segment(&segment)?;
+            Err(err)
+        }
+    }
+}
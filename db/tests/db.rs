@@ -13,6 +13,22 @@ use solcery_db::*;
 
 #[test]
 fn creation_of_the_test_db() {
+    let fs_data = prepare_test_db();
+
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let expected_fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    assert_eq!(fs_data, expected_fs_data);
+}
+
+/// Creates a test DB on top of the clean FS image, the result is stored in the `prepared_db` image
+fn prepare_test_db() -> FSAccounts {
     let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
 
     let mut file = File::open(filename).unwrap();
@@ -106,21 +122,12 @@ fn creation_of_the_test_db() {
         assert_eq!(old_val, None);
     }
 
-    dbg!(segment.pubkey.to_bytes());
+    assert_eq!(segment, DB_SEGMENT);
     drop(db);
     drop(fs);
     drop(account_infos);
 
-    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
-
-    let mut file = File::open(filename).unwrap();
-
-    let mut db_fs_bytes = Vec::new();
-    file.read_to_end(&mut db_fs_bytes).unwrap();
-
-    let expected_fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
-
-    assert_eq!(fs_data, expected_fs_data);
+    fs_data
 }
 
 const DB_SEGMENT: SegmentId = SegmentId {
@@ -588,6 +595,1748 @@ fn drop_db() {
     db.drop_db().unwrap();
 }
 
+#[test]
+fn drop_db_checks_segments_first() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let (_, players_segment) = DB::init_in_segment_with_options(
+        fs.clone(),
+        "Players",
+        1,
+        4,
+        DataType::Int,
+        TableOptions::default(),
+    )
+    .unwrap();
+
+    let (mut scores, scores_segment) = DB::init_in_segment_with_options(
+        fs.clone(),
+        "Scores",
+        2,
+        4,
+        DataType::Int,
+        TableOptions::default(),
+    )
+    .unwrap();
+    let player_column = scores.add_column("Player", DataType::Int, false).unwrap();
+    let replay_column = scores.add_column("Replay", DataType::Blob, false).unwrap();
+    scores
+        .add_foreign_key(player_column, players_segment, OnDelete::Restrict)
+        .unwrap();
+
+    // The replay does not fit in the account with the tables
+    let replay = vec![7; 1_200];
+    scores
+        .set_blob(Data::Int(10), replay_column, &replay)
+        .unwrap();
+    let replay_account = match scores.value(Data::Int(10), replay_column).unwrap() {
+        Some(Data::Blob(handle)) => handle.segment_id().pubkey,
+        value => panic!("Unexpected replay value: {:?}", value),
+    };
+    assert_ne!(replay_account, scores_segment.pubkey);
+
+    drop(scores);
+    drop(fs);
+
+    // The account with the replay is missing, so nothing is deleted
+    {
+        let fs = Rc::new(RefCell::new(
+            FS::from_account_iter(
+                &program_id,
+                &mut account_infos
+                    .iter()
+                    .filter(|account| *account.key != replay_account),
+            )
+            .unwrap(),
+        ));
+
+        let scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+        let err = scores.drop_db().unwrap_err();
+        assert_eq!(err, Error::NotAllColumnsArePresent);
+    }
+
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+    assert_eq!(
+        scores.foreign_keys(),
+        vec![ForeignKey {
+            column: player_column,
+            table: players_segment,
+            on_delete: OnDelete::Restrict,
+        }]
+    );
+    assert_eq!(
+        scores.blob(Data::Int(10), replay_column).unwrap(),
+        Some(replay)
+    );
+
+    scores.drop_db().unwrap();
+
+    let players = DB::from_segment(fs.clone(), players_segment).unwrap();
+    players.drop_db().unwrap();
+}
+
+#[test]
+fn rename_column() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+
+    let err = db
+        .rename_column(
+            name_column,
+            "An Entirely Too Long Name For Such A Small Column Which Would Obviously Be An Overkill",
+        )
+        .unwrap_err();
+    assert_eq!(err, Error::NameTooLong);
+
+    db.rename_column(name_column, "First name").unwrap();
+
+    let val = db.value(Data::Int(0), name_column).unwrap();
+    assert_eq!(
+        val,
+        Some(Data::ShortString(ShortString::try_from("Alice").unwrap()))
+    );
+}
+
+#[test]
+fn alter_column_type() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let age_column = ColumnId::new(1);
+
+    db.alter_column_type(age_column, DataType::Int64, |value| {
+        value.convert(DataType::Int64)
+    })
+    .unwrap();
+
+    let val = db.value(Data::Int(0), age_column).unwrap();
+    assert_eq!(val, Some(Data::Int64(22)));
+
+    let val = db.value(Data::Int(4), age_column).unwrap();
+    assert_eq!(val, Some(Data::Int64(30)));
+
+    let old_val = db
+        .set_value(Data::Int(1), age_column, Data::Int64(24))
+        .unwrap();
+    assert_eq!(old_val, Some(Data::Int64(23)));
+}
+
+#[test]
+fn alter_column_type_lossy() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+
+    let err = db
+        .alter_column_type(name_column, DataType::Int, |value| {
+            value.convert(DataType::Int)
+        })
+        .unwrap_err();
+    assert_eq!(err, Error::LossyConversion);
+
+    // The converter must return values of the new type
+    let err = db
+        .alter_column_type(name_column, DataType::Int, Ok)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::ShortString,
+        }
+    );
+
+    let val = db.value(Data::Int(0), name_column).unwrap();
+    assert_eq!(
+        val,
+        Some(Data::ShortString(ShortString::try_from("Alice").unwrap()))
+    );
+}
+
+#[test]
+fn composite_primary_key() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let options = TableOptions {
+        key_schema: vec![DataType::Int, DataType::ShortString],
+        ..Default::default()
+    };
+
+    let err = DB::init_in_segment(fs.clone(), "Scores", 2, 4, DataType::Composite).unwrap_err();
+    assert_eq!(err, Error::WrongKeySchema);
+
+    let (mut db, _) =
+        DB::init_in_segment_with_options(fs.clone(), "Scores", 2, 4, DataType::Composite, options)
+            .unwrap();
+
+    assert_eq!(db.key_schema(), vec![DataType::Int, DataType::ShortString]);
+
+    let score_column = db.add_column("Score", DataType::Int, false).unwrap();
+
+    let key = |game: i32, player: &str| {
+        Data::Composite(
+            CompositeKey::new(&[
+                Data::Int(game),
+                Data::ShortString(ShortString::try_from(player).unwrap()),
+            ])
+            .unwrap(),
+        )
+    };
+
+    db.set_value(key(2, "Bob"), score_column, Data::Int(10))
+        .unwrap();
+    db.set_value(key(1, "Eve"), score_column, Data::Int(20))
+        .unwrap();
+    db.set_value(key(2, "Alice"), score_column, Data::Int(30))
+        .unwrap();
+
+    let wrong_key = Data::Composite(CompositeKey::new(&[Data::Int(2)]).unwrap());
+    let err = db
+        .set_value(wrong_key, score_column, Data::Int(40))
+        .unwrap_err();
+    assert_eq!(err, Error::WrongKeySchema);
+
+    // Keys are checked on all the write paths
+    let wrong_key = Data::Composite(CompositeKey::new(&[Data::Int(2)]).unwrap());
+    let err = db
+        .set_row(wrong_key.clone(), vec![(score_column, Data::Int(40))])
+        .unwrap_err();
+    assert_eq!(err, Error::WrongKeySchema);
+    let err = db.delete_row(wrong_key).unwrap_err();
+    assert_eq!(err, Error::WrongKeySchema);
+    let err = db.delete_value(Data::Int(2), score_column).unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Composite,
+            got: DataType::Int,
+        }
+    );
+
+    db.set_value(key(3, "Bob"), score_column, Data::Int(50))
+        .unwrap();
+
+    let game = db.prefix_scan(score_column, &[Data::Int(2)]).unwrap();
+    assert_eq!(
+        game,
+        vec![
+            (key(2, "Alice"), Data::Int(30)),
+            (key(2, "Bob"), Data::Int(10)),
+        ]
+    );
+
+    let err = db.prefix_scan(score_column, &[Data::Int64(2)]).unwrap_err();
+    assert_eq!(err, Error::WrongKeySchema);
+}
+
+#[derive(DbRow, Debug, PartialEq)]
+struct Person {
+    #[db(name = "Name", secondary_key)]
+    name: ShortString,
+    #[db(name = "Age")]
+    age: Option<i32>,
+}
+
+#[test]
+fn typed_rows() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    assert_eq!(
+        Person::schema(),
+        vec![
+            ColumnSchema {
+                name: String::from("Name"),
+                data_type: DataType::ShortString,
+                is_secondary_key: true,
+            },
+            ColumnSchema {
+                name: String::from("Age"),
+                data_type: DataType::Int,
+                is_secondary_key: false,
+            },
+        ]
+    );
+
+    assert_eq!(
+        db.columns(),
+        vec![
+            ColumnInfo {
+                id: ColumnId::new(0),
+                name: String::from("Name"),
+                data_type: DataType::ShortString,
+                column_type: ColumnType::OneToOne,
+            },
+            ColumnInfo {
+                id: ColumnId::new(1),
+                name: String::from("Age"),
+                data_type: DataType::Int,
+                column_type: ColumnType::RBTree,
+            },
+        ]
+    );
+
+    let alice: Person = db.typed_row(Data::Int(0)).unwrap();
+    assert_eq!(
+        alice,
+        Person {
+            name: ShortString::try_from("Alice").unwrap(),
+            age: Some(22),
+        }
+    );
+
+    let frank = Person {
+        name: ShortString::try_from("Frank").unwrap(),
+        age: None,
+    };
+    let had_old_values = db.set_typed_row(Data::Int(5), &frank).unwrap();
+    assert!(!had_old_values);
+
+    let obtained: Person = db.typed_row(Data::Int(5)).unwrap();
+    assert_eq!(obtained, frank);
+
+    // `None` fields delete the old values
+    let older_alice = Person {
+        name: ShortString::try_from("Alice").unwrap(),
+        age: None,
+    };
+    let had_old_values = db.set_typed_row(Data::Int(0), &older_alice).unwrap();
+    assert!(had_old_values);
+
+    let obtained: Person = db.typed_row(Data::Int(0)).unwrap();
+    assert_eq!(obtained, older_alice);
+    assert_eq!(db.value(Data::Int(0), ColumnId::new(1)).unwrap(), None);
+
+    // Columns are added all at once or not at all
+    let columns_before = db.columns();
+    let schema = vec![
+        ColumnSchema {
+            name: String::from("Height"),
+            data_type: DataType::Int,
+            is_secondary_key: false,
+        },
+        ColumnSchema {
+            name: "Too long column name".repeat(10),
+            data_type: DataType::Int,
+            is_secondary_key: false,
+        },
+    ];
+    let err = db.add_columns(&schema).unwrap_err();
+    assert_eq!(err, Error::NameTooLong);
+    assert_eq!(db.columns(), columns_before);
+
+    let err = db.typed_row::<Person>(Data::Int(7)).unwrap_err();
+    assert_eq!(err, Error::MissingValue);
+}
+
+#[test]
+fn foreign_keys() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let players_segment = {
+        let (mut players, segment) = DB::init_in_segment_with_options(
+            fs.clone(),
+            "Players",
+            2,
+            4,
+            DataType::Int,
+            TableOptions::default(),
+        )
+        .unwrap();
+
+        let name_column = players
+            .add_column("Name", DataType::ShortString, false)
+            .unwrap();
+        for (id, name) in [(1, "Alice"), (2, "Bob")] {
+            let name = Data::ShortString(ShortString::try_from(name).unwrap());
+            players.set_value(Data::Int(id), name_column, name).unwrap();
+        }
+
+        segment
+    };
+
+    let (scores_segment, player_column) = {
+        let (mut scores, segment) = DB::init_in_segment_with_options(
+            fs.clone(),
+            "Scores",
+            1,
+            4,
+            DataType::Int,
+            TableOptions::default(),
+        )
+        .unwrap();
+
+        let player_column = scores.add_column("Player", DataType::Int, false).unwrap();
+
+        let err = scores
+            .add_foreign_key(player_column, segment, OnDelete::Cascade)
+            .unwrap_err();
+        assert_eq!(err, Error::WrongForeignKey);
+
+        scores
+            .add_foreign_key(player_column, players_segment, OnDelete::Restrict)
+            .unwrap();
+
+        scores
+            .set_value(Data::Int(10), player_column, Data::Int(1))
+            .unwrap();
+        scores
+            .set_value(Data::Int(11), player_column, Data::Int(2))
+            .unwrap();
+
+        let err = scores
+            .set_value(Data::Int(12), player_column, Data::Int(3))
+            .unwrap_err();
+        assert_eq!(err, Error::ForeignKeyViolation);
+
+        (segment, player_column)
+    };
+
+    {
+        let mut players = DB::from_segment(fs.clone(), players_segment).unwrap();
+        let err = players.delete_row(Data::Int(1)).unwrap_err();
+        assert_eq!(err, Error::RowIsReferenced);
+    }
+
+    {
+        let mut scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+        scores.remove_foreign_key(player_column).unwrap();
+        scores
+            .add_foreign_key(player_column, players_segment, OnDelete::Cascade)
+            .unwrap();
+        assert_eq!(
+            scores.foreign_keys(),
+            vec![ForeignKey {
+                column: player_column,
+                table: players_segment,
+                on_delete: OnDelete::Cascade,
+            }]
+        );
+    }
+
+    {
+        let mut players = DB::from_segment(fs.clone(), players_segment).unwrap();
+        players.delete_row(Data::Int(1)).unwrap();
+        assert!(!players.contains_row(Data::Int(1)).unwrap());
+        assert!(players.contains_row(Data::Int(2)).unwrap());
+    }
+
+    {
+        let scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+        assert_eq!(scores.value(Data::Int(10), player_column).unwrap(), None);
+        assert_eq!(
+            scores.value(Data::Int(11), player_column).unwrap(),
+            Some(Data::Int(2))
+        );
+    }
+
+    // A restriction deep in the cascade prevents the whole deletion
+    let (awards_segment, score_column) = {
+        let (mut awards, segment) = DB::init_in_segment_with_options(
+            fs.clone(),
+            "Awards",
+            1,
+            4,
+            DataType::Int,
+            TableOptions::default(),
+        )
+        .unwrap();
+
+        let score_column = awards.add_column("Score", DataType::Int, false).unwrap();
+        awards
+            .add_foreign_key(score_column, scores_segment, OnDelete::Restrict)
+            .unwrap();
+        awards
+            .set_value(Data::Int(20), score_column, Data::Int(11))
+            .unwrap();
+
+        (segment, score_column)
+    };
+
+    {
+        let mut players = DB::from_segment(fs.clone(), players_segment).unwrap();
+        let err = players.delete_row(Data::Int(2)).unwrap_err();
+        assert_eq!(err, Error::RowIsReferenced);
+        assert!(players.contains_row(Data::Int(2)).unwrap());
+
+        let scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+        assert_eq!(
+            scores.value(Data::Int(11), player_column).unwrap(),
+            Some(Data::Int(2))
+        );
+    }
+
+    {
+        let mut awards = DB::from_segment(fs.clone(), awards_segment).unwrap();
+        awards.delete_row(Data::Int(20)).unwrap();
+        assert_eq!(awards.value(Data::Int(20), score_column).unwrap(), None);
+    }
+
+    // Cyclic references: the deleted table is reached again through the cascade
+    let best_score_column = {
+        let mut players = DB::from_segment(fs.clone(), players_segment).unwrap();
+        let best_score_column = players
+            .add_column("Best score", DataType::Int, false)
+            .unwrap();
+        players
+            .add_foreign_key(best_score_column, scores_segment, OnDelete::Cascade)
+            .unwrap();
+        players
+            .set_value(Data::Int(2), best_score_column, Data::Int(11))
+            .unwrap();
+        best_score_column
+    };
+
+    {
+        let mut players = DB::from_segment(fs.clone(), players_segment).unwrap();
+        players.delete_row(Data::Int(2)).unwrap();
+        assert!(!players.contains_row(Data::Int(2)).unwrap());
+        assert_eq!(
+            players.value(Data::Int(2), best_score_column).unwrap(),
+            None
+        );
+    }
+
+    let scores = DB::from_segment(fs.clone(), scores_segment).unwrap();
+    assert_eq!(scores.value(Data::Int(11), player_column).unwrap(), None);
+}
+
+#[test]
+fn select() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+    let age_column = ColumnId::new(1);
+
+    let keys = db.select().keys().unwrap();
+    assert_eq!(
+        keys,
+        vec![
+            Data::Int(0),
+            Data::Int(1),
+            Data::Int(2),
+            Data::Int(3),
+            Data::Int(4)
+        ]
+    );
+
+    let keys = db
+        .select()
+        .filter(age_column, Predicate::Eq(Data::Int(22)))
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(0), Data::Int(2)]);
+
+    let keys = db
+        .select()
+        .filter(name_column, Predicate::Prefix(String::from("C")))
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(2), Data::Int(3)]);
+
+    let keys = db
+        .select()
+        .filter(age_column, Predicate::Lt(Data::Int(21)))
+        .filter(name_column, Predicate::Prefix(String::from("C")))
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(3)]);
+
+    let rows = db
+        .select()
+        .filter(age_column, Predicate::Between(Data::Int(20), Data::Int(22)))
+        .limit(2)
+        .rows()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                Data::Int(0),
+                BTreeMap::from([
+                    (
+                        name_column,
+                        Some(Data::ShortString(ShortString::try_from("Alice").unwrap()))
+                    ),
+                    (age_column, Some(Data::Int(22))),
+                ])
+            ),
+            (
+                Data::Int(2),
+                BTreeMap::from([
+                    (
+                        name_column,
+                        Some(Data::ShortString(ShortString::try_from("Carol").unwrap()))
+                    ),
+                    (age_column, Some(Data::Int(22))),
+                ])
+            ),
+        ]
+    );
+
+    let keys = db
+        .select()
+        .filter(
+            name_column,
+            Predicate::Gt(Data::ShortString(ShortString::try_from("Chad").unwrap())),
+        )
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(4)]);
+
+    let keys = db
+        .select()
+        .filter(name_column, Predicate::Prefix(String::from("Ca")))
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(2)]);
+
+    let err = db
+        .select()
+        .filter(name_column, Predicate::Lt(Data::Int(5)))
+        .keys()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::ShortString,
+            got: DataType::Int,
+        }
+    );
+
+    let err = db
+        .select()
+        .filter(
+            age_column,
+            Predicate::Between(Data::Int(20), Data::Int64(22)),
+        )
+        .keys()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::Int64,
+        }
+    );
+
+    let err = db
+        .select()
+        .filter(age_column, Predicate::Prefix(String::from("2")))
+        .keys()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::ShortString,
+        }
+    );
+
+    let keys = db
+        .select()
+        .filter(name_column, Predicate::Prefix(String::from("C")))
+        .limit(1)
+        .keys()
+        .unwrap();
+    assert_eq!(keys, vec![Data::Int(2)]);
+
+    let keys = db.select().limit(0).keys().unwrap();
+    assert_eq!(keys, vec![]);
+}
+
+#[test]
+fn blob() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let bio_column = db.add_column("Bio", DataType::Blob, false).unwrap();
+
+    let bio = b"Alice is a cryptographer, who likes to send messages to Bob";
+    db.set_blob(Data::Int(0), bio_column, bio).unwrap();
+    assert_eq!(
+        db.blob(Data::Int(0), bio_column).unwrap(),
+        Some(bio.to_vec())
+    );
+
+    let handle = db.value(Data::Int(0), bio_column).unwrap().unwrap();
+    let err = db.set_value(Data::Int(1), bio_column, handle).unwrap_err();
+    assert_eq!(err, Error::BlobColumn);
+
+    let new_bio = b"Alice";
+    db.set_blob(Data::Int(0), bio_column, new_bio).unwrap();
+    assert_eq!(
+        db.blob(Data::Int(0), bio_column).unwrap(),
+        Some(new_bio.to_vec())
+    );
+
+    db.set_blob(Data::Int(1), bio_column, &[]).unwrap();
+    assert_eq!(db.blob(Data::Int(1), bio_column).unwrap(), Some(vec![]));
+
+    assert!(db.delete_value(Data::Int(0), bio_column).unwrap());
+    assert_eq!(db.blob(Data::Int(0), bio_column).unwrap(), None);
+
+    let age_column = ColumnId::new(1);
+    let err = db.set_blob(Data::Int(0), age_column, new_bio).unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::Blob
+        }
+    );
+
+    // Payloads, which do not fit in one instruction, are written in chunks
+    let buffer = db.create_blob_buffer(8).unwrap();
+    db.write_blob_chunk(&buffer, 4, b"Text").unwrap();
+    db.write_blob_chunk(&buffer, 0, b"Card").unwrap();
+    let err = db.write_blob_chunk(&buffer, 6, b"Text").unwrap_err();
+    assert_eq!(err, Error::BlobOutOfBounds);
+
+    db.finalize_blob(Data::Int(2), bio_column, buffer).unwrap();
+    assert_eq!(
+        db.blob(Data::Int(2), bio_column).unwrap(),
+        Some(b"CardText".to_vec())
+    );
+
+    let err = db.write_blob_chunk(&buffer, 0, b"Deck").unwrap_err();
+    assert_eq!(err, Error::NotABlobBuffer);
+    let err = db
+        .finalize_blob(Data::Int(3), bio_column, buffer)
+        .unwrap_err();
+    assert_eq!(err, Error::NotABlobBuffer);
+
+    // A failed finalization leaves the buffer writable
+    let buffer = db.create_blob_buffer(4).unwrap();
+    let err = db
+        .finalize_blob(Data::Int(3), age_column, buffer)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::Blob
+        }
+    );
+    db.write_blob_chunk(&buffer, 0, b"Deck").unwrap();
+    db.discard_blob_buffer(&buffer).unwrap();
+
+    drop(db);
+    drop(fs);
+
+    {
+        let fs =
+            Rc::new(FSReader::from_account_iter(&program_id, &mut account_infos.iter()).unwrap());
+        let reader = DBReader::from_segment(fs, DB_SEGMENT).unwrap();
+
+        assert_eq!(
+            reader.blob(Data::Int(2), bio_column).unwrap(),
+            Some(b"CardText".to_vec())
+        );
+        assert_eq!(
+            reader.blob_chunk(Data::Int(2), bio_column, 2, 4).unwrap(),
+            Some(b"rdTe".to_vec())
+        );
+        assert_eq!(
+            reader.blob_chunk(Data::Int(2), bio_column, 6, 4).unwrap(),
+            Some(b"xt".to_vec())
+        );
+        assert_eq!(
+            reader.blob_chunk(Data::Int(2), bio_column, 10, 4).unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(reader.blob(Data::Int(0), bio_column).unwrap(), None);
+    }
+
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+    let mut db = DB::from_segment(fs, DB_SEGMENT).unwrap();
+
+    db.remove_column(bio_column).unwrap();
+}
+
+#[test]
+fn aggregate() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+    let age_column = ColumnId::new(1);
+
+    let all = KeyRange::all();
+    let aggregate = |column, agg, range: &KeyRange| db.aggregate(column, agg, range).unwrap();
+
+    assert_eq!(
+        aggregate(age_column, Agg::Count, &all),
+        Some(Data::Int64(5))
+    );
+    assert_eq!(
+        aggregate(age_column, Agg::Sum, &all),
+        Some(Data::Int64(117))
+    );
+    assert_eq!(aggregate(age_column, Agg::Avg, &all), Some(Data::Int64(23)));
+    assert_eq!(aggregate(age_column, Agg::Min, &all), Some(Data::Int(20)));
+    assert_eq!(aggregate(age_column, Agg::Max, &all), Some(Data::Int(30)));
+
+    // Secondary key column is ordered by values
+    let alice = Data::ShortString(ShortString::try_from("Alice").unwrap());
+    let eve = Data::ShortString(ShortString::try_from("Eve").unwrap());
+    assert_eq!(aggregate(name_column, Agg::Min, &all), Some(alice));
+    assert_eq!(aggregate(name_column, Agg::Max, &all), Some(eve));
+
+    let range = KeyRange {
+        start: Some(Data::Int(1)),
+        end: Some(Data::Int(3)),
+    };
+    assert_eq!(
+        aggregate(age_column, Agg::Count, &range),
+        Some(Data::Int64(2))
+    );
+    assert_eq!(
+        aggregate(age_column, Agg::Sum, &range),
+        Some(Data::Int64(45))
+    );
+    assert_eq!(
+        aggregate(age_column, Agg::Avg, &range),
+        Some(Data::Int64(22))
+    );
+    assert_eq!(aggregate(age_column, Agg::Min, &range), Some(Data::Int(22)));
+    assert_eq!(aggregate(age_column, Agg::Max, &range), Some(Data::Int(23)));
+
+    let empty = KeyRange {
+        start: Some(Data::Int(10)),
+        end: None,
+    };
+    assert_eq!(
+        aggregate(age_column, Agg::Count, &empty),
+        Some(Data::Int64(0))
+    );
+    assert_eq!(
+        aggregate(age_column, Agg::Sum, &empty),
+        Some(Data::Int64(0))
+    );
+    assert_eq!(aggregate(age_column, Agg::Avg, &empty), None);
+    assert_eq!(aggregate(age_column, Agg::Max, &empty), None);
+
+    let reversed = KeyRange {
+        start: Some(Data::Int(3)),
+        end: Some(Data::Int(1)),
+    };
+    assert_eq!(
+        aggregate(age_column, Agg::Count, &reversed),
+        Some(Data::Int64(0))
+    );
+    assert_eq!(aggregate(age_column, Agg::Min, &reversed), None);
+
+    let err = db.aggregate(name_column, Agg::Sum, &all).unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int64,
+            got: DataType::ShortString,
+        }
+    );
+
+    // Extremes of the values follow the changes of the column
+    db.delete_value(Data::Int(3), age_column).unwrap();
+    db.set_value(Data::Int(4), age_column, Data::Int(21))
+        .unwrap();
+    assert_eq!(
+        db.aggregate(age_column, Agg::Min, &all).unwrap(),
+        Some(Data::Int(21))
+    );
+    assert_eq!(
+        db.aggregate(age_column, Agg::Max, &all).unwrap(),
+        Some(Data::Int(23))
+    );
+}
+
+#[test]
+fn change_log() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let options = TableOptions {
+        change_log_capacity: Some(128),
+        ..Default::default()
+    };
+
+    let (mut db, _) =
+        DB::init_in_segment_with_options(fs.clone(), "Scores", 2, 4, DataType::Int, options)
+            .unwrap();
+
+    let score_column = db.add_column("Score", DataType::Int, false).unwrap();
+
+    db.set_value(Data::Int(1), score_column, Data::Int(10))
+        .unwrap();
+    db.set_value(Data::Int(1), score_column, Data::Int(15))
+        .unwrap();
+    db.set_row(Data::Int(2), vec![(score_column, Data::Int(7))])
+        .unwrap();
+    db.delete_row(Data::Int(1)).unwrap();
+
+    let changes = db.changes_since(0).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change {
+                sequence: 0,
+                operation: Operation::Set,
+                column: score_column,
+                key: Data::Int(1),
+                old_value: None,
+                new_value: Some(Data::Int(10)),
+            },
+            Change {
+                sequence: 1,
+                operation: Operation::Set,
+                column: score_column,
+                key: Data::Int(1),
+                old_value: Some(Data::Int(10)),
+                new_value: Some(Data::Int(15)),
+            },
+            Change {
+                sequence: 2,
+                operation: Operation::Set,
+                column: score_column,
+                key: Data::Int(2),
+                old_value: None,
+                new_value: Some(Data::Int(7)),
+            },
+            Change {
+                sequence: 3,
+                operation: Operation::Delete,
+                column: score_column,
+                key: Data::Int(1),
+                old_value: Some(Data::Int(15)),
+                new_value: None,
+            },
+        ]
+    );
+
+    assert_eq!(db.changes_since(3).unwrap(), changes[3..].to_vec());
+    assert_eq!(db.changes_since(4).unwrap(), vec![]);
+
+    // Changes of this column may not fit in the change log
+    let bio_column = db.add_column("Bio", DataType::MediumString, false).unwrap();
+    let bio = Data::MediumString(MediumString::try_from("Likes long walks").unwrap());
+    let err = db.set_value(Data::Int(2), bio_column, bio).unwrap_err();
+    assert_eq!(err, Error::ChangeLogOverflow);
+    assert_eq!(db.value(Data::Int(2), bio_column).unwrap(), None);
+
+    let (db_without_log, _) =
+        DB::init_in_segment(fs.clone(), "Plain", 2, 4, DataType::Int).unwrap();
+    let err = db_without_log.changes_since(0).unwrap_err();
+    assert_eq!(err, Error::NoChangeLog);
+}
+
+#[test]
+fn read_only() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(FSReader::from_account_iter(&program_id, &mut account_infos.iter()).unwrap());
+
+    let db = DBReader::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+    // Segments are borrowed immutably, so the same table can be opened twice
+    let other_db = DBReader::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+    let age_column = ColumnId::new(1);
+
+    assert_eq!(db.table_name(), other_db.table_name());
+    assert_eq!(db.primary_key_type(), DataType::Int);
+    assert_eq!(db.columns().len(), 2);
+
+    assert_eq!(
+        db.value(Data::Int(0), age_column).unwrap(),
+        Some(Data::Int(22))
+    );
+    assert_eq!(db.value(Data::Int(6), age_column).unwrap(), None);
+
+    let bob = Data::ShortString(ShortString::try_from("Bob").unwrap());
+    assert_eq!(
+        other_db
+            .value_secondary(name_column, bob.clone(), age_column)
+            .unwrap(),
+        Some(Data::Int(23))
+    );
+    assert_eq!(
+        db.row(Data::Int(1)).unwrap(),
+        BTreeMap::from([(name_column, Some(bob)), (age_column, Some(Data::Int(23)))])
+    );
+
+    let range = KeyRange {
+        start: Some(Data::Int(1)),
+        end: Some(Data::Int(3)),
+    };
+    assert_eq!(
+        db.scan(age_column, &range).unwrap(),
+        vec![(Data::Int(1), Data::Int(23)), (Data::Int(2), Data::Int(22))]
+    );
+
+    assert_eq!(
+        db.aggregate(age_column, Agg::Sum, &KeyRange::all())
+            .unwrap(),
+        Some(Data::Int64(117))
+    );
+    assert_eq!(
+        db.aggregate(age_column, Agg::Count, &range).unwrap(),
+        Some(Data::Int64(2))
+    );
+
+    let page = db.scan_from(&Cursor::new(age_column), 2).unwrap();
+    assert_eq!(
+        page.pairs,
+        vec![(Data::Int(0), Data::Int(22)), (Data::Int(1), Data::Int(23))]
+    );
+    let rest = other_db.scan_from(page.next.as_ref().unwrap(), 10).unwrap();
+    assert_eq!(rest.pairs.len(), 3);
+    assert_eq!(rest.next, None);
+
+    assert!(account_infos[0].data.try_borrow_mut().is_err());
+}
+
+#[test]
+fn expiring_rows() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let (mut db, lobbies_segment) = DB::init_in_segment_with_options(
+        fs.clone(),
+        "Lobbies",
+        3,
+        4,
+        DataType::Int,
+        TableOptions::default(),
+    )
+    .unwrap();
+
+    let players_column = db.add_column("Players", DataType::Int, false).unwrap();
+    let expires_column = db.add_column("ExpiresAt", DataType::Int64, false).unwrap();
+
+    let err = db.purge_expired(100, 10).unwrap_err();
+    assert_eq!(err, Error::NoExpiryColumn);
+
+    let err = db.set_expiry_column(Some(ColumnId::new(42))).unwrap_err();
+    assert_eq!(err, Error::NoSuchColumn);
+
+    db.set_expiry_column(Some(expires_column)).unwrap();
+    assert_eq!(db.expiry_column(), Some(expires_column));
+
+    for (lobby, expires_at) in [(1, 100), (2, 200), (3, 300), (4, 50)] {
+        db.set_row(
+            Data::Int(lobby),
+            vec![
+                (players_column, Data::Int(2)),
+                (expires_column, Data::Int64(expires_at)),
+            ],
+        )
+        .unwrap();
+    }
+
+    let purged = |deleted, skipped| PurgeReport { deleted, skipped };
+
+    assert_eq!(db.purge_expired(150, 1).unwrap(), purged(1, 0));
+    assert_eq!(db.purge_expired(150, 10).unwrap(), purged(1, 0));
+    assert_eq!(db.purge_expired(150, 10).unwrap(), purged(0, 0));
+
+    assert!(!db.contains_row(Data::Int(1)).unwrap());
+    assert!(!db.contains_row(Data::Int(4)).unwrap());
+    assert!(db.contains_row(Data::Int(2)).unwrap());
+    assert!(db.contains_row(Data::Int(3)).unwrap());
+
+    // A game of the lobby 2 keeps it from being deleted
+    drop(db);
+    {
+        let (mut games, _) = DB::init_in_segment_with_options(
+            fs.clone(),
+            "Games",
+            1,
+            4,
+            DataType::Int,
+            TableOptions::default(),
+        )
+        .unwrap();
+        let lobby_column = games.add_column("Lobby", DataType::Int, false).unwrap();
+        games
+            .add_foreign_key(lobby_column, lobbies_segment, OnDelete::Restrict)
+            .unwrap();
+        games
+            .set_value(Data::Int(1), lobby_column, Data::Int(2))
+            .unwrap();
+    }
+    let mut db = DB::from_segment(fs.clone(), lobbies_segment).unwrap();
+
+    // The skipped row does not count towards the limit
+    assert_eq!(db.purge_expired(300, 1).unwrap(), purged(1, 1));
+    assert!(db.contains_row(Data::Int(2)).unwrap());
+    assert!(!db.contains_row(Data::Int(3)).unwrap());
+
+    db.remove_column(expires_column).unwrap();
+    assert_eq!(db.expiry_column(), None);
+
+    let host_column = db.add_column("Host", DataType::ShortString, false).unwrap();
+    let err = db.set_expiry_column(Some(host_column)).unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int64,
+            got: DataType::ShortString
+        }
+    );
+
+    let (mut plain_db, _) = DB::init_in_segment(fs.clone(), "Plain", 2, 4, DataType::Int).unwrap();
+    let column = plain_db
+        .add_column("ExpiresAt", DataType::Int64, false)
+        .unwrap();
+    let err = plain_db.set_expiry_column(Some(column)).unwrap_err();
+    assert_eq!(err, Error::NoMetadata);
+}
+
+#[test]
+fn catalog() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let (mut catalog, catalog_segment) = Catalog::init_in_segment(fs.clone(), 2).unwrap();
+
+    let mut players = catalog
+        .create_table("Players", 1, 4, DataType::Int, TableOptions::default())
+        .unwrap();
+    let score_column = players.add_column("Score", DataType::Int, false).unwrap();
+    players
+        .set_value(Data::Int(1), score_column, Data::Int(100))
+        .unwrap();
+    drop(players);
+
+    let mut items = catalog
+        .create_table("Items", 1, 4, DataType::Int64, TableOptions::default())
+        .unwrap();
+    items.add_column("Price", DataType::Int, false).unwrap();
+    drop(items);
+
+    let err = catalog
+        .create_table("Players", 1, 4, DataType::Int, TableOptions::default())
+        .unwrap_err();
+    assert_eq!(err, Error::TableAlreadyExists);
+
+    let err = catalog
+        .create_table("Buffs", 1, 4, DataType::Int, TableOptions::default())
+        .unwrap_err();
+    assert_eq!(err, Error::NoTablesLeft);
+
+    drop(catalog);
+
+    let mut catalog = Catalog::from_segment(fs.clone(), catalog_segment).unwrap();
+
+    let tables = catalog.tables();
+    assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["Items", "Players"]);
+
+    let players = catalog.open_table("Players").unwrap();
+    assert_eq!(players.table_name(), "Players".to_string());
+    assert_eq!(
+        players.value(Data::Int(1), score_column).unwrap(),
+        Some(Data::Int(100))
+    );
+    drop(players);
+
+    let items = catalog.open_table("Items").unwrap();
+    assert_eq!(items.primary_key_type(), DataType::Int64);
+    drop(items);
+
+    catalog.drop_table("Players").unwrap();
+
+    assert_eq!(catalog.tables().len(), 1);
+    let err = catalog.open_table("Players").unwrap_err();
+    assert_eq!(err, Error::NoSuchTable);
+    let err = catalog.drop_table("Players").unwrap_err();
+    assert_eq!(err, Error::NoSuchTable);
+
+    drop(catalog);
+    drop(fs);
+
+    let fs = Rc::new(FSReader::from_account_iter(&program_id, &mut account_infos.iter()).unwrap());
+    let catalog = CatalogReader::from_segment(fs, catalog_segment).unwrap();
+
+    assert_eq!(catalog.tables().keys().collect::<Vec<_>>(), vec!["Items"]);
+    let items = catalog.open_table("Items").unwrap();
+    assert_eq!(items.primary_key_type(), DataType::Int64);
+    let err = catalog.open_table("Players").unwrap_err();
+    assert_eq!(err, Error::NoSuchTable);
+}
+
+#[test]
+fn paginated_scan() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let age_column = ColumnId::new(1);
+
+    let mut pages = Vec::new();
+    let mut cursor = Some(Cursor::new(age_column));
+    while let Some(current) = cursor {
+        // Cursors are passed between transactions in the serialized form
+        let bytes = current.try_to_vec().unwrap();
+        let current = Cursor::deserialize(&mut bytes.as_slice()).unwrap();
+
+        let page = db.scan_from(&current, 2).unwrap();
+        cursor = page.next.clone();
+        pages.push(page.pairs);
+    }
+
+    assert_eq!(
+        pages,
+        vec![
+            vec![(Data::Int(0), Data::Int(22)), (Data::Int(1), Data::Int(23))],
+            vec![(Data::Int(2), Data::Int(22)), (Data::Int(3), Data::Int(20))],
+            vec![(Data::Int(4), Data::Int(30))],
+        ]
+    );
+
+    let full_page = db.scan_from(&Cursor::new(age_column), 10).unwrap();
+    assert_eq!(full_page.pairs.len(), 5);
+    assert_eq!(full_page.next, None);
+
+    let mut page = full_page.clone();
+    let size = page.try_to_vec().unwrap().len();
+    page.truncate_to_size(size - 1).unwrap();
+    assert!(page.pairs.len() < 5);
+
+    let rest = db.scan_from(page.next.as_ref().unwrap(), 10).unwrap();
+    page.pairs.extend(rest.pairs);
+    assert_eq!(page.pairs, full_page.pairs);
+
+    let mut page = full_page;
+    let err = page.truncate_to_size(1).unwrap_err();
+    assert_eq!(err, Error::PageOverflow);
+}
+
+#[test]
+fn unique_columns() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let (mut db, _) = DB::init_in_segment_with_options(
+        fs.clone(),
+        "Players",
+        2,
+        4,
+        DataType::Int,
+        TableOptions::default(),
+    )
+    .unwrap();
+
+    let nickname_column = db
+        .add_column("Nickname", DataType::ShortString, false)
+        .unwrap();
+    let score_column = db.add_column("Score", DataType::Int, false).unwrap();
+
+    db.set_value(Data::Int(1), score_column, Data::Int(10))
+        .unwrap();
+    db.set_value(Data::Int(2), score_column, Data::Int(10))
+        .unwrap();
+
+    let err = db.make_unique(score_column).unwrap_err();
+    assert_eq!(err, Error::UniqueViolation);
+    assert!(!db.is_unique(score_column).unwrap());
+
+    db.set_value(
+        Data::Int(1),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("neo").unwrap()),
+    )
+    .unwrap();
+    db.make_unique(nickname_column).unwrap();
+    assert!(db.is_unique(nickname_column).unwrap());
+
+    let err = db
+        .set_value(
+            Data::Int(2),
+            nickname_column,
+            Data::ShortString(ShortString::try_from("neo").unwrap()),
+        )
+        .unwrap_err();
+    assert_eq!(err, Error::UniqueViolation);
+    assert_eq!(db.value(Data::Int(2), nickname_column).unwrap(), None);
+
+    // Setting the same value again is not a violation
+    db.set_value(
+        Data::Int(1),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("neo").unwrap()),
+    )
+    .unwrap();
+
+    db.set_value(
+        Data::Int(1),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("morpheus").unwrap()),
+    )
+    .unwrap();
+    db.set_value(
+        Data::Int(2),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("neo").unwrap()),
+    )
+    .unwrap();
+
+    db.delete_row(Data::Int(2)).unwrap();
+    db.set_value(
+        Data::Int(3),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("neo").unwrap()),
+    )
+    .unwrap();
+
+    // Tombstones of the replaced values are compacted, so the index never fills up
+    let nickname = |round: usize| {
+        Data::ShortString(ShortString::try_from(format!("agent {}", round).as_str()).unwrap())
+    };
+    for round in 0..20 {
+        db.set_value(Data::Int(3), nickname_column, nickname(round))
+            .unwrap();
+    }
+    let err = db
+        .set_value(Data::Int(1), nickname_column, nickname(19))
+        .unwrap_err();
+    assert_eq!(err, Error::UniqueViolation);
+    db.set_value(Data::Int(1), nickname_column, nickname(7))
+        .unwrap();
+
+    db.drop_unique(nickname_column).unwrap();
+    assert!(!db.is_unique(nickname_column).unwrap());
+    db.set_value(
+        Data::Int(4),
+        nickname_column,
+        Data::ShortString(ShortString::try_from("neo").unwrap()),
+    )
+    .unwrap();
+
+    // The duplicate score was deleted with the row 2
+    db.make_unique(score_column).unwrap();
+    db.remove_column(score_column).unwrap();
+
+    let mut plain_db = DB::init_in_segment(fs, "Plain", 1, 4, DataType::Int)
+        .unwrap()
+        .0;
+    let column = plain_db.add_column("Value", DataType::Int, false).unwrap();
+    let err = plain_db.make_unique(column).unwrap_err();
+    assert_eq!(err, Error::NoMetadata);
+}
+
+#[test]
+fn type_mismatch() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+    let age_column = ColumnId::new(1);
+
+    let err = db
+        .set_value(
+            Data::Int(0),
+            age_column,
+            Data::ShortString(ShortString::try_from("old").unwrap()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::ShortString,
+        }
+    );
+
+    let err = db
+        .value(
+            Data::ShortString(ShortString::try_from("Alice").unwrap()),
+            age_column,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::ShortString,
+        }
+    );
+
+    let err = db
+        .value_secondary(name_column, Data::Int(22), age_column)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::ShortString,
+            got: DataType::Int,
+        }
+    );
+
+    let err = db.delete_value(Data::Int64(0), name_column).unwrap_err();
+    assert_eq!(
+        err,
+        Error::TypeMismatch {
+            expected: DataType::Int,
+            got: DataType::Int64,
+        }
+    );
+
+    // The DB is left untouched
+    assert_eq!(
+        db.value(Data::Int(0), age_column).unwrap(),
+        Some(Data::Int(22))
+    );
+    assert_eq!(
+        db.value(Data::Int(0), name_column).unwrap(),
+        Some(Data::ShortString(ShortString::try_from("Alice").unwrap()))
+    );
+}
+
+// This function is used to recreate the image of the test DB, after the storage format of the
+// columns is changed
+#[cfg_attr(tarpaulin, ignore)]
+fn _prepared_db_generation() {
+    let fs_data = prepare_test_db();
+
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::create(filename).unwrap();
+
+    fs_data.serialize(&mut file).unwrap();
+}
+
+#[test]
+fn check_integrity() {
+    let filename = format!("{}/tests/fs_images/prepared_db", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut db_fs_bytes = Vec::new();
+    file.read_to_end(&mut db_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut db_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let mut db = DB::from_segment(fs.clone(), DB_SEGMENT).unwrap();
+
+    let name_column = ColumnId::new(0);
+
+    let report = db.check_integrity(false).unwrap();
+    assert!(report.is_consistent());
+    assert_eq!(report, IntegrityReport::default());
+
+    // Name column is OneToOne, so its values are moved between the trees
+    db.set_value(
+        Data::Int(0),
+        name_column,
+        Data::ShortString(ShortString::try_from("Dave").unwrap()),
+    )
+    .unwrap();
+    db.delete_value(Data::Int(1), name_column).unwrap();
+    db.delete_row(Data::Int(2)).unwrap();
+
+    let report = db.check_integrity(true).unwrap();
+    assert_eq!(report, IntegrityReport::default());
+}
+
+#[test]
+fn repair_with_change_log() {
+    let filename = format!("{}/tests/fs_images/clean_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let fs = Rc::new(RefCell::new(
+        FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap(),
+    ));
+
+    let options = TableOptions {
+        change_log_capacity: Some(128),
+        ..Default::default()
+    };
+
+    let (mut db, _) =
+        DB::init_in_segment_with_options(fs.clone(), "Players", 2, 4, DataType::Int, options)
+            .unwrap();
+
+    let name_column = db.add_column("Name", DataType::ShortString, true).unwrap();
+    db.set_value(
+        Data::Int(1),
+        name_column,
+        Data::ShortString(ShortString::try_from("Alice").unwrap()),
+    )
+    .unwrap();
+
+    // The check alone does not change the table, so it is allowed
+    let report = db.check_integrity(false).unwrap();
+    assert!(report.is_consistent());
+
+    // Removals of the repair would not be logged
+    let err = db.check_integrity(true).unwrap_err();
+    assert_eq!(err, Error::RepairNotSupported);
+}
+
 // This function was used to create an image of empty FS, which is now used as a basis for DB
 // creation
 #[cfg_attr(tarpaulin, ignore)]
@@ -597,7 +2346,7 @@ fn _fs_initialization() {
     let account_params = AccountParams {
         address: None,
         owner: program_id.to_bytes(),
-        data: AccountData::Empty(1_000),
+        data: AccountData::Empty(1_500),
     };
     let mut fs_data = FSAccounts::replicate_params(account_params, 3);
 
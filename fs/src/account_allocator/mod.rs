@@ -21,10 +21,13 @@ use tinyvec::SliceVec;
 
 mod allocation_table;
 mod inode;
+mod reader;
 
 use allocation_table::AllocationTable;
 use inode::Inode;
 
+pub use reader::AccountReader;
+
 /// Slice allocator for a single [`Account`](super::AccountInfo)
 ///
 /// See [module](self) level documentation for more info.
@@ -367,35 +370,11 @@ impl<'long: 'short, 'short> AccountAllocator<'long> {
     }
 
     fn is_consistent(&self) -> bool {
-        // Firstly, we check, that the inodes table is consistent.
-        if self.inode_data[0].start_idx() != 0 {
-            return false;
-        }
-
-        for arr in self.inode_data.windows(2) {
-            let first = arr[0];
-            let second = arr[1];
-            if first.end_idx() != second.start_idx() {
-                return false;
-            }
-        }
-
-        if self.inode_data[self.inode_data.len() - 1].end_idx() != self.len {
-            return false;
-        }
-
-        // this is the guarantee, that there will be no index collisions
-        // NOTE: existing index collisions are memory-safe
-        let max_index = self
-            .inode_data
-            .iter()
-            .filter_map(|inode| inode.id())
-            .reduce(std::cmp::max);
-
-        match max_index {
-            Some(idx) => self.allocation_table.id_autoincrement() > idx,
-            None => true,
-        }
+        is_consistent(
+            &self.inode_data,
+            self.len,
+            self.allocation_table.id_autoincrement(),
+        )
     }
 
     fn collect_slices(&self) -> BTreeMap<u32, &[u8]> {
@@ -496,6 +475,38 @@ impl<'a> PartialEq for AccountAllocator<'a> {
     }
 }
 
+/// Checks the invariants of the inode table, which describes `len` bytes of data
+fn is_consistent(inode_data: &[Inode], len: usize, id_autoincrement: u32) -> bool {
+    // Firstly, we check, that the inodes table is consistent.
+    if inode_data.is_empty() || inode_data[0].start_idx() != 0 {
+        return false;
+    }
+
+    for arr in inode_data.windows(2) {
+        let first = arr[0];
+        let second = arr[1];
+        if first.end_idx() != second.start_idx() {
+            return false;
+        }
+    }
+
+    if inode_data[inode_data.len() - 1].end_idx() != len {
+        return false;
+    }
+
+    // this is the guarantee, that there will be no index collisions
+    // NOTE: existing index collisions are memory-safe
+    let max_index = inode_data
+        .iter()
+        .filter_map(|inode| inode.id())
+        .reduce(std::cmp::max);
+
+    match max_index {
+        Some(idx) => id_autoincrement > idx,
+        None => true,
+    }
+}
+
 /// The reasons, why FS operations may fail.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Error {
@@ -0,0 +1,75 @@
+use bytemuck::{cast_ref, cast_slice};
+use std::fmt;
+use std::mem;
+
+use super::{is_consistent, AllocationTable, Error, Inode};
+
+/// Read-only view of the segments inside a single [`Account`](crate::AccountInfo)
+///
+/// Unlike [`AccountAllocator`](super::AccountAllocator), it needs only a shared reference to the
+/// account data, so the same segment can be borrowed any number of times.
+pub struct AccountReader<'long> {
+    allocation_table: &'long AllocationTable,
+    inode_data: &'long [Inode],
+    data: &'long [u8],
+}
+
+impl<'long> AccountReader<'long> {
+    /// Get [`AccountReader`] from data slice, the account must be initialized by
+    /// [`AccountAllocator`](super::AccountAllocator)
+    pub fn from_account(account_data: &'long [u8]) -> Result<Self, Error> {
+        if account_data.len() < mem::size_of::<AllocationTable>() {
+            return Err(Error::TooSmall);
+        }
+        let (allocation_table, tail) = account_data.split_at(mem::size_of::<AllocationTable>());
+
+        let allocation_table: &[[u8; mem::size_of::<AllocationTable>()]] =
+            cast_slice(allocation_table);
+        let allocation_table: &AllocationTable = cast_ref(&allocation_table[0]);
+
+        if !allocation_table.check_magic() {
+            return Err(Error::WrongMagic);
+        }
+
+        if tail.len() < allocation_table.inodes_max() * mem::size_of::<Inode>()
+            || allocation_table.inodes_count() > allocation_table.inodes_max()
+        {
+            return Err(Error::WrongSize);
+        }
+        let (inodes_slice, data) =
+            tail.split_at(allocation_table.inodes_max() * mem::size_of::<Inode>());
+
+        let inodes: &[Inode] = cast_slice(inodes_slice);
+        let inode_data = &inodes[..allocation_table.inodes_count()];
+
+        if !is_consistent(inode_data, data.len(), allocation_table.id_autoincrement()) {
+            return Err(Error::BrokenFSAccount);
+        }
+
+        Ok(Self {
+            allocation_table,
+            inode_data,
+            data,
+        })
+    }
+
+    /// Returns the content of the segment with the given `id`
+    pub fn segment(&self, id: u32) -> Result<&'long [u8], Error> {
+        let data = self.data;
+        self.inode_data
+            .iter()
+            .find(|inode| inode.id() == Some(id) && inode.is_occupied())
+            .map(|inode| &data[inode.start_idx()..inode.end_idx()])
+            .ok_or(Error::NoSuchSegment)
+    }
+}
+
+impl<'a> fmt::Debug for AccountReader<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountReader")
+            .field("allocation_table", &self.allocation_table)
+            .field("data_size", &self.data.len())
+            .field("inodes", &self.inode_data)
+            .finish()
+    }
+}
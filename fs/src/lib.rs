@@ -64,6 +64,10 @@
 //! assert_eq!(segment[0], 10);
 //! assert_eq!(segment[15], 118);
 //!```
+//! # Read-only access
+//! [`FSReader`] borrows account data immutably, so it can be used with non-writable accounts.
+//! It can only read segments, that were allocated by [`FS`].
+//!
 //! # Internal structure
 //! Internally [`FS`] is set of [`AccountAllocators`](AccountAllocator). Build docs with
 //! `--document-private-items` to see its documentation
@@ -83,11 +87,13 @@ use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
 mod account_allocator;
+mod reader;
 mod segment_id;
 
 use account_allocator::AccountAllocator;
 
 pub use account_allocator::Error as FSError;
+pub use reader::FSReader;
 pub use segment_id::SegmentId;
 
 /// A struct which allocates and deallocates bytes
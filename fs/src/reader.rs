@@ -0,0 +1,106 @@
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+use std::cell::Ref;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::account_allocator::AccountReader;
+use crate::{FSError, SegmentId};
+
+/// Read-only counterpart of [`FS`](crate::FS)
+///
+/// Account data is borrowed immutably, so the accounts don't have to be writable. Segments can be
+/// borrowed any number of times, but can not be allocated or deallocated.
+pub struct FSReader<'long: 'short, 'short> {
+    readers: BTreeMap<Pubkey, (AccountReader<'short>, &'short AccountInfo<'long>)>,
+}
+
+impl<'long: 'short, 'short> FSReader<'long, 'short> {
+    /// Constructs [`FSReader`], all accounts must be initialized as filesystem accounts
+    pub fn from_account_iter<AccountIter>(
+        program_id: &Pubkey,
+        accounts_iter: &mut AccountIter,
+    ) -> Result<Self, FSError>
+    where
+        AccountIter: Iterator<Item = &'short AccountInfo<'long>>,
+    {
+        let mut readers = BTreeMap::new();
+        for account in accounts_iter {
+            let result = if account.owner != program_id {
+                Err(FSError::WrongOwner)
+            } else {
+                let data = Ref::<'_, &'long mut [u8]>::leak(account.data.borrow());
+                AccountReader::from_account(data).map_err(|err| {
+                    undo_leak(account);
+                    err
+                })
+            };
+
+            match result {
+                Ok(reader) => {
+                    readers.insert(*account.key, (reader, account));
+                }
+                Err(err) => {
+                    for (_, account) in readers.values() {
+                        undo_leak(account);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(Self { readers })
+    }
+
+    /// Borrows a segment with given [`SegmentId`]
+    pub fn segment(&self, id: &SegmentId) -> Result<&'short [u8], FSError> {
+        match self.readers.get(&id.pubkey) {
+            Some((reader, _)) => reader.segment(id.id),
+            None => Err(FSError::NoSuchPubkey),
+        }
+    }
+
+    /// Checks if a segment with given [`SegmentId`] can be accessed in the [`FSReader`]
+    pub fn is_accessible(&self, id: &SegmentId) -> bool {
+        self.readers.contains_key(&id.pubkey)
+    }
+}
+
+/// Releases the leaked shared borrow of the account data
+fn undo_leak(account_info: &AccountInfo<'_>) {
+    let cell = account_info.data.clone();
+    // SAFETY: the only other `Rc` to the data is owned by the `account_info`, so the temporary
+    // decrement of the strong count makes `Rc::get_mut` succeed without freeing the data
+    unsafe {
+        let ptr = Rc::into_raw(cell);
+        Rc::decrement_strong_count(ptr);
+
+        let mut ref_counter = Rc::from_raw(ptr);
+        Rc::get_mut(&mut ref_counter)
+            .expect("Already borrowed")
+            .undo_leak();
+
+        Rc::increment_strong_count(ptr);
+    }
+}
+
+impl<'long: 'short, 'short> Drop for FSReader<'long, 'short> {
+    fn drop(&mut self) {
+        for (_, account_info) in self.readers.values() {
+            undo_leak(account_info);
+        }
+    }
+}
+
+impl<'long: 'short, 'short> Debug for FSReader<'long, 'short> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        fmt.debug_map()
+            .entries(
+                self.readers
+                    .iter()
+                    .map(|(pubkey, (reader, _))| (pubkey, reader)),
+            )
+            .finish()
+    }
+}
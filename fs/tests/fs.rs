@@ -4,7 +4,7 @@ use solana_program::pubkey::Pubkey;
 use std::fs::File;
 use std::io::Read;
 
-use account_fs::FS;
+use account_fs::{FSError, FSReader, FS};
 use fs_test::*;
 
 #[test]
@@ -93,3 +93,46 @@ fn usage() {
 
     assert_eq!(fs_data, expected_fs_data);
 }
+
+#[test]
+fn read_only() {
+    let filename = format!("{}/tests/fs_images/new_fs", env!("CARGO_MANIFEST_DIR"));
+
+    let mut file = File::open(filename).unwrap();
+
+    let mut clean_fs_bytes = Vec::new();
+    file.read_to_end(&mut clean_fs_bytes).unwrap();
+
+    let mut fs_data = FSAccounts::deserialize(&mut clean_fs_bytes.as_slice()).unwrap();
+
+    let program_id = fs_data.owner_pubkey().unwrap();
+
+    let account_infos = fs_data.account_info_iter();
+    let mut fs = FS::from_account_iter(&program_id, &mut account_infos.iter()).unwrap();
+
+    let segment_id = fs.allocate_segment(15).unwrap();
+    let segment = fs.segment(&segment_id).unwrap();
+    segment[0] = 42;
+    segment[14] = 7;
+
+    drop(fs);
+
+    let reader = FSReader::from_account_iter(&program_id, &mut account_infos.iter()).unwrap();
+
+    let first = reader.segment(&segment_id).unwrap();
+    let second = reader.segment(&segment_id).unwrap();
+    assert_eq!(first[0], 42);
+    assert_eq!(second[14], 7);
+
+    // The data is borrowed immutably, so other readers are allowed, but writers are not
+    assert!(account_infos[0].data.try_borrow().is_ok());
+    assert!(account_infos[0].data.try_borrow_mut().is_err());
+
+    drop(reader);
+
+    assert!(account_infos[0].data.try_borrow_mut().is_ok());
+
+    let other_program = Pubkey::new_unique();
+    let err = FSReader::from_account_iter(&other_program, &mut account_infos.iter()).unwrap_err();
+    assert_eq!(err, FSError::WrongOwner);
+}
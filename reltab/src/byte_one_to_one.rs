@@ -0,0 +1,563 @@
+//! One to one relation over byte strings with runtime key and value sizes
+//!
+//! It is the [`ByteTree`] counterpart of [`OneToOne`](crate::one_to_one::OneToOne).
+//!
+//! # Internal structure
+//! [`ByteOneToOne`] container consists of three parts:
+//! - 8 bytes magic string, must be equal to [`MAGIC`]
+//! - `K -> V` [`ByteTree`]
+//! - `V -> K` [`ByteTree`]
+//!
+//! Both trees have the same size
+use std::fmt;
+use std::ops::Bound;
+
+use crate::byte_tree::{tree_size, ByteTree, Comparator, Error, Iter, TreeParams};
+use crate::one_to_one::Inconsistency;
+
+pub const MAGIC: &[u8; 8] = b"Byte1to1";
+
+/// Returns the required size of the slice
+#[must_use]
+#[inline]
+pub fn byte_one_to_one_size(k_size: usize, v_size: usize, max_nodes: usize) -> usize {
+    2 * tree_size(direct_params(k_size, v_size), max_nodes) + MAGIC.len()
+}
+
+fn direct_params(k_size: usize, v_size: usize) -> TreeParams {
+    TreeParams {
+        k_size,
+        v_size,
+        value_extremes: false,
+    }
+}
+
+fn converse_params(k_size: usize, v_size: usize) -> TreeParams {
+    direct_params(v_size, k_size)
+}
+
+/// Returns the size of each tree in the slice after the magic
+fn split_trees(tail: &[u8]) -> Result<usize, Error> {
+    if tail.len() % 2 != 0 {
+        return Err(Error::WrongNodePoolSize);
+    }
+    Ok(tail.len() / 2)
+}
+
+pub struct ByteOneToOne<S> {
+    direct_relation: ByteTree<S>,
+    converse_relation: ByteTree<S>,
+}
+
+impl<'a> ByteOneToOne<&'a mut [u8]> {
+    /// Initializes an empty container in the slice, both keys and values are ordered by
+    /// `compare`
+    pub fn init_slice(
+        slice: &'a mut [u8],
+        k_size: usize,
+        v_size: usize,
+        compare: Comparator,
+    ) -> Result<Self, Error> {
+        if slice.len() < MAGIC.len() {
+            return Err(Error::TooSmall);
+        }
+
+        let (magic, tail) = slice.split_at_mut(MAGIC.len());
+        let half = split_trees(tail)?;
+        let (direct, converse) = tail.split_at_mut(half);
+
+        let direct_relation = ByteTree::init_slice(direct, direct_params(k_size, v_size), compare)?;
+        let converse_relation =
+            ByteTree::init_slice(converse, converse_params(k_size, v_size), compare)?;
+
+        magic.copy_from_slice(MAGIC);
+
+        Ok(Self {
+            direct_relation,
+            converse_relation,
+        })
+    }
+
+    /// Opens the container, initialized by [`ByteOneToOne::init_slice`] with the same sizes
+    ///
+    /// The trees are validated as in [`ByteTree::from_slice`], use [`ByteOneToOne::verify`] to
+    /// check that they mirror each other.
+    pub fn from_slice(
+        slice: &'a mut [u8],
+        k_size: usize,
+        v_size: usize,
+        compare: Comparator,
+    ) -> Result<Self, Error> {
+        if slice.len() < MAGIC.len() {
+            return Err(Error::TooSmall);
+        }
+
+        let (magic, tail) = slice.split_at_mut(MAGIC.len());
+        if magic != MAGIC {
+            return Err(Error::WrongMagic);
+        }
+        let half = split_trees(tail)?;
+        let (direct, converse) = tail.split_at_mut(half);
+
+        Ok(Self {
+            direct_relation: ByteTree::from_slice(direct, direct_params(k_size, v_size), compare)?,
+            converse_relation: ByteTree::from_slice(
+                converse,
+                converse_params(k_size, v_size),
+                compare,
+            )?,
+        })
+    }
+}
+
+impl<'a> ByteOneToOne<&'a [u8]> {
+    /// Opens the container read-only, the same as [`ByteOneToOne::from_slice`]
+    pub fn view_slice(
+        slice: &'a [u8],
+        k_size: usize,
+        v_size: usize,
+        compare: Comparator,
+    ) -> Result<Self, Error> {
+        if slice.len() < MAGIC.len() {
+            return Err(Error::TooSmall);
+        }
+
+        let (magic, tail) = slice.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(Error::WrongMagic);
+        }
+        let half = split_trees(tail)?;
+        let (direct, converse) = tail.split_at(half);
+
+        Ok(Self {
+            direct_relation: ByteTree::from_slice(direct, direct_params(k_size, v_size), compare)?,
+            converse_relation: ByteTree::from_slice(
+                converse,
+                converse_params(k_size, v_size),
+                compare,
+            )?,
+        })
+    }
+}
+
+impl<S> ByteOneToOne<S>
+where
+    S: AsRef<[u8]>,
+{
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.direct_relation.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.direct_relation.is_empty()
+    }
+
+    #[must_use]
+    pub fn free_nodes_left(&self) -> usize {
+        self.direct_relation.free_nodes_left()
+    }
+
+    #[must_use]
+    pub fn get_value(&self, k: &[u8]) -> Option<&[u8]> {
+        self.direct_relation.get(k)
+    }
+
+    #[must_use]
+    pub fn get_key(&self, v: &[u8]) -> Option<&[u8]> {
+        self.converse_relation.get(v)
+    }
+
+    /// Returns the entry with the smallest value
+    #[must_use]
+    pub fn first_by_value(&self) -> Option<(&[u8], &[u8])> {
+        self.converse_relation
+            .first()
+            .map(|(value, key)| (key, value))
+    }
+
+    /// Returns the entry with the largest value
+    #[must_use]
+    pub fn last_by_value(&self) -> Option<(&[u8], &[u8])> {
+        self.converse_relation
+            .last()
+            .map(|(value, key)| (key, value))
+    }
+
+    /// Iterates over the pairs in the order of the keys
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, S> {
+        self.direct_relation.iter()
+    }
+
+    /// Iterates over the pairs with the keys between `start` and `end` in the order of the keys
+    #[must_use]
+    pub fn range_by_key(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Iter<'_, S> {
+        self.direct_relation.range(start, end)
+    }
+
+    /// Iterates over the pairs with the values between `start` and `end` in the order of the
+    /// values
+    ///
+    /// The start of the range is found by a search in the converse tree, so the values before
+    /// the range are not visited.
+    pub fn range_by_value<'b>(
+        &'b self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+    ) -> impl Iterator<Item = (&'b [u8], &'b [u8])> + 'b {
+        self.converse_relation
+            .range(start, end)
+            .map(|(value, key)| (key, value))
+    }
+
+    /// Walks both trees and checks that they mirror each other, returns the found
+    /// inconsistencies
+    #[must_use]
+    pub fn verify(&self) -> Vec<Inconsistency<Vec<u8>, Vec<u8>>> {
+        let mut inconsistencies = Vec::new();
+
+        for (key, value) in self.direct_relation.iter() {
+            match self.converse_relation.get(value) {
+                Some(converse_key) if converse_key == key => {}
+                Some(converse_key) => inconsistencies.push(Inconsistency::MismatchedPair {
+                    key: key.to_vec(),
+                    value: value.to_vec(),
+                    converse_key: converse_key.to_vec(),
+                }),
+                None => inconsistencies.push(Inconsistency::DanglingDirect {
+                    key: key.to_vec(),
+                    value: value.to_vec(),
+                }),
+            }
+        }
+
+        for (value, key) in self.converse_relation.iter() {
+            if self.direct_relation.get(key) != Some(value) {
+                inconsistencies.push(Inconsistency::DanglingConverse {
+                    key: key.to_vec(),
+                    value: value.to_vec(),
+                });
+            }
+        }
+
+        let direct = self.direct_relation.free_nodes_left();
+        let converse = self.converse_relation.free_nodes_left();
+        if direct != converse {
+            inconsistencies.push(Inconsistency::FreeNodesMismatch { direct, converse });
+        }
+
+        inconsistencies
+    }
+}
+
+impl<S> ByteOneToOne<S>
+where
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Inserts the pair, unless `v` already belongs to another key, returns the old value of `k`
+    ///
+    /// It never removes other pairs, all the checks are done before the trees are changed, so
+    /// the container is left intact on failure.
+    pub fn try_insert_unique(
+        &mut self,
+        k: &[u8],
+        v: &[u8],
+    ) -> Result<Option<Vec<u8>>, TryInsertError> {
+        let params = self.direct_relation.params();
+        if k.len() != params.k_size {
+            return Err(Error::WrongKeySize.into());
+        }
+        if v.len() != params.v_size {
+            return Err(Error::WrongValueSize.into());
+        }
+
+        if let Some(owner) = self.converse_relation.get(v) {
+            if owner != k {
+                return Err(TryInsertError::ValueOccupied(owner.to_vec()));
+            }
+            // The pair is already in the container
+            return Ok(Some(v.to_vec()));
+        }
+
+        let old_value = self.direct_relation.get(k).map(<[u8]>::to_vec);
+        if old_value.is_none() && self.direct_relation.free_nodes_left() == 0 {
+            return Err(Error::NoNodesLeft.into());
+        }
+
+        self.direct_relation.insert(k, v).expect(CHECKED);
+        if let Some(old_value) = &old_value {
+            self.converse_relation.remove(old_value);
+        }
+        self.converse_relation.insert(v, k).expect(CHECKED);
+
+        Ok(old_value)
+    }
+
+    /// Removes the pair with the key `k`, returns its value
+    pub fn remove_by_key(&mut self, k: &[u8]) -> Option<Vec<u8>> {
+        let value = self.direct_relation.remove(k)?;
+        self.converse_relation.remove(&value);
+        Some(value)
+    }
+
+    /// Removes the pair with the value `v`, returns its key
+    pub fn remove_by_value(&mut self, v: &[u8]) -> Option<Vec<u8>> {
+        let key = self.converse_relation.remove(v)?;
+        self.direct_relation.remove(&key);
+        Some(key)
+    }
+
+    /// Removes all the pairs
+    pub fn clear(&mut self) {
+        self.direct_relation.clear();
+        self.converse_relation.clear();
+    }
+
+    /// Rebuilds the converse tree from the direct one, returns the pairs, removed from the
+    /// direct tree
+    ///
+    /// If several keys of the direct tree have the same value, only the pair with the largest
+    /// key is kept.
+    pub fn repair(&mut self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> = self
+            .direct_relation
+            .iter()
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .collect();
+
+        self.converse_relation.clear();
+
+        let mut removed = Vec::new();
+        for (key, value) in pairs {
+            // Both trees have the same number of nodes and the converse one holds no more pairs
+            // than the direct one, so there is always a free node
+            let old_key = self
+                .converse_relation
+                .insert(&value, &key)
+                .expect("the converse tree has enough nodes for all the pairs of the direct one");
+
+            if let Some(old_key) = old_key {
+                self.direct_relation.remove(&old_key);
+                removed.push((old_key, value));
+            }
+        }
+
+        debug_assert!(self.verify().is_empty());
+
+        removed
+    }
+}
+
+const CHECKED: &str = "the insertion is checked in advance, so it can not fail";
+
+impl<S> fmt::Debug for ByteOneToOne<S>
+where
+    S: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteOneToOne")
+            .field("direct_relation", &self.direct_relation)
+            .field("converse_relation", &self.converse_relation)
+            .finish()
+    }
+}
+
+/// Error of [`ByteOneToOne::try_insert_unique`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TryInsertError {
+    /// The value already belongs to the given key
+    ValueOccupied(Vec<u8>),
+    /// The pair can not be inserted in the trees
+    Tree(Error),
+}
+
+impl From<Error> for TryInsertError {
+    fn from(err: Error) -> Self {
+        Self::Tree(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cmp::Ordering;
+
+    fn bytewise(a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+
+    #[test]
+    fn init() {
+        let mut slice = vec![0; byte_one_to_one_size(2, 1, 10)];
+
+        let container = ByteOneToOne::init_slice(&mut slice, 2, 1, bytewise).unwrap();
+        assert!(container.is_empty());
+
+        ByteOneToOne::from_slice(&mut slice, 2, 1, bytewise).unwrap();
+        ByteOneToOne::view_slice(&slice, 2, 1, bytewise).unwrap();
+
+        assert_eq!(
+            ByteOneToOne::view_slice(&slice, 1, 2, bytewise).unwrap_err(),
+            Error::WrongParams
+        );
+
+        slice[0] = 0;
+        assert_eq!(
+            ByteOneToOne::view_slice(&slice, 2, 1, bytewise).unwrap_err(),
+            Error::WrongMagic
+        );
+    }
+
+    #[test]
+    fn insert_unique() {
+        let mut slice = vec![0; byte_one_to_one_size(2, 1, 2)];
+        let mut container = ByteOneToOne::init_slice(&mut slice, 2, 1, bytewise).unwrap();
+
+        assert_eq!(container.try_insert_unique(&[0, 1], &[5]), Ok(None));
+        assert_eq!(
+            container.try_insert_unique(&[0, 1], &[5]),
+            Ok(Some(vec![5]))
+        );
+        assert_eq!(
+            container.try_insert_unique(&[0, 2], &[5]),
+            Err(TryInsertError::ValueOccupied(vec![0, 1]))
+        );
+        assert_eq!(container.try_insert_unique(&[0, 2], &[3]), Ok(None));
+        assert_eq!(
+            container.try_insert_unique(&[0, 3], &[4]),
+            Err(TryInsertError::Tree(Error::NoNodesLeft))
+        );
+        assert_eq!(
+            container.try_insert_unique(&[0, 1], &[4]),
+            Ok(Some(vec![5]))
+        );
+
+        assert_eq!(container.get_key(&[5]), None);
+        assert_eq!(container.get_key(&[4]), Some(&[0, 1][..]));
+        assert_eq!(container.first_by_value(), Some((&[0, 2][..], &[3][..])));
+        assert_eq!(container.last_by_value(), Some((&[0, 1][..], &[4][..])));
+        assert!(container.verify().is_empty());
+
+        assert_eq!(container.remove_by_value(&[3]), Some(vec![0, 2]));
+        assert_eq!(container.remove_by_key(&[0, 1]), Some(vec![4]));
+        assert!(container.is_empty());
+    }
+
+    #[test]
+    fn verify_and_repair() {
+        let mut slice = vec![0; byte_one_to_one_size(2, 1, 10)];
+        let mut container = ByteOneToOne::init_slice(&mut slice, 2, 1, bytewise).unwrap();
+
+        container.try_insert_unique(&[0, 1], &[7]).unwrap();
+        container.direct_relation.insert(&[0, 2], &[7]).unwrap();
+
+        assert_eq!(
+            container.verify(),
+            vec![
+                Inconsistency::MismatchedPair {
+                    key: vec![0, 2],
+                    value: vec![7],
+                    converse_key: vec![0, 1],
+                },
+                Inconsistency::FreeNodesMismatch {
+                    direct: 8,
+                    converse: 9,
+                },
+            ]
+        );
+
+        assert_eq!(container.repair(), vec![(vec![0, 1], vec![7])]);
+        assert!(container.verify().is_empty());
+        assert_eq!(container.get_value(&[0, 2]), Some(&[7][..]));
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+        use std::collections::BTreeMap;
+
+        const MAX_NODES: usize = 16;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            TryInsertUnique(u8, u8),
+            RemoveByKey(u8),
+            RemoveByValue(u8),
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            // Small domains make collisions of keys and values frequent
+            prop_oneof![
+                (0..24_u8, 0..24_u8).prop_map(|(k, v)| Op::TryInsertUnique(k, v)),
+                (0..24_u8).prop_map(Op::RemoveByKey),
+                (0..24_u8).prop_map(Op::RemoveByValue),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn trees_mirror_each_other(
+                ops in prop::collection::vec(op(), 1..200),
+                start in 0..24_u8,
+                end in 0..24_u8,
+            ) {
+                let mut slice = vec![0; byte_one_to_one_size(1, 1, MAX_NODES)];
+                let mut container = ByteOneToOne::init_slice(&mut slice, 1, 1, bytewise).unwrap();
+                let mut model = BTreeMap::new();
+
+                for op in ops {
+                    match op {
+                        Op::TryInsertUnique(k, v) => {
+                            let owner = model.iter().find(|(_, &val)| val == v).map(|(&key, _)| key);
+                            let expected = match owner {
+                                Some(owner) if owner != k => {
+                                    Err(TryInsertError::ValueOccupied(vec![owner]))
+                                }
+                                _ if !model.contains_key(&k) && model.len() == MAX_NODES => {
+                                    Err(TryInsertError::Tree(Error::NoNodesLeft))
+                                }
+                                _ => Ok(model.insert(k, v).map(|old| vec![old])),
+                            };
+                            prop_assert_eq!(container.try_insert_unique(&[k], &[v]), expected);
+                        }
+                        Op::RemoveByKey(k) => {
+                            prop_assert_eq!(
+                                container.remove_by_key(&[k]),
+                                model.remove(&k).map(|old| vec![old])
+                            );
+                        }
+                        Op::RemoveByValue(v) => {
+                            let owner = model.iter().find(|(_, &val)| val == v).map(|(&key, _)| key);
+                            if let Some(owner) = owner {
+                                model.remove(&owner);
+                            }
+                            prop_assert_eq!(container.remove_by_value(&[v]), owner.map(|k| vec![k]));
+                        }
+                    }
+
+                    prop_assert!(container.verify().is_empty());
+                }
+
+                let pairs: Vec<(u8, u8)> = container.iter().map(|(k, v)| (k[0], v[0])).collect();
+                prop_assert_eq!(pairs, model.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>());
+
+                if start <= end {
+                    let by_value: Vec<(u8, u8)> = container
+                        .range_by_value(Bound::Included(&[start]), Bound::Excluded(&[end]))
+                        .map(|(k, v)| (k[0], v[0]))
+                        .collect();
+                    let mut expected: Vec<(u8, u8)> = model
+                        .iter()
+                        .filter(|(_, &v)| (start..end).contains(&v))
+                        .map(|(&k, &v)| (k, v))
+                        .collect();
+                    expected.sort_unstable_by_key(|&(_, v)| v);
+                    prop_assert_eq!(by_value, expected);
+                }
+            }
+        }
+    }
+}
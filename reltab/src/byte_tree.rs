@@ -0,0 +1,1089 @@
+//! Red-black tree over byte strings with runtime key and value sizes
+//!
+//! Unlike [`RBTree`](slice_rbtree::tree::RBTree), the sizes of the nodes are not fixed by const
+//! generics and the keys are ordered by a [`Comparator`], given when the tree is opened, so a
+//! single instantiation of [`ByteTree`] serves keys and values of any type.
+//!
+//! # Internal structure
+//! [`ByteTree`] consists of two parts:
+//! - 36 bytes header: [`MAGIC`], sizes of keys and values, flags and the state of the tree as
+//!   big-endian integers
+//! - node pool, each node holds the key, the value, the color and the links to the left, right
+//!   and parent nodes
+//!
+//! If [`TreeParams::value_extremes`] is set, each node also links to the nodes with the smallest
+//! and the largest values in its subtree, so the extreme values of the tree are found in O(1).
+//!
+//! Nodes are never moved after they are allocated, deleted nodes are linked in a free list.
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+pub const MAGIC: &[u8; 8] = b"ByteTree";
+
+/// Function, ordering the keys of the tree
+///
+/// If [`TreeParams::value_extremes`] is set, it also orders the values.
+pub type Comparator = fn(&[u8], &[u8]) -> Ordering;
+
+/// Sizes of the keys and the values, and the layout of the nodes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TreeParams {
+    pub k_size: usize,
+    pub v_size: usize,
+    /// Keep the nodes with the smallest and the largest values of each subtree, it makes
+    /// [`ByteTree::min_value`] and [`ByteTree::max_value`] O(1) at the cost of 8 bytes per node
+    pub value_extremes: bool,
+}
+
+impl TreeParams {
+    fn node_size(&self) -> usize {
+        let links = if self.value_extremes {
+            EXTREMES_SIZE
+        } else {
+            0
+        };
+        self.k_size + self.v_size + LINKS_SIZE + links
+    }
+
+    fn flags(&self) -> u8 {
+        if self.value_extremes {
+            VALUE_EXTREMES
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// The slice is too small to hold the header
+    TooSmall,
+    /// The slice does not start with [`MAGIC`]
+    WrongMagic,
+    /// The sizes or the flags of the tree differ from the given [`TreeParams`]
+    WrongParams,
+    /// The node pool size does not match the header
+    WrongNodePoolSize,
+    /// The state of the tree in the header is invalid
+    CorruptedHeader,
+    /// The key does not have [`TreeParams::k_size`] bytes
+    WrongKeySize,
+    /// The value does not have [`TreeParams::v_size`] bytes
+    WrongValueSize,
+    /// All the nodes of the pool are used
+    NoNodesLeft,
+}
+
+/// Returns the required size of the slice
+#[must_use]
+#[inline]
+pub fn tree_size(params: TreeParams, max_nodes: usize) -> usize {
+    HEADER_SIZE + params.node_size() * max_nodes
+}
+
+const NIL: u32 = u32::MAX;
+
+const HEADER_SIZE: usize = 36;
+const K_SIZE: usize = 8;
+const V_SIZE: usize = 10;
+const FLAGS: usize = 12;
+const MAX_NODES: usize = 16;
+const LEN: usize = 20;
+const ROOT: usize = 24;
+const FREE_HEAD: usize = 28;
+const WATERMARK: usize = 32;
+
+const VALUE_EXTREMES: u8 = 1;
+
+// Offsets of the node fields after the key and the value
+const COLOR: usize = 0;
+const LEFT: usize = 1;
+const RIGHT: usize = 5;
+const PARENT: usize = 9;
+const MIN: usize = 13;
+const MAX: usize = 17;
+const LINKS_SIZE: usize = 13;
+const EXTREMES_SIZE: usize = 8;
+
+const BLACK: u8 = 0;
+const RED: u8 = 1;
+
+fn opposite(side: usize) -> usize {
+    if side == LEFT {
+        RIGHT
+    } else {
+        LEFT
+    }
+}
+
+/// Red-black tree, stored in a byte slice `S`
+///
+/// Read operations are available for any `S: AsRef<[u8]>`, so the tree can be opened over a
+/// shared slice, modifications require `S: AsMut<[u8]>`.
+pub struct ByteTree<S> {
+    storage: S,
+    params: TreeParams,
+    node_size: usize,
+    compare: Comparator,
+}
+
+impl<S> ByteTree<S>
+where
+    S: AsRef<[u8]>,
+{
+    /// Opens the tree, initialized by [`ByteTree::init_slice`] with the same `params`
+    ///
+    /// The header is validated, but the nodes are trusted: a corrupted node pool does not cause
+    /// undefined behavior, but the operations may panic or return wrong results.
+    pub fn from_slice(storage: S, params: TreeParams, compare: Comparator) -> Result<Self, Error> {
+        let tree = Self {
+            storage,
+            params,
+            node_size: params.node_size(),
+            compare,
+        };
+
+        let bytes = tree.bytes();
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::TooSmall);
+        }
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::WrongMagic);
+        }
+        if usize::from(tree.header_u16(K_SIZE)) != params.k_size
+            || usize::from(tree.header_u16(V_SIZE)) != params.v_size
+            || bytes[FLAGS] != params.flags()
+        {
+            return Err(Error::WrongParams);
+        }
+
+        let pool_size = bytes.len() - HEADER_SIZE;
+        let max_nodes = tree.header_u32(MAX_NODES);
+        if pool_size % tree.node_size != 0 || pool_size / tree.node_size != max_nodes as usize {
+            return Err(Error::WrongNodePoolSize);
+        }
+
+        let watermark = tree.header_u32(WATERMARK);
+        let is_node = |node: u32| node == NIL || node < watermark;
+        if watermark > max_nodes
+            || tree.header_u32(LEN) > watermark
+            || !is_node(tree.root())
+            || !is_node(tree.header_u32(FREE_HEAD))
+        {
+            return Err(Error::CorruptedHeader);
+        }
+
+        Ok(tree)
+    }
+
+    #[must_use]
+    pub fn params(&self) -> TreeParams {
+        self.params
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.header_u32(LEN) as usize
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root() == NIL
+    }
+
+    #[must_use]
+    pub fn free_nodes_left(&self) -> usize {
+        (self.header_u32(MAX_NODES) - self.header_u32(LEN)) as usize
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        match self.find(key) {
+            NIL => None,
+            node => Some(self.value(node)),
+        }
+    }
+
+    #[must_use]
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.find(key) != NIL
+    }
+
+    /// Returns the pair with the smallest key
+    #[must_use]
+    pub fn first(&self) -> Option<(&[u8], &[u8])> {
+        match self.root() {
+            NIL => None,
+            root => Some(self.pair(self.outermost(root, LEFT))),
+        }
+    }
+
+    /// Returns the pair with the largest key
+    #[must_use]
+    pub fn last(&self) -> Option<(&[u8], &[u8])> {
+        match self.root() {
+            NIL => None,
+            root => Some(self.pair(self.outermost(root, RIGHT))),
+        }
+    }
+
+    /// Iterates over the pairs in the order of the keys
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, S> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Iterates over the pairs with the keys between `start` and `end` in the order of the keys
+    ///
+    /// The first pair is found by a search in the tree, so the keys before the range are not
+    /// visited.
+    #[must_use]
+    pub fn range(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Iter<'_, S> {
+        let mut node = self.root();
+        let mut first = NIL;
+        while node != NIL {
+            let ordering = match start {
+                Bound::Included(start) => (self.compare)(self.key(node), start),
+                Bound::Excluded(start) => match (self.compare)(self.key(node), start) {
+                    Ordering::Equal => Ordering::Less,
+                    ordering => ordering,
+                },
+                Bound::Unbounded => Ordering::Greater,
+            };
+
+            if ordering == Ordering::Less {
+                node = self.link(node, RIGHT);
+            } else {
+                first = node;
+                node = self.link(node, LEFT);
+            }
+        }
+
+        Iter {
+            tree: self,
+            next: first,
+            end: match end {
+                Bound::Included(end) => Bound::Included(end.to_vec()),
+                Bound::Excluded(end) => Bound::Excluded(end.to_vec()),
+                Bound::Unbounded => Bound::Unbounded,
+            },
+        }
+    }
+
+    /// Returns the smallest value of the tree
+    ///
+    /// It is O(1) if [`TreeParams::value_extremes`] is set, otherwise all the values are scanned.
+    #[must_use]
+    pub fn min_value(&self) -> Option<&[u8]> {
+        self.extreme_value(MIN, Ordering::Less)
+    }
+
+    /// Returns the largest value of the tree
+    ///
+    /// It is O(1) if [`TreeParams::value_extremes`] is set, otherwise all the values are scanned.
+    #[must_use]
+    pub fn max_value(&self) -> Option<&[u8]> {
+        self.extreme_value(MAX, Ordering::Greater)
+    }
+
+    fn extreme_value(&self, field: usize, ordering: Ordering) -> Option<&[u8]> {
+        if self.params.value_extremes {
+            return match self.root() {
+                NIL => None,
+                root => Some(self.value(self.link(root, field))),
+            };
+        }
+
+        self.iter()
+            .map(|(_, value)| value)
+            .reduce(|extreme, value| {
+                if (self.compare)(value, extreme) == ordering {
+                    value
+                } else {
+                    extreme
+                }
+            })
+    }
+
+    fn find(&self, key: &[u8]) -> u32 {
+        let mut node = self.root();
+        while node != NIL {
+            node = match (self.compare)(key, self.key(node)) {
+                Ordering::Less => self.link(node, LEFT),
+                Ordering::Greater => self.link(node, RIGHT),
+                Ordering::Equal => return node,
+            };
+        }
+        NIL
+    }
+
+    /// Returns the last node on the path from `node`, which always goes to the `side`
+    fn outermost(&self, mut node: u32, side: usize) -> u32 {
+        loop {
+            match self.link(node, side) {
+                NIL => return node,
+                child => node = child,
+            }
+        }
+    }
+
+    fn successor(&self, node: u32) -> u32 {
+        let right = self.link(node, RIGHT);
+        if right != NIL {
+            return self.outermost(right, LEFT);
+        }
+
+        let mut node = node;
+        let mut parent = self.link(node, PARENT);
+        while parent != NIL && self.link(parent, RIGHT) == node {
+            node = parent;
+            parent = self.link(node, PARENT);
+        }
+        parent
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.storage.as_ref()
+    }
+
+    fn header_u16(&self, offset: usize) -> u16 {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(&self.bytes()[offset..offset + 2]);
+        u16::from_be_bytes(bytes)
+    }
+
+    fn header_u32(&self, offset: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.bytes()[offset..offset + 4]);
+        u32::from_be_bytes(bytes)
+    }
+
+    fn root(&self) -> u32 {
+        self.header_u32(ROOT)
+    }
+
+    fn node(&self, node: u32) -> &[u8] {
+        let start = HEADER_SIZE + node as usize * self.node_size;
+        &self.bytes()[start..start + self.node_size]
+    }
+
+    fn key(&self, node: u32) -> &[u8] {
+        &self.node(node)[..self.params.k_size]
+    }
+
+    fn value(&self, node: u32) -> &[u8] {
+        &self.node(node)[self.params.k_size..self.params.k_size + self.params.v_size]
+    }
+
+    fn pair(&self, node: u32) -> (&[u8], &[u8]) {
+        (self.key(node), self.value(node))
+    }
+
+    fn field(&self, node: u32, field: usize) -> &[u8] {
+        let offset = self.params.k_size + self.params.v_size + field;
+        &self.node(node)[offset..]
+    }
+
+    fn link(&self, node: u32, field: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.field(node, field)[..4]);
+        u32::from_be_bytes(bytes)
+    }
+
+    fn is_red(&self, node: u32) -> bool {
+        node != NIL && self.field(node, COLOR)[0] == RED
+    }
+}
+
+impl<S> ByteTree<S>
+where
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Initializes an empty tree in the slice, the number of nodes is derived from its size
+    pub fn init_slice(
+        mut storage: S,
+        params: TreeParams,
+        compare: Comparator,
+    ) -> Result<Self, Error> {
+        let bytes = storage.as_mut();
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::TooSmall);
+        }
+
+        let k_size = u16::try_from(params.k_size).map_err(|_| Error::WrongParams)?;
+        let v_size = u16::try_from(params.v_size).map_err(|_| Error::WrongParams)?;
+
+        let pool_size = bytes.len() - HEADER_SIZE;
+        let node_size = params.node_size();
+        if pool_size % node_size != 0 {
+            return Err(Error::WrongNodePoolSize);
+        }
+        let max_nodes = u32::try_from(pool_size / node_size)
+            .ok()
+            .filter(|&max_nodes| max_nodes < NIL)
+            .ok_or(Error::WrongNodePoolSize)?;
+
+        bytes[..MAGIC.len()].copy_from_slice(MAGIC);
+        bytes[K_SIZE..K_SIZE + 2].copy_from_slice(&k_size.to_be_bytes());
+        bytes[V_SIZE..V_SIZE + 2].copy_from_slice(&v_size.to_be_bytes());
+        bytes[FLAGS] = params.flags();
+        bytes[FLAGS + 1..MAX_NODES].fill(0);
+        bytes[MAX_NODES..LEN].copy_from_slice(&max_nodes.to_be_bytes());
+
+        let mut tree = Self {
+            storage,
+            params,
+            node_size,
+            compare,
+        };
+        tree.clear();
+
+        Ok(tree)
+    }
+
+    /// Inserts the pair, returns the old value of the key
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        if key.len() != self.params.k_size {
+            return Err(Error::WrongKeySize);
+        }
+        if value.len() != self.params.v_size {
+            return Err(Error::WrongValueSize);
+        }
+
+        let mut parent = NIL;
+        let mut side = LEFT;
+        let mut node = self.root();
+        while node != NIL {
+            parent = node;
+            side = match (self.compare)(key, self.key(node)) {
+                Ordering::Less => LEFT,
+                Ordering::Greater => RIGHT,
+                Ordering::Equal => {
+                    let old_value = self.value(node).to_vec();
+                    self.value_mut(node).copy_from_slice(value);
+                    self.update_extremes_upwards(node);
+                    return Ok(Some(old_value));
+                }
+            };
+            node = self.link(node, side);
+        }
+
+        let node = self.allocate()?;
+        let k_size = self.params.k_size;
+        self.node_mut(node)[..k_size].copy_from_slice(key);
+        self.value_mut(node).copy_from_slice(value);
+        self.set_color(node, RED);
+        self.set_link(node, LEFT, NIL);
+        self.set_link(node, RIGHT, NIL);
+        self.set_link(node, PARENT, parent);
+        if parent == NIL {
+            self.set_header_u32(ROOT, node);
+        } else {
+            self.set_link(parent, side, node);
+        }
+        self.set_header_u32(LEN, self.header_u32(LEN) + 1);
+
+        self.update_extremes_upwards(node);
+        self.insert_fixup(node);
+
+        Ok(None)
+    }
+
+    /// Removes the key, returns its value
+    pub fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        let node = self.find(key);
+        if node == NIL {
+            return None;
+        }
+
+        let value = self.value(node).to_vec();
+        self.delete_node(node);
+        Some(value)
+    }
+
+    /// Removes all the pairs
+    pub fn clear(&mut self) {
+        self.set_header_u32(LEN, 0);
+        self.set_header_u32(ROOT, NIL);
+        self.set_header_u32(FREE_HEAD, NIL);
+        self.set_header_u32(WATERMARK, 0);
+    }
+
+    fn allocate(&mut self) -> Result<u32, Error> {
+        let free_head = self.header_u32(FREE_HEAD);
+        if free_head != NIL {
+            self.set_header_u32(FREE_HEAD, self.link(free_head, LEFT));
+            return Ok(free_head);
+        }
+
+        let watermark = self.header_u32(WATERMARK);
+        if watermark == self.header_u32(MAX_NODES) {
+            return Err(Error::NoNodesLeft);
+        }
+        self.set_header_u32(WATERMARK, watermark + 1);
+        Ok(watermark)
+    }
+
+    /// Puts the node to the free list, it is linked by the `LEFT` field
+    fn free(&mut self, node: u32) {
+        self.set_link(node, LEFT, self.header_u32(FREE_HEAD));
+        self.set_header_u32(FREE_HEAD, node);
+    }
+
+    fn insert_fixup(&mut self, mut node: u32) {
+        // The parent of a red node is never the root, so the grandparent exists
+        while self.is_red(self.link(node, PARENT)) {
+            let parent = self.link(node, PARENT);
+            let grandparent = self.link(parent, PARENT);
+            let side = if self.link(grandparent, LEFT) == parent {
+                LEFT
+            } else {
+                RIGHT
+            };
+            let other = opposite(side);
+
+            let uncle = self.link(grandparent, other);
+            if self.is_red(uncle) {
+                self.set_color(parent, BLACK);
+                self.set_color(uncle, BLACK);
+                self.set_color(grandparent, RED);
+                node = grandparent;
+            } else {
+                if self.link(parent, other) == node {
+                    node = parent;
+                    self.rotate(node, side);
+                }
+                let parent = self.link(node, PARENT);
+                let grandparent = self.link(parent, PARENT);
+                self.set_color(parent, BLACK);
+                self.set_color(grandparent, RED);
+                self.rotate(grandparent, other);
+            }
+        }
+
+        let root = self.root();
+        self.set_color(root, BLACK);
+    }
+
+    fn delete_node(&mut self, node: u32) {
+        let mut removed_black = !self.is_red(node);
+        let child;
+        let child_parent;
+
+        let left = self.link(node, LEFT);
+        let right = self.link(node, RIGHT);
+        if left == NIL || right == NIL {
+            child = if left == NIL { right } else { left };
+            child_parent = self.link(node, PARENT);
+            self.transplant(node, child);
+        } else {
+            // The successor takes the place of the node, so the other nodes are not moved
+            let successor = self.outermost(right, LEFT);
+            removed_black = !self.is_red(successor);
+            child = self.link(successor, RIGHT);
+
+            if self.link(successor, PARENT) == node {
+                child_parent = successor;
+            } else {
+                child_parent = self.link(successor, PARENT);
+                self.transplant(successor, child);
+                self.set_link(successor, RIGHT, right);
+                self.set_link(right, PARENT, successor);
+            }
+
+            self.transplant(node, successor);
+            self.set_link(successor, LEFT, left);
+            self.set_link(left, PARENT, successor);
+            let color = self.field(node, COLOR)[0];
+            self.set_color(successor, color);
+        }
+
+        // All the changed subtrees are on the path from the parent of the moved child
+        self.update_extremes_upwards(child_parent);
+        self.free(node);
+        self.set_header_u32(LEN, self.header_u32(LEN) - 1);
+
+        if removed_black {
+            self.delete_fixup(child, child_parent);
+        }
+    }
+
+    fn delete_fixup(&mut self, mut node: u32, mut parent: u32) {
+        while node != self.root() && !self.is_red(node) {
+            // The removed node was black, so the sibling of `node` is never NIL
+            let side = if self.link(parent, LEFT) == node {
+                LEFT
+            } else {
+                RIGHT
+            };
+            let other = opposite(side);
+
+            let mut sibling = self.link(parent, other);
+            if self.is_red(sibling) {
+                self.set_color(sibling, BLACK);
+                self.set_color(parent, RED);
+                self.rotate(parent, side);
+                sibling = self.link(parent, other);
+            }
+
+            if !self.is_red(self.link(sibling, LEFT)) && !self.is_red(self.link(sibling, RIGHT)) {
+                self.set_color(sibling, RED);
+                node = parent;
+                parent = self.link(node, PARENT);
+            } else {
+                if !self.is_red(self.link(sibling, other)) {
+                    let near = self.link(sibling, side);
+                    self.set_color(near, BLACK);
+                    self.set_color(sibling, RED);
+                    self.rotate(sibling, other);
+                    sibling = self.link(parent, other);
+                }
+
+                let color = self.field(parent, COLOR)[0];
+                self.set_color(sibling, color);
+                self.set_color(parent, BLACK);
+                let far = self.link(sibling, other);
+                self.set_color(far, BLACK);
+                self.rotate(parent, side);
+                node = self.root();
+                parent = NIL;
+            }
+        }
+
+        if node != NIL {
+            self.set_color(node, BLACK);
+        }
+    }
+
+    /// Rotates the subtree of `node`, so that `node` becomes the child of its `opposite(side)`
+    /// child on the `side`
+    fn rotate(&mut self, node: u32, side: usize) {
+        let other = opposite(side);
+        let pivot = self.link(node, other);
+
+        let inner = self.link(pivot, side);
+        self.set_link(node, other, inner);
+        if inner != NIL {
+            self.set_link(inner, PARENT, node);
+        }
+
+        let parent = self.link(node, PARENT);
+        self.set_link(pivot, PARENT, parent);
+        self.replace_child(parent, node, pivot);
+
+        self.set_link(pivot, side, node);
+        self.set_link(node, PARENT, pivot);
+
+        // The subtree of the pivot has the same nodes, as the subtree of `node` had, so the
+        // ancestors are not affected
+        self.update_extremes(node);
+        self.update_extremes(pivot);
+    }
+
+    /// Replaces the subtree of `old` with the subtree of `new`
+    fn transplant(&mut self, old: u32, new: u32) {
+        let parent = self.link(old, PARENT);
+        self.replace_child(parent, old, new);
+        if new != NIL {
+            self.set_link(new, PARENT, parent);
+        }
+    }
+
+    fn replace_child(&mut self, parent: u32, old: u32, new: u32) {
+        if parent == NIL {
+            self.set_header_u32(ROOT, new);
+        } else if self.link(parent, LEFT) == old {
+            self.set_link(parent, LEFT, new);
+        } else {
+            self.set_link(parent, RIGHT, new);
+        }
+    }
+
+    fn update_extremes_upwards(&mut self, mut node: u32) {
+        if !self.params.value_extremes {
+            return;
+        }
+
+        while node != NIL {
+            self.update_extremes(node);
+            node = self.link(node, PARENT);
+        }
+    }
+
+    /// Recomputes the extremes of the node from the extremes of its children
+    fn update_extremes(&mut self, node: u32) {
+        if !self.params.value_extremes {
+            return;
+        }
+
+        let mut min = node;
+        let mut max = node;
+        for side in [LEFT, RIGHT] {
+            let child = self.link(node, side);
+            if child == NIL {
+                continue;
+            }
+
+            let child_min = self.link(child, MIN);
+            if (self.compare)(self.value(child_min), self.value(min)) == Ordering::Less {
+                min = child_min;
+            }
+            let child_max = self.link(child, MAX);
+            if (self.compare)(self.value(child_max), self.value(max)) == Ordering::Greater {
+                max = child_max;
+            }
+        }
+
+        self.set_link(node, MIN, min);
+        self.set_link(node, MAX, max);
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self.storage.as_mut()
+    }
+
+    fn set_header_u32(&mut self, offset: usize, value: u32) {
+        self.bytes_mut()[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn node_mut(&mut self, node: u32) -> &mut [u8] {
+        let start = HEADER_SIZE + node as usize * self.node_size;
+        let end = start + self.node_size;
+        &mut self.bytes_mut()[start..end]
+    }
+
+    fn value_mut(&mut self, node: u32) -> &mut [u8] {
+        let TreeParams { k_size, v_size, .. } = self.params;
+        &mut self.node_mut(node)[k_size..k_size + v_size]
+    }
+
+    fn field_mut(&mut self, node: u32, field: usize) -> &mut [u8] {
+        let offset = self.params.k_size + self.params.v_size + field;
+        &mut self.node_mut(node)[offset..]
+    }
+
+    fn set_link(&mut self, node: u32, field: usize, link: u32) {
+        self.field_mut(node, field)[..4].copy_from_slice(&link.to_be_bytes());
+    }
+
+    fn set_color(&mut self, node: u32, color: u8) {
+        self.field_mut(node, COLOR)[0] = color;
+    }
+}
+
+impl<S> fmt::Debug for ByteTree<S>
+where
+    S: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteTree")
+            .field("params", &self.params)
+            .field("len", &self.len())
+            .field("free_nodes_left", &self.free_nodes_left())
+            .finish()
+    }
+}
+
+/// Iterator over the pairs of [`ByteTree`] in the order of the keys
+pub struct Iter<'b, S> {
+    tree: &'b ByteTree<S>,
+    next: u32,
+    end: Bound<Vec<u8>>,
+}
+
+impl<'b, S> Iterator for Iter<'b, S>
+where
+    S: AsRef<[u8]>,
+{
+    type Item = (&'b [u8], &'b [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == NIL {
+            return None;
+        }
+
+        let (key, value) = self.tree.pair(self.next);
+        let is_past_end = match &self.end {
+            Bound::Included(end) => (self.tree.compare)(key, end) == Ordering::Greater,
+            Bound::Excluded(end) => (self.tree.compare)(key, end) != Ordering::Less,
+            Bound::Unbounded => false,
+        };
+        if is_past_end {
+            self.next = NIL;
+            return None;
+        }
+
+        self.next = self.tree.successor(self.next);
+        Some((key, value))
+    }
+}
+
+impl<'b, S> fmt::Debug for Iter<'b, S>
+where
+    S: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter")
+            .field("tree", self.tree)
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PARAMS: TreeParams = TreeParams {
+        k_size: 2,
+        v_size: 1,
+        value_extremes: true,
+    };
+
+    fn bytewise(a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+
+    /// Checks the red-black invariants and the extremes of the subtree, returns its black height
+    fn check_subtree<S: AsRef<[u8]>>(tree: &ByteTree<S>, node: u32, parent: u32) -> usize {
+        if node == NIL {
+            return 1;
+        }
+
+        assert_eq!(tree.link(node, PARENT), parent);
+        if tree.is_red(node) {
+            assert!(!tree.is_red(tree.link(node, LEFT)));
+            assert!(!tree.is_red(tree.link(node, RIGHT)));
+        }
+
+        let left = check_subtree(tree, tree.link(node, LEFT), node);
+        let right = check_subtree(tree, tree.link(node, RIGHT), node);
+        assert_eq!(left, right);
+
+        if tree.params.value_extremes {
+            let mut values = Vec::new();
+            let mut stack = vec![node];
+            while let Some(node) = stack.pop() {
+                if node != NIL {
+                    values.push(tree.value(node));
+                    stack.push(tree.link(node, LEFT));
+                    stack.push(tree.link(node, RIGHT));
+                }
+            }
+            assert_eq!(
+                tree.value(tree.link(node, MIN)),
+                *values.iter().min().unwrap()
+            );
+            assert_eq!(
+                tree.value(tree.link(node, MAX)),
+                *values.iter().max().unwrap()
+            );
+        }
+
+        left + usize::from(!tree.is_red(node))
+    }
+
+    fn check_invariants<S: AsRef<[u8]>>(tree: &ByteTree<S>) {
+        assert!(!tree.is_red(tree.root()));
+        check_subtree(tree, tree.root(), NIL);
+    }
+
+    #[test]
+    fn init() {
+        let mut slice = vec![0; tree_size(PARAMS, 10)];
+
+        let tree = ByteTree::init_slice(slice.as_mut_slice(), PARAMS, bytewise).unwrap();
+        assert!(tree.is_empty());
+        assert_eq!(tree.free_nodes_left(), 10);
+
+        let tree = ByteTree::from_slice(slice.as_slice(), PARAMS, bytewise).unwrap();
+        assert!(tree.is_empty());
+        assert_eq!(tree.first(), None);
+        assert_eq!(tree.min_value(), None);
+    }
+
+    #[test]
+    fn from_slice_errors() {
+        assert_eq!(
+            ByteTree::from_slice(&[0; 10][..], PARAMS, bytewise).unwrap_err(),
+            Error::TooSmall
+        );
+        assert_eq!(
+            ByteTree::from_slice(&[0; 100][..], PARAMS, bytewise).unwrap_err(),
+            Error::WrongMagic
+        );
+
+        let mut slice = vec![0; tree_size(PARAMS, 10)];
+        ByteTree::init_slice(slice.as_mut_slice(), PARAMS, bytewise).unwrap();
+
+        let other_params = TreeParams {
+            value_extremes: false,
+            ..PARAMS
+        };
+        assert_eq!(
+            ByteTree::from_slice(slice.as_slice(), other_params, bytewise).unwrap_err(),
+            Error::WrongParams
+        );
+        assert_eq!(
+            ByteTree::from_slice(&slice[..slice.len() - 1], PARAMS, bytewise).unwrap_err(),
+            Error::WrongNodePoolSize
+        );
+
+        slice[ROOT..ROOT + 4].copy_from_slice(&5_u32.to_be_bytes());
+        assert_eq!(
+            ByteTree::from_slice(slice.as_slice(), PARAMS, bytewise).unwrap_err(),
+            Error::CorruptedHeader
+        );
+    }
+
+    #[test]
+    fn wrong_sizes() {
+        let mut slice = vec![0; tree_size(PARAMS, 1)];
+        let mut tree = ByteTree::init_slice(slice.as_mut_slice(), PARAMS, bytewise).unwrap();
+
+        assert_eq!(tree.insert(&[1], &[1]), Err(Error::WrongKeySize));
+        assert_eq!(tree.insert(&[1, 1], &[1, 1]), Err(Error::WrongValueSize));
+        assert_eq!(tree.insert(&[1, 1], &[1]), Ok(None));
+        assert_eq!(tree.insert(&[1, 2], &[1]), Err(Error::NoNodesLeft));
+        assert_eq!(tree.insert(&[1, 1], &[2]), Ok(Some(vec![1])));
+    }
+
+    #[test]
+    fn runtime_comparator() {
+        fn reversed(a: &[u8], b: &[u8]) -> Ordering {
+            b.cmp(a)
+        }
+
+        let mut slice = vec![0; tree_size(PARAMS, 10)];
+        let mut tree = ByteTree::init_slice(slice.as_mut_slice(), PARAMS, reversed).unwrap();
+
+        for i in 0..5 {
+            tree.insert(&[0, i], &[i]).unwrap();
+        }
+
+        let keys: Vec<&[u8]> = tree.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![&[0, 4], &[0, 3], &[0, 2], &[0, 1], &[0, 0]]);
+
+        // The smallest value in the order of the comparator
+        assert_eq!(tree.min_value(), Some(&[4][..]));
+        assert_eq!(tree.max_value(), Some(&[0][..]));
+    }
+
+    #[test]
+    fn range() {
+        let mut slice = vec![0; tree_size(PARAMS, 20)];
+        let mut tree = ByteTree::init_slice(slice.as_mut_slice(), PARAMS, bytewise).unwrap();
+
+        for i in 0..10 {
+            tree.insert(&[0, i * 2], &[i]).unwrap();
+        }
+
+        let values = |start: Bound<&[u8]>, end: Bound<&[u8]>| -> Vec<u8> {
+            tree.range(start, end).map(|(_, value)| value[0]).collect()
+        };
+
+        assert_eq!(
+            values(Bound::Included(&[0, 4]), Bound::Excluded(&[0, 10])),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            values(Bound::Excluded(&[0, 4]), Bound::Included(&[0, 10])),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            values(Bound::Included(&[0, 5]), Bound::Included(&[0, 5])),
+            Vec::<u8>::new()
+        );
+        assert_eq!(
+            values(Bound::Unbounded, Bound::Excluded(&[0, 3])),
+            vec![0, 1]
+        );
+        assert_eq!(
+            values(Bound::Included(&[0, 15]), Bound::Unbounded),
+            vec![8, 9]
+        );
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+        use std::collections::BTreeMap;
+
+        const MAX_NODES: usize = 32;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Insert(u8, u8),
+            Remove(u8),
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                (0..48_u8, any::<u8>()).prop_map(|(k, v)| Op::Insert(k, v)),
+                (0..48_u8).prop_map(Op::Remove),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn behaves_as_btree_map(
+                ops in prop::collection::vec(op(), 1..300),
+                value_extremes in any::<bool>(),
+                start in 0..48_u8,
+                end in 0..48_u8,
+            ) {
+                let params = TreeParams { value_extremes, ..PARAMS };
+                let mut slice = vec![0; tree_size(params, MAX_NODES)];
+                let mut tree = ByteTree::init_slice(slice.as_mut_slice(), params, bytewise).unwrap();
+                let mut model = BTreeMap::new();
+
+                for op in ops {
+                    match op {
+                        Op::Insert(k, v) => {
+                            let expected = if model.len() == MAX_NODES && !model.contains_key(&[0, k]) {
+                                Err(Error::NoNodesLeft)
+                            } else {
+                                Ok(model.insert([0, k], [v]).map(|old| old.to_vec()))
+                            };
+                            prop_assert_eq!(tree.insert(&[0, k], &[v]), expected);
+                        }
+                        Op::Remove(k) => {
+                            prop_assert_eq!(
+                                tree.remove(&[0, k]),
+                                model.remove(&[0, k]).map(|old| old.to_vec())
+                            );
+                        }
+                    }
+
+                    check_invariants(&tree);
+                    prop_assert_eq!(tree.len(), model.len());
+                    prop_assert_eq!(tree.free_nodes_left(), MAX_NODES - model.len());
+                    prop_assert_eq!(tree.min_value(), model.values().min().map(|v| &v[..]));
+                    prop_assert_eq!(tree.max_value(), model.values().max().map(|v| &v[..]));
+                }
+
+                let pairs: Vec<(&[u8], &[u8])> = tree.iter().collect();
+                let expected: Vec<(&[u8], &[u8])> =
+                    model.iter().map(|(k, v)| (&k[..], &v[..])).collect();
+                prop_assert_eq!(pairs, expected);
+
+                if start <= end {
+                    let range: Vec<(&[u8], &[u8])> = tree
+                        .range(Bound::Included(&[0, start]), Bound::Excluded(&[0, end]))
+                        .collect();
+                    let expected: Vec<(&[u8], &[u8])> = model
+                        .range([0, start]..[0, end])
+                        .map(|(k, v)| (&k[..], &v[..]))
+                        .collect();
+                    prop_assert_eq!(range, expected);
+                }
+
+                for (k, v) in model.iter() {
+                    prop_assert_eq!(tree.get(k), Some(&v[..]));
+                }
+            }
+        }
+    }
+}
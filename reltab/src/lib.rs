@@ -1,6 +1,10 @@
-//! A collection of relational containers based on [slice-rbtree](slice_rbtree)
+//! A collection of relational containers based on [slice-rbtree](slice_rbtree) and on
+//! [`ByteTree`](byte_tree::ByteTree), its counterpart with runtime key and value sizes
 #![deny(unsafe_op_in_unsafe_fn)]
 #![deny(missing_debug_implementations)]
 //#![deny(missing_docs)]
 
+pub mod byte_one_to_one;
+pub mod byte_tree;
+pub mod many_to_many;
 pub mod one_to_one;
@@ -0,0 +1,388 @@
+//! This container represents a many to many relation: a set of `(K, V)` pairs, where each key
+//! may be related to several values and vice versa.
+//!
+//! # Internal structure
+//! [`ManyToMany`] container consists of three parts:
+//! - 20 bytes magic string, must be equal to [`MAGIC`]
+//! - [`RBTree`] of `(K, V)` pairs
+//! - [`RBTree`] of `(V, K)` pairs
+//!
+//! Both trees have the same size. Pairs are the keys of the trees, so the pairs with the same
+//! key (or value) are stored next to each other.
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::fmt;
+
+use slice_rbtree::tree::{tree_size, RBTree, TreeParams};
+use slice_rbtree::Error;
+
+pub const MAGIC: &[u8; 20] = b"ManyToMany container";
+
+/// Returns the required size of the slice, `k_size + v_size` must be equal to the `SIZE` of the
+/// [`ManyToMany`]
+#[must_use]
+#[inline]
+pub fn many_to_many_size(k_size: usize, v_size: usize, max_pairs: usize) -> usize {
+    let params = TreeParams {
+        k_size: k_size + v_size,
+        v_size: 0,
+    };
+    2 * tree_size(params, max_pairs) + MAGIC.len()
+}
+
+/// Pair, stored in the trees, it is serialized exactly as the `(A, B)` tuple
+///
+/// Pairs are ordered by `A` first, so the pairs with the same `A` form a contiguous run in the
+/// tree.
+#[derive(PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize, Clone, Debug)]
+struct Pair<A, B>(A, B)
+where
+    A: BorshSerialize + BorshDeserialize,
+    B: BorshSerialize + BorshDeserialize;
+
+/// Many to many relation, `SIZE` is the maximal size of the serialized key and value together
+pub struct ManyToMany<'a, K, V, const SIZE: usize>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    direct_relation: RBTree<'a, Pair<K, V>, (), SIZE, 0>,
+    converse_relation: RBTree<'a, Pair<V, K>, (), SIZE, 0>,
+}
+
+impl<'a, K, V, const SIZE: usize> ManyToMany<'a, K, V, SIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    pub fn init_slice(slice: &'a mut [u8]) -> Result<Self, Error> {
+        if slice.len() < MAGIC.len() {
+            return Err(Error::TooSmall);
+        }
+
+        let (magic, tail) = slice.split_at_mut(MAGIC.len());
+
+        if tail.len() % 2 != 0 {
+            return Err(Error::WrongNodePoolSize);
+        }
+
+        let (direct, converse) = tail.split_at_mut(tail.len() / 2);
+        check_tree_size::<SIZE>(direct.len())?;
+
+        let direct_relation = RBTree::<'a, Pair<K, V>, (), SIZE, 0>::init_slice(direct)?;
+        let converse_relation = RBTree::<'a, Pair<V, K>, (), SIZE, 0>::init_slice(converse)?;
+
+        magic.copy_from_slice(MAGIC);
+
+        Ok(Self {
+            direct_relation,
+            converse_relation,
+        })
+    }
+
+    pub unsafe fn from_slice(slice: &'a mut [u8]) -> Result<Self, Error> {
+        if slice.len() < MAGIC.len() {
+            return Err(Error::TooSmall);
+        }
+
+        let (magic, tail) = slice.split_at_mut(MAGIC.len());
+
+        if magic != MAGIC {
+            return Err(Error::WrongMagic);
+        }
+
+        if tail.len() % 2 != 0 {
+            return Err(Error::WrongNodePoolSize);
+        }
+
+        let (direct, converse) = tail.split_at_mut(tail.len() / 2);
+        check_tree_size::<SIZE>(direct.len())?;
+
+        unsafe {
+            let direct_relation = RBTree::<'a, Pair<K, V>, (), SIZE, 0>::from_slice(direct)?;
+            let converse_relation = RBTree::<'a, Pair<V, K>, (), SIZE, 0>::from_slice(converse)?;
+
+            Ok(Self {
+                direct_relation,
+                converse_relation,
+            })
+        }
+    }
+
+    /// Returns the number of pairs
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.direct_relation.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.direct_relation.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.direct_relation.clear();
+        self.converse_relation.clear();
+    }
+
+    #[must_use]
+    pub fn free_nodes_left(&self) -> usize {
+        self.direct_relation.free_nodes_left()
+    }
+
+    #[must_use]
+    pub fn contains(&self, k: &K, v: &V) -> bool {
+        self.direct_relation
+            .contains_key(&Pair(k.clone(), v.clone()))
+    }
+
+    /// Inserts the pair, returns `false` if it is already present
+    ///
+    /// Returns [`Error::KeySerializationError`] if the key alone does not fit in `SIZE` and
+    /// [`Error::ValueSerializationError`] if the value does not fit after the key. All the checks
+    /// are done before the trees are changed, so a failed insertion leaves the container intact.
+    pub fn insert(&mut self, k: K, v: V) -> Result<bool, Error> {
+        let key_len = serialized_len(&k).map_err(|_| Error::KeySerializationError)?;
+        if key_len > SIZE {
+            return Err(Error::KeySerializationError);
+        }
+        let value_len = serialized_len(&v).map_err(|_| Error::ValueSerializationError)?;
+        if key_len + value_len > SIZE {
+            return Err(Error::ValueSerializationError);
+        }
+
+        if self.contains(&k, &v) {
+            return Ok(false);
+        }
+
+        if self.free_nodes_left() == 0 {
+            return Err(Error::NoNodesLeft);
+        }
+
+        self.direct_relation
+            .insert(Pair(k.clone(), v.clone()), ())
+            .expect(CHECKED);
+        self.converse_relation
+            .insert(Pair(v, k), ())
+            .expect(CHECKED);
+
+        debug_assert_eq!(
+            self.direct_relation.free_nodes_left(),
+            self.converse_relation.free_nodes_left()
+        );
+
+        Ok(true)
+    }
+
+    /// Removes the pair, returns `false` if it was not present
+    pub fn remove(&mut self, k: &K, v: &V) -> bool {
+        let removed = self.direct_relation.delete(&Pair(k.clone(), v.clone()));
+        if removed {
+            let result = self.converse_relation.delete(&Pair(v.clone(), k.clone()));
+            debug_assert!(result);
+        }
+        debug_assert_eq!(
+            self.direct_relation.free_nodes_left(),
+            self.converse_relation.free_nodes_left()
+        );
+        removed
+    }
+
+    /// Iterates over the values, related to the key `k`, in ascending order
+    ///
+    /// The pairs of the smaller keys are skipped one by one, the iteration stops right after the
+    /// last pair of the key.
+    #[must_use]
+    pub fn values_of<'b>(&'b self, k: &K) -> Box<dyn Iterator<Item = V> + 'b> {
+        let k = k.clone();
+        Box::new(
+            self.direct_relation
+                .keys()
+                .skip_while({
+                    let k = k.clone();
+                    move |Pair(key, _)| *key < k
+                })
+                .take_while(move |Pair(key, _)| *key == k)
+                .map(|Pair(_, value)| value),
+        )
+    }
+
+    /// Iterates over the keys, related to the value `v`, in ascending order
+    ///
+    /// The pairs of the smaller values are skipped one by one, the iteration stops right after
+    /// the last pair of the value.
+    #[must_use]
+    pub fn keys_of<'b>(&'b self, v: &V) -> Box<dyn Iterator<Item = K> + 'b> {
+        let v = v.clone();
+        Box::new(
+            self.converse_relation
+                .keys()
+                .skip_while({
+                    let v = v.clone();
+                    move |Pair(value, _)| *value < v
+                })
+                .take_while(move |Pair(value, _)| *value == v)
+                .map(|Pair(_, key)| key),
+        )
+    }
+
+    /// Returns the number of values, related to the key `k`, the values are visited one by one
+    #[must_use]
+    pub fn count_values_of(&self, k: &K) -> usize {
+        self.values_of(k).count()
+    }
+
+    /// Returns the number of keys, related to the value `v`, the keys are visited one by one
+    #[must_use]
+    pub fn count_keys_of(&self, v: &V) -> usize {
+        self.keys_of(v).count()
+    }
+
+    /// Iterates over the pairs in the order of the keys
+    #[must_use]
+    pub fn iter<'b>(&'b self) -> Box<dyn Iterator<Item = (K, V)> + 'b> {
+        Box::new(self.direct_relation.keys().map(|Pair(k, v)| (k, v)))
+    }
+}
+
+const CHECKED: &str = "the insertion is checked in advance, so it can not fail";
+
+fn serialized_len<T: BorshSerialize>(value: &T) -> Result<usize, std::io::Error> {
+    value.try_to_vec().map(|bytes| bytes.len())
+}
+
+/// Checks that a tree of `len` bytes consists of the nodes of `SIZE`, so that the slice was
+/// sized by [`many_to_many_size`] with `k_size + v_size == SIZE`
+fn check_tree_size<const SIZE: usize>(len: usize) -> Result<(), Error> {
+    let params = TreeParams {
+        k_size: SIZE,
+        v_size: 0,
+    };
+    let header_size = tree_size(params, 0);
+    let node_size = tree_size(params, 1) - header_size;
+
+    if len < header_size || (len - header_size) % node_size != 0 {
+        return Err(Error::WrongNodePoolSize);
+    }
+    Ok(())
+}
+
+impl<'a, K, V, const SIZE: usize> fmt::Debug for ManyToMany<'a, K, V, SIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn init() {
+        let mut slice = vec![0; many_to_many_size(4, 4, 100)];
+
+        let container = ManyToMany::<u32, u32, 8>::init_slice(&mut slice).unwrap();
+
+        assert!(container.is_empty());
+
+        unsafe {
+            ManyToMany::<u32, u32, 8>::from_slice(&mut slice).unwrap();
+        }
+    }
+
+    #[test]
+    fn wrong_size() {
+        let mut slice = vec![0; many_to_many_size(4, 4, 1)];
+
+        let err = ManyToMany::<u32, u32, 12>::init_slice(&mut slice).unwrap_err();
+        assert_eq!(err, Error::WrongNodePoolSize);
+
+        ManyToMany::<u32, u32, 8>::init_slice(&mut slice).unwrap();
+        let err = unsafe { ManyToMany::<u32, u32, 12>::from_slice(&mut slice).unwrap_err() };
+        assert_eq!(err, Error::WrongNodePoolSize);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut slice = vec![0; many_to_many_size(4, 4, 100)];
+
+        let mut container = ManyToMany::<u32, u32, 8>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.insert(1, 10), Ok(true));
+        assert_eq!(container.insert(1, 11), Ok(true));
+        assert_eq!(container.insert(2, 10), Ok(true));
+        assert_eq!(container.insert(1, 10), Ok(false));
+
+        assert_eq!(container.len(), 3);
+        assert!(container.contains(&1, &11));
+        assert!(!container.contains(&2, &11));
+
+        assert!(container.remove(&1, &10));
+        assert!(!container.remove(&1, &10));
+
+        assert_eq!(container.iter().collect::<Vec<_>>(), vec![(1, 11), (2, 10)]);
+    }
+
+    #[test]
+    fn relations() {
+        let mut slice = vec![0; many_to_many_size(4, 4, 100)];
+
+        let mut container = ManyToMany::<u32, u32, 8>::init_slice(&mut slice).unwrap();
+
+        for (k, v) in [(1, 30), (2, 10), (1, 10), (3, 20), (1, 20), (3, 10)] {
+            assert_eq!(container.insert(k, v), Ok(true));
+        }
+
+        assert_eq!(
+            container.values_of(&1).collect::<Vec<_>>(),
+            vec![10, 20, 30]
+        );
+        assert_eq!(container.values_of(&3).collect::<Vec<_>>(), vec![10, 20]);
+        assert_eq!(container.values_of(&4).count(), 0);
+
+        assert_eq!(container.keys_of(&10).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(container.keys_of(&30).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(container.keys_of(&40).count(), 0);
+
+        assert_eq!(container.count_values_of(&1), 3);
+        assert_eq!(container.count_values_of(&2), 1);
+        assert_eq!(container.count_keys_of(&20), 2);
+    }
+
+    #[test]
+    fn insertion_failures() {
+        let mut slice = vec![0; many_to_many_size(8, 8, 2)];
+
+        let mut container = ManyToMany::<String, u32, 16>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(
+            container.insert("key, which is too long".to_string(), 1),
+            Err(Error::KeySerializationError)
+        );
+        assert_eq!(
+            container.insert("twelve bytes".to_string(), 1),
+            Err(Error::ValueSerializationError)
+        );
+
+        assert_eq!(container.insert("one".to_string(), 1), Ok(true));
+        assert_eq!(container.insert("two".to_string(), 1), Ok(true));
+        assert_eq!(
+            container.insert("three".to_string(), 1),
+            Err(Error::NoNodesLeft)
+        );
+        assert_eq!(container.insert("two".to_string(), 1), Ok(false));
+
+        assert_eq!(
+            container.keys_of(&1).collect::<Vec<_>>(),
+            vec!["one".to_string(), "two".to_string()]
+        );
+        assert_eq!(
+            container.direct_relation.free_nodes_left(),
+            container.converse_relation.free_nodes_left()
+        );
+    }
+}
@@ -10,9 +10,11 @@
 //! Both trees have the same size
 use borsh::{BorshDeserialize, BorshSerialize};
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::{Bound, RangeBounds};
 
-use slice_rbtree::tree::{tree_size, KeysIterator, RBTree, TreeParams};
+use slice_rbtree::tree::{tree_size, KeysIterator, PairsIterator, RBTree, TreeParams};
 use slice_rbtree::Error;
 
 pub const MAGIC: &[u8; 18] = b"OneToOne container";
@@ -159,28 +161,47 @@ where
         self.converse_relation.get(v)
     }
 
+    /// Inserts the pair, returns the old key of `v` and the old value of `k`, which are removed
+    /// from the container
     pub fn insert(&mut self, k: K, v: V) -> Result<(Option<K>, Option<V>), Error> {
-        let maybe_old_val = self.direct_relation.get(&k);
-        let maybe_old_key = self.converse_relation.get(&v);
-
-        self.direct_relation.insert(k.clone(), v.clone())?;
-        self.converse_relation
-            .insert(v, k)
-            .expect("insertion in second relation failed, the container is now corrupted");
-
-        if let Some(ref old_key) = maybe_old_key {
-            self.direct_relation.delete(old_key);
+        match self.entry(k) {
+            Entry::Occupied(entry) => entry
+                .insert(v)
+                .map(|(old_key, old_val)| (old_key, Some(old_val))),
+            Entry::Vacant(entry) => entry.insert(v).map(|old_key| (old_key, None)),
         }
-        if let Some(ref old_val) = maybe_old_val {
-            self.converse_relation.delete(old_val);
+    }
+
+    /// Inserts the pair, unless `v` already belongs to another key, returns the old value of `k`
+    ///
+    /// Unlike [`OneToOne::insert`], it never removes other pairs, the container is left intact
+    /// on failure.
+    pub fn try_insert_unique(&mut self, k: K, v: V) -> Result<Option<V>, TryInsertError<K>> {
+        if let Some(owner) = self.converse_relation.get(&v) {
+            if owner != k {
+                return Err(TryInsertError::ValueOccupied(owner));
+            }
         }
 
-        debug_assert_eq!(
-            self.direct_relation.free_nodes_left(),
-            self.converse_relation.free_nodes_left()
-        );
+        let (old_key, old_val) = self.insert(k, v)?;
+        debug_assert!(old_key.is_none());
 
-        Ok((maybe_old_key, maybe_old_val))
+        Ok(old_val)
+    }
+
+    /// Gets the entry of the key `k` for in-place manipulation
+    pub fn entry<'b>(&'b mut self, k: K) -> Entry<'b, 'a, K, V, KSIZE, VSIZE> {
+        match self.direct_relation.get(&k) {
+            Some(value) => Entry::Occupied(OccupiedEntry {
+                container: self,
+                key: k,
+                value,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                container: self,
+                key: k,
+            }),
+        }
     }
 
     #[must_use]
@@ -258,6 +279,36 @@ where
         })
     }
 
+    /// Returns the entry with the smallest key
+    #[must_use]
+    pub fn first_by_key(&self) -> Option<(K, V)> {
+        self.direct_relation.pairs().next()
+    }
+
+    /// Returns the entry with the largest key, all the pairs are visited
+    #[must_use]
+    pub fn last_by_key(&self) -> Option<(K, V)> {
+        self.direct_relation.pairs().last()
+    }
+
+    /// Returns the entry with the smallest value
+    #[must_use]
+    pub fn first_by_value(&self) -> Option<(K, V)> {
+        self.converse_relation
+            .pairs()
+            .next()
+            .map(|(value, key)| (key, value))
+    }
+
+    /// Returns the entry with the largest value, all the pairs are visited
+    #[must_use]
+    pub fn last_by_value(&self) -> Option<(K, V)> {
+        self.converse_relation
+            .pairs()
+            .last()
+            .map(|(value, key)| (key, value))
+    }
+
     #[must_use]
     pub fn keys<'b>(&'b self) -> KeysIterator<'b, 'a, K, V, KSIZE, VSIZE> {
         self.direct_relation.keys()
@@ -267,6 +318,383 @@ where
     pub fn values<'b>(&'b self) -> KeysIterator<'b, 'a, V, K, VSIZE, KSIZE> {
         self.converse_relation.keys()
     }
+
+    /// Iterates over the pairs in the order of the keys
+    #[must_use]
+    pub fn iter<'b>(&'b self) -> PairsIterator<'b, 'a, K, V, KSIZE, VSIZE> {
+        self.direct_relation.pairs()
+    }
+
+    /// Iterates over the pairs with the keys in the `range` in the order of the keys
+    ///
+    /// The keys before the range are skipped one by one, the iteration stops at the first key
+    /// after the range.
+    #[must_use]
+    pub fn range_by_key<'b, Q, R>(&'b self, range: R) -> Box<dyn Iterator<Item = (K, V)> + 'b>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized + 'b,
+        R: RangeBounds<Q> + 'b,
+    {
+        Box::new(
+            self.direct_relation
+                .pairs()
+                .map(move |(key, value)| (position(&range, key.borrow()), (key, value)))
+                .skip_while(|(position, _)| *position == Ordering::Less)
+                .take_while(|(position, _)| *position == Ordering::Equal)
+                .map(|(_, pair)| pair),
+        )
+    }
+
+    /// Iterates over the pairs with the values in the `range` in the order of the values
+    ///
+    /// The values before the range are skipped one by one, the iteration stops at the first
+    /// value after the range.
+    #[must_use]
+    pub fn range_by_value<'b, Q, R>(&'b self, range: R) -> Box<dyn Iterator<Item = (K, V)> + 'b>
+    where
+        V: Borrow<Q>,
+        Q: Ord + ?Sized + 'b,
+        R: RangeBounds<Q> + 'b,
+    {
+        Box::new(
+            self.converse_relation
+                .pairs()
+                .map(move |(value, key)| (position(&range, value.borrow()), (key, value)))
+                .skip_while(|(position, _)| *position == Ordering::Less)
+                .take_while(|(position, _)| *position == Ordering::Equal)
+                .map(|(_, pair)| pair),
+        )
+    }
+
+    /// Walks both trees and checks that they mirror each other, returns the found
+    /// inconsistencies
+    ///
+    /// [`OneToOne::from_slice`] trusts the content of the slice, so this check is the way to
+    /// detect a corruption of the container.
+    #[must_use]
+    pub fn verify(&self) -> Vec<Inconsistency<K, V>> {
+        let mut inconsistencies = Vec::new();
+
+        for (key, value) in self.direct_relation.pairs() {
+            match self.converse_relation.get(&value) {
+                Some(converse_key) if converse_key == key => {}
+                Some(converse_key) => inconsistencies.push(Inconsistency::MismatchedPair {
+                    key,
+                    value,
+                    converse_key,
+                }),
+                None => inconsistencies.push(Inconsistency::DanglingDirect { key, value }),
+            }
+        }
+
+        for (value, key) in self.converse_relation.pairs() {
+            if self.direct_relation.get(&key).as_ref() != Some(&value) {
+                inconsistencies.push(Inconsistency::DanglingConverse { key, value });
+            }
+        }
+
+        let direct = self.direct_relation.free_nodes_left();
+        let converse = self.converse_relation.free_nodes_left();
+        if direct != converse {
+            inconsistencies.push(Inconsistency::FreeNodesMismatch { direct, converse });
+        }
+
+        inconsistencies
+    }
+
+    /// Rebuilds the converse tree from the direct one, returns the pairs, removed from the
+    /// direct tree
+    ///
+    /// If several keys of the direct tree have the same value, only the pair with the largest
+    /// key is kept.
+    pub fn repair(&mut self) -> Vec<(K, V)> {
+        let pairs: Vec<(K, V)> = self.direct_relation.pairs().collect();
+
+        self.converse_relation.clear();
+
+        let mut removed = Vec::new();
+        for (key, value) in pairs {
+            // Both trees have the same number of nodes and the converse one holds no more pairs
+            // than the direct one, so there is always a free node
+            let old_key = self
+                .converse_relation
+                .insert(value.clone(), key)
+                .expect("the converse tree has enough nodes for all the pairs of the direct one");
+
+            if let Some(old_key) = old_key {
+                self.direct_relation.delete(&old_key);
+                removed.push((old_key, value));
+            }
+        }
+
+        debug_assert!(self.verify().is_empty());
+
+        removed
+    }
+}
+
+/// Returns the position of the `item` relative to the `range`: [`Ordering::Less`] if it is
+/// before the range, [`Ordering::Greater`] if it is after it and [`Ordering::Equal`] if it is
+/// inside
+fn position<Q, R>(range: &R, item: &Q) -> Ordering
+where
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+{
+    let before_start = match range.start_bound() {
+        Bound::Included(start) => item < start,
+        Bound::Excluded(start) => item <= start,
+        Bound::Unbounded => false,
+    };
+    let after_end = match range.end_bound() {
+        Bound::Included(end) => item > end,
+        Bound::Excluded(end) => item >= end,
+        Bound::Unbounded => false,
+    };
+
+    if before_start {
+        Ordering::Less
+    } else if after_end {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+const CHECKED: &str = "the insertion is checked in advance, so it can not fail";
+
+/// Checks that the pair fits in the nodes of the trees
+///
+/// All the checks are done before the trees are changed, so that a failed insertion leaves the
+/// container intact.
+fn check_sizes<K, V, const KSIZE: usize, const VSIZE: usize>(k: &K, v: &V) -> Result<(), Error>
+where
+    K: BorshSerialize,
+    V: BorshSerialize,
+{
+    if k.try_to_vec().map_or(true, |bytes| bytes.len() > KSIZE) {
+        return Err(Error::KeySerializationError);
+    }
+    if v.try_to_vec().map_or(true, |bytes| bytes.len() > VSIZE) {
+        return Err(Error::ValueSerializationError);
+    }
+    Ok(())
+}
+
+/// Inconsistency between the trees of [`OneToOne`], found by [`OneToOne::verify`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Inconsistency<K, V> {
+    /// The pair of the direct tree, which value has another key in the converse tree
+    MismatchedPair { key: K, value: V, converse_key: K },
+    /// The pair of the direct tree, which value is missing in the converse tree
+    DanglingDirect { key: K, value: V },
+    /// The pair of the converse tree, which is missing in the direct tree
+    DanglingConverse { key: K, value: V },
+    /// The trees have different numbers of free nodes
+    FreeNodesMismatch { direct: usize, converse: usize },
+}
+
+impl<K, V> Inconsistency<K, V> {
+    /// Converts the keys and the values of the inconsistency
+    pub fn map<K2, V2, FK, FV>(self, map_key: FK, map_value: FV) -> Inconsistency<K2, V2>
+    where
+        FK: Fn(K) -> K2,
+        FV: Fn(V) -> V2,
+    {
+        match self {
+            Self::MismatchedPair {
+                key,
+                value,
+                converse_key,
+            } => Inconsistency::MismatchedPair {
+                key: map_key(key),
+                value: map_value(value),
+                converse_key: map_key(converse_key),
+            },
+            Self::DanglingDirect { key, value } => Inconsistency::DanglingDirect {
+                key: map_key(key),
+                value: map_value(value),
+            },
+            Self::DanglingConverse { key, value } => Inconsistency::DanglingConverse {
+                key: map_key(key),
+                value: map_value(value),
+            },
+            Self::FreeNodesMismatch { direct, converse } => {
+                Inconsistency::FreeNodesMismatch { direct, converse }
+            }
+        }
+    }
+}
+
+/// Error of [`OneToOne::try_insert_unique`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryInsertError<K> {
+    /// The value already belongs to the given key
+    ValueOccupied(K),
+    /// The pair can not be inserted in the trees
+    Tree(Error),
+}
+
+impl<K> From<Error> for TryInsertError<K> {
+    fn from(err: Error) -> Self {
+        Self::Tree(err)
+    }
+}
+
+/// A view into a single entry of [`OneToOne`], which may be either vacant or occupied
+///
+/// This is constructed from the [`OneToOne::entry`] method.
+pub enum Entry<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    Occupied(OccupiedEntry<'b, 'a, K, V, KSIZE, VSIZE>),
+    Vacant(VacantEntry<'b, 'a, K, V, KSIZE, VSIZE>),
+}
+
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> Entry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    /// Returns the key of the entry
+    #[must_use]
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+/// An occupied entry of [`OneToOne`], the key and its value are already read from the container
+pub struct OccupiedEntry<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    container: &'b mut OneToOne<'a, K, V, KSIZE, VSIZE>,
+    key: K,
+    value: V,
+}
+
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> OccupiedEntry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    #[must_use]
+    pub fn get(&self) -> &V {
+        &self.value
+    }
+
+    /// Replaces the value of the entry with `v`, returns the old key of `v` and the old value of
+    /// the entry, which are removed from the container
+    pub fn insert(self, v: V) -> Result<(Option<K>, V), Error> {
+        let Self {
+            container,
+            key,
+            value,
+        } = self;
+
+        if v == value {
+            return Ok((None, value));
+        }
+
+        check_sizes::<K, V, KSIZE, VSIZE>(&key, &v)?;
+        let old_key = container.converse_relation.get(&v);
+
+        // The old value frees a node in the converse tree and the key is already in the direct
+        // one, so the insertions below do not need new nodes
+        container.converse_relation.delete(&value);
+        if let Some(ref old_key) = old_key {
+            container.direct_relation.delete(old_key);
+        }
+        // Both trees store the pair and `RBTree::insert` takes it by value, so one copy of the
+        // key and the value is unavoidable
+        container
+            .direct_relation
+            .insert(key.clone(), v.clone())
+            .expect(CHECKED);
+        container.converse_relation.insert(v, key).expect(CHECKED);
+
+        debug_assert_eq!(
+            container.direct_relation.free_nodes_left(),
+            container.converse_relation.free_nodes_left()
+        );
+
+        Ok((old_key, value))
+    }
+
+    /// Removes the entry from the container, returns its value
+    pub fn remove(self) -> V {
+        self.container.direct_relation.delete(&self.key);
+        self.container.converse_relation.delete(&self.value);
+
+        debug_assert_eq!(
+            self.container.direct_relation.free_nodes_left(),
+            self.container.converse_relation.free_nodes_left()
+        );
+
+        self.value
+    }
+}
+
+/// A vacant entry of [`OneToOne`]
+pub struct VacantEntry<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    container: &'b mut OneToOne<'a, K, V, KSIZE, VSIZE>,
+    key: K,
+}
+
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> VacantEntry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + Clone,
+{
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Inserts the value `v` for the key of the entry, returns the old key of `v`, which is
+    /// removed from the container
+    pub fn insert(self, v: V) -> Result<Option<K>, Error> {
+        let Self { container, key } = self;
+
+        check_sizes::<K, V, KSIZE, VSIZE>(&key, &v)?;
+        let old_key = container.converse_relation.get(&v);
+
+        // The pair takes a new node in both trees, unless `v` had a key, which node is reused
+        if let Some(ref old_key) = old_key {
+            container.direct_relation.delete(old_key);
+        } else if container.free_nodes_left() == 0 {
+            return Err(Error::NoNodesLeft);
+        }
+        // See `OccupiedEntry::insert` for the copies of the pair
+        container
+            .direct_relation
+            .insert(key.clone(), v.clone())
+            .expect(CHECKED);
+        container.converse_relation.insert(v, key).expect(CHECKED);
+
+        debug_assert_eq!(
+            container.direct_relation.free_nodes_left(),
+            container.converse_relation.free_nodes_left()
+        );
+
+        Ok(old_key)
+    }
 }
 
 impl<'a, K, V, const KSIZE: usize, const VSIZE: usize> fmt::Debug
@@ -280,6 +708,45 @@ where
     }
 }
 
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> fmt::Debug
+    for Entry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> fmt::Debug
+    for OccupiedEntry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<'b, 'a, K, V, const KSIZE: usize, const VSIZE: usize> fmt::Debug
+    for VacantEntry<'b, 'a, K, V, KSIZE, VSIZE>
+where
+    K: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+    V: Ord + BorshDeserialize + BorshSerialize + fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -546,4 +1013,319 @@ mod tests {
 
         assert!(container.is_empty());
     }
+
+    #[test]
+    fn verify_and_repair() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        for (k, v) in [(1, 6), (2, 7), (3, 8), (4, 9)] {
+            assert_eq!(container.insert(k, v).unwrap(), (None, None));
+        }
+
+        assert_eq!(container.verify(), vec![]);
+
+        // Corrupt the trees directly
+        container.direct_relation.insert(5, 6).unwrap();
+        container.converse_relation.delete(&8);
+        container.converse_relation.insert(10, 2).unwrap();
+
+        assert_eq!(
+            container.verify(),
+            vec![
+                Inconsistency::DanglingDirect { key: 3, value: 8 },
+                Inconsistency::MismatchedPair {
+                    key: 5,
+                    value: 6,
+                    converse_key: 1
+                },
+                Inconsistency::DanglingConverse { key: 2, value: 10 },
+                Inconsistency::FreeNodesMismatch {
+                    direct: 95,
+                    converse: 96
+                },
+            ]
+        );
+
+        assert_eq!(container.repair(), vec![(1, 6)]);
+
+        assert_eq!(container.verify(), vec![]);
+        assert_eq!(
+            container.iter().collect::<Vec<_>>(),
+            vec![(2, 7), (3, 8), (4, 9), (5, 6)]
+        );
+        assert_eq!(container.get_key(&6), Some(5));
+        assert_eq!(container.get_key(&10), None);
+    }
+
+    #[test]
+    fn first_and_last_by_value() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.first_by_value(), None);
+        assert_eq!(container.last_by_value(), None);
+
+        assert_eq!(container.insert(1, 8).unwrap(), (None, None));
+        assert_eq!(container.insert(2, 6).unwrap(), (None, None));
+        assert_eq!(container.insert(3, 10).unwrap(), (None, None));
+        assert_eq!(container.insert(4, 7).unwrap(), (None, None));
+
+        assert_eq!(container.first_by_value(), Some((2, 6)));
+        assert_eq!(container.last_by_value(), Some((3, 10)));
+    }
+
+    #[test]
+    fn first_and_last_by_key() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.first_by_key(), None);
+        assert_eq!(container.last_by_key(), None);
+
+        assert_eq!(container.insert(3, 8).unwrap(), (None, None));
+        assert_eq!(container.insert(1, 6).unwrap(), (None, None));
+        assert_eq!(container.insert(4, 10).unwrap(), (None, None));
+        assert_eq!(container.insert(2, 7).unwrap(), (None, None));
+
+        assert_eq!(container.first_by_key(), Some((1, 6)));
+        assert_eq!(container.last_by_key(), Some((4, 10)));
+    }
+
+    #[test]
+    fn iter_and_ranges() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        for (k, v) in [(1, 50), (2, 40), (3, 30), (4, 20), (5, 10)] {
+            assert_eq!(container.insert(k, v).unwrap(), (None, None));
+        }
+
+        assert_eq!(
+            container.iter().collect::<Vec<_>>(),
+            vec![(1, 50), (2, 40), (3, 30), (4, 20), (5, 10)]
+        );
+
+        assert_eq!(
+            container.range_by_key(2..4).collect::<Vec<_>>(),
+            vec![(2, 40), (3, 30)]
+        );
+        assert_eq!(
+            container.range_by_key(4..).collect::<Vec<_>>(),
+            vec![(4, 20), (5, 10)]
+        );
+        assert_eq!(
+            container.range_by_key(..=1).collect::<Vec<_>>(),
+            vec![(1, 50)]
+        );
+        assert_eq!(container.range_by_key(6..).count(), 0);
+
+        assert_eq!(
+            container.range_by_value(15..=30).collect::<Vec<_>>(),
+            vec![(4, 20), (3, 30)]
+        );
+        assert_eq!(
+            container.range_by_value(..).collect::<Vec<_>>(),
+            vec![(5, 10), (4, 20), (3, 30), (2, 40), (1, 50)]
+        );
+    }
+
+    #[test]
+    fn entry() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.insert(1, 6).unwrap(), (None, None));
+        assert_eq!(container.insert(2, 7).unwrap(), (None, None));
+
+        match container.entry(3) {
+            Entry::Vacant(entry) => assert_eq!(entry.insert(8).unwrap(), None),
+            Entry::Occupied(entry) => panic!("Unexpected {:?}", entry),
+        }
+
+        // Taking the value of the other key removes that key
+        match container.entry(3) {
+            Entry::Occupied(entry) => {
+                assert_eq!(entry.get(), &8);
+                assert_eq!(entry.insert(7).unwrap(), (Some(2), 8));
+            }
+            Entry::Vacant(entry) => panic!("Unexpected {:?}", entry),
+        }
+
+        assert_eq!(container.get_value(&2), None);
+        assert_eq!(container.get_key(&8), None);
+        assert_eq!(container.get_key(&7), Some(3));
+
+        match container.entry(1) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 6),
+            Entry::Vacant(entry) => panic!("Unexpected {:?}", entry),
+        }
+
+        assert_eq!(container.iter().collect::<Vec<_>>(), vec![(3, 7)]);
+        assert_eq!(container.entry(1).key(), &1);
+    }
+
+    #[test]
+    fn insert_existing_pair() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.insert(1, 6).unwrap(), (None, None));
+        assert_eq!(container.insert(1, 6).unwrap(), (None, Some(6)));
+
+        assert_eq!(container.get_value(&1), Some(6));
+        assert_eq!(container.get_key(&6), Some(1));
+        assert_eq!(container.len(), 1);
+    }
+
+    #[test]
+    fn insert_in_full_container() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 2)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.insert(1, 6).unwrap(), (None, None));
+        assert_eq!(container.insert(2, 7).unwrap(), (None, None));
+        assert_eq!(container.free_nodes_left(), 0);
+
+        assert_eq!(container.insert(3, 8), Err(Error::NoNodesLeft));
+
+        // Replacements do not need new nodes
+        assert_eq!(container.insert(3, 7).unwrap(), (Some(2), None));
+        assert_eq!(container.insert(1, 8).unwrap(), (None, Some(6)));
+
+        assert_eq!(container.iter().collect::<Vec<_>>(), vec![(1, 8), (3, 7)]);
+    }
+
+    #[test]
+    fn try_insert_unique() {
+        let mut slice = vec![0; one_to_one_size(4, 4, 100)];
+
+        let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+
+        assert_eq!(container.try_insert_unique(1, 6), Ok(None));
+        assert_eq!(container.try_insert_unique(2, 7), Ok(None));
+        assert_eq!(container.try_insert_unique(1, 6), Ok(Some(6)));
+        assert_eq!(container.try_insert_unique(1, 8), Ok(Some(6)));
+
+        assert_eq!(
+            container.try_insert_unique(3, 7),
+            Err(TryInsertError::ValueOccupied(2))
+        );
+        assert_eq!(
+            container.try_insert_unique(1, 7),
+            Err(TryInsertError::ValueOccupied(2))
+        );
+
+        assert_eq!(container.iter().collect::<Vec<_>>(), vec![(1, 8), (2, 7)]);
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+        use std::collections::BTreeMap;
+
+        const MAX_NODES: usize = 8;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Insert(u32, u32),
+            TryInsertUnique(u32, u32),
+            RemoveByKey(u32),
+            RemoveByValue(u32),
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            // Small domains make collisions of keys and values frequent
+            prop_oneof![
+                (0..16_u32, 0..16_u32).prop_map(|(k, v)| Op::Insert(k, v)),
+                (0..16_u32, 0..16_u32).prop_map(|(k, v)| Op::TryInsertUnique(k, v)),
+                (0..16_u32).prop_map(Op::RemoveByKey),
+                (0..16_u32).prop_map(Op::RemoveByValue),
+            ]
+        }
+
+        /// Expected result of [`OneToOne::insert`], applied to the `model`
+        fn model_insert(
+            model: &mut BTreeMap<u32, u32>,
+            k: u32,
+            v: u32,
+        ) -> Result<(Option<u32>, Option<u32>), Error> {
+            let old_key = model.iter().find(|(_, &val)| val == v).map(|(&key, _)| key);
+            let old_val = model.get(&k).copied();
+
+            if old_val == Some(v) {
+                return Ok((None, old_val));
+            }
+            if old_key.is_none() && old_val.is_none() && model.len() == MAX_NODES {
+                return Err(Error::NoNodesLeft);
+            }
+
+            if let Some(old_key) = old_key {
+                model.remove(&old_key);
+            }
+            model.insert(k, v);
+
+            Ok((old_key, old_val))
+        }
+
+        proptest! {
+            #[test]
+            fn trees_mirror_each_other(ops in prop::collection::vec(op(), 1..200)) {
+                let mut slice = vec![0; one_to_one_size(4, 4, MAX_NODES)];
+                let mut container = OneToOne::<u32, u32, 4, 4>::init_slice(&mut slice).unwrap();
+                let mut model = BTreeMap::new();
+
+                for op in ops {
+                    match op {
+                        Op::Insert(k, v) => {
+                            let expected = model_insert(&mut model, k, v);
+                            prop_assert_eq!(container.insert(k, v), expected);
+                        }
+                        Op::TryInsertUnique(k, v) => {
+                            let owner = model.iter().find(|(_, &val)| val == v).map(|(&key, _)| key);
+                            let expected = match owner {
+                                Some(owner) if owner != k => Err(TryInsertError::ValueOccupied(owner)),
+                                _ => model_insert(&mut model, k, v)
+                                    .map(|(_, old_val)| old_val)
+                                    .map_err(TryInsertError::Tree),
+                            };
+                            prop_assert_eq!(container.try_insert_unique(k, v), expected);
+                        }
+                        Op::RemoveByKey(k) => {
+                            prop_assert_eq!(container.remove_by_key(&k), model.remove(&k));
+                        }
+                        Op::RemoveByValue(v) => {
+                            let owner = model.iter().find(|(_, &val)| val == v).map(|(&key, _)| key);
+                            if let Some(owner) = owner {
+                                model.remove(&owner);
+                            }
+                            prop_assert_eq!(container.remove_by_value(&v), owner);
+                        }
+                    }
+
+                    let direct: Vec<(u32, u32)> = container.direct_relation.pairs().collect();
+                    let mut converse: Vec<(u32, u32)> = container
+                        .converse_relation
+                        .pairs()
+                        .map(|(v, k)| (k, v))
+                        .collect();
+                    converse.sort_unstable();
+
+                    prop_assert_eq!(&direct, &converse);
+                    prop_assert_eq!(&direct, &model.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>());
+                    prop_assert_eq!(
+                        container.direct_relation.free_nodes_left(),
+                        container.converse_relation.free_nodes_left()
+                    );
+                }
+            }
+        }
+    }
 }